@@ -0,0 +1,74 @@
+//! A Language Server Protocol server that publishes ck3-tiger reports as diagnostics.
+//! It talks to the editor over stdin and stdout.
+
+use std::io::{stdin, stdout};
+use std::mem::forget;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use tiger_lib::{find_game_directory_steam, validate_config_file, Game, LspServer, ModFile};
+
+/// Steam's code for Crusader Kings 3
+const CK3_APP_ID: &str = "1158310";
+
+/// CK3 directory under steam library dir
+const CK3_DIR: &str = "steamapps/common/Crusader Kings III";
+
+/// A file that should be present if this is the CK3 directory
+const CK3_SIGNATURE_FILE: &str = "game/events/witch_events.txt";
+
+#[derive(Parser)]
+struct Cli {
+    /// Path to .mod file of mod to check. If not given, the editor's workspace is checked.
+    modpath: Option<PathBuf>,
+    /// Path to CK3 main directory.
+    #[clap(long)]
+    ck3: Option<PathBuf>,
+    /// Path to custom .conf file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let mut args = Cli::parse();
+
+    Game::set(Game::Ck3)?;
+
+    if args.ck3.is_none() {
+        args.ck3 = find_game_directory_steam(CK3_APP_ID, &PathBuf::from(CK3_DIR));
+    }
+    if let Some(ref mut ck3) = args.ck3 {
+        eprintln!("Using CK3 directory: {}", ck3.display());
+        if !ck3.join(CK3_SIGNATURE_FILE).is_file() {
+            ck3.push("..");
+            if !ck3.join(CK3_SIGNATURE_FILE).is_file() {
+                bail!("Cannot find CK3 directory. Please supply it as the --ck3 option.");
+            }
+        }
+    } else {
+        bail!("Cannot find CK3 directory. Please supply it as the --ck3 option.");
+    }
+
+    args.config = validate_config_file(args.config);
+
+    let mut mod_root = None;
+    let mut replace_paths = Vec::new();
+    if let Some(mut modpath) = args.modpath {
+        if modpath.is_dir() {
+            modpath.push("descriptor.mod");
+        }
+        let modfile = ModFile::read(&modpath)?;
+        mod_root = Some(modfile.modpath());
+        replace_paths = modfile.replace_paths();
+    }
+
+    let mut server = LspServer::new(args.config, args.ck3, mod_root, replace_paths);
+    server.run(stdin().lock(), stdout().lock())?;
+
+    // Properly dropping the server takes a noticeable amount of time, and we're exiting anyway.
+    forget(server);
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use crate::block::Block;
+use crate::ck3::data::characters::{adult_age, Character, Gender};
+use crate::ck3::history::HistoryState;
 use crate::ck3::validate::validate_portrait_modifier_overrides;
 use crate::context::ScopeContext;
 use crate::data::dna::validate_genes;
@@ -94,6 +96,15 @@ fn validate_bookmark_character(
         vd.field_value("name");
     } else {
         vd.field_item("name", Item::Localization);
+        // The portrait is looked up by the character's name
+        if let Some(name) = block.get_field_value("name") {
+            data.verify_exists(Item::BookmarkPortrait, name);
+            if let Some((_, portrait)) =
+                data.database.get_key_block(Item::BookmarkPortrait, name.as_str())
+            {
+                validate_portrait_against_bookmark(block, portrait, data);
+            }
+        }
     }
     if toplevel {
         if let Some(token) = block.get_field_value("name") {
@@ -169,6 +180,108 @@ fn validate_bookmark_character(
     });
 }
 
+/// Check the characters of a bookmark, and the characters nested in them, against the history on
+/// the bookmark's start date.
+pub fn check_bookmark_characters(block: &Block, history: &HistoryState, data: &Everything) {
+    for block in block.get_field_blocks("character") {
+        if let Some(id) = block.get_field_value("history_id") {
+            if data.item_exists(Item::Character, id.as_str()) {
+                validate_bookmark_character_alive(block, id, history, data);
+            }
+        }
+        check_bookmark_characters(block, history, data);
+    }
+}
+
+/// Check the bookmark character against the history on the bookmark date: the character must be
+/// alive, must hold the title they are shown with, and must have the age and gender of their
+/// portrait type.
+fn validate_bookmark_character_alive(
+    block: &Block,
+    id: &Token,
+    history: &HistoryState,
+    data: &Everything,
+) {
+    let date = history.date();
+    let name = block.get_field_value("name");
+    if !history.is_alive(id) {
+        let msg = format!("character {id} is not alive on {date}");
+        warn(ErrorKey::Bookmarks).strong().msg(msg).loc(id).opt_loc_msg(name, "character").push();
+        return;
+    }
+
+    if let Some(title) = block.get_field_value("title") {
+        let holder = history.holder(title.as_str());
+        if holder != Some(id) {
+            let holder = holder.map_or_else(|| "no one".to_string(), ToString::to_string);
+            let msg = format!("{title} is held by {holder} in history at {date}, not by {id}");
+            warn(ErrorKey::Bookmarks)
+                .strong()
+                .msg(msg)
+                .loc(title)
+                .opt_loc_msg(name, "character")
+                .push();
+        }
+    }
+
+    let Some(character) = data.characters.get(id.as_str()) else { return };
+    if let Some(kind) = block.get_field_value("type") {
+        let is_female = kind.is("female") || kind.is("girl");
+        if is_female != (character.gender() == Gender::Female) {
+            let gender = character.gender();
+            let msg = format!("character type is {kind} but {id} is {gender} in history");
+            warn(ErrorKey::Bookmarks).msg(msg).loc(kind).push();
+        }
+        if let Some(age) = character.age_at(date) {
+            let is_child = kind.is("boy") || kind.is("girl");
+            if is_child != (age < adult_age(data)) {
+                let msg = format!("character type is {kind} but {id} is {age} years old at {date}");
+                warn(ErrorKey::Bookmarks).msg(msg).loc(kind).push();
+            }
+        }
+    }
+}
+
+/// Check that the saved portrait matches the character it is shown for.
+fn validate_portrait_against_bookmark(block: &Block, portrait: &Block, data: &Everything) {
+    if let Some(kind) = block.get_field_value("type") {
+        if let Some(portrait_kind) = portrait.get_field_value("type") {
+            if kind != portrait_kind {
+                let msg =
+                    format!("character type is {kind} but their portrait is for a {portrait_kind}");
+                warn(ErrorKey::Bookmarks)
+                    .msg(msg)
+                    .loc(kind)
+                    .loc_msg(portrait_kind, "portrait")
+                    .push();
+            }
+        }
+    }
+
+    // If the history character has fixed DNA, the portrait should show the same face.
+    let Some(genes) = portrait.get_field_block("genes") else { return };
+    let Some(id) = block.get_field_value("history_id") else { return };
+    let Some(dna) = data.characters.get(id.as_str()).and_then(Character::dna) else { return };
+    let Some((_, dna_block)) = data.database.get_key_block(Item::Dna, dna.as_str()) else {
+        return;
+    };
+    if let Some(dna_genes) =
+        dna_block.get_field_block("portrait_info").and_then(|b| b.get_field_block("genes"))
+    {
+        if !genes.equivalent(dna_genes) {
+            let msg = format!("bookmark portrait genes differ from the genes of {id}'s dna {dna}");
+            let info = "the portrait will not look like the character in game";
+            warn(ErrorKey::Bookmarks)
+                .weak()
+                .msg(msg)
+                .info(info)
+                .loc(genes)
+                .loc_msg(dna_genes, "dna")
+                .push();
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BookmarkPortrait {}
 
@@ -26,6 +26,19 @@ use crate::tooltipped::Tooltipped;
 use crate::validate::validate_color;
 use crate::validator::Validator;
 
+/// The vanilla age of majority, for when the defines don't set it.
+const DEFAULT_ADULT_AGE: i16 = 16;
+
+/// Return the age at which characters become adults, as set by `NCharacter|ADULT_AGE`.
+pub fn adult_age(data: &Everything) -> i16 {
+    data.get_defined_string("NCharacter|ADULT_AGE")
+        .and_then(|token| token.as_str().parse().ok())
+        .unwrap_or(DEFAULT_ADULT_AGE)
+}
+
+/// The youngest age at which a character can be a mother.
+const MIN_MOTHER_AGE: i16 = 12;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Gender {
     Male,
@@ -113,6 +126,15 @@ impl Characters {
         self.characters.values().map(|ch| &ch.key).chain(self.duplicates.iter().map(|ch| &ch.key))
     }
 
+    /// Iterate over the characters, leaving out the ones with duplicate ids.
+    pub fn iter(&self) -> impl Iterator<Item = &Character> {
+        self.characters.values()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Character> {
+        self.characters.get(key)
+    }
+
     pub fn is_alive(&self, item: &Token, date: Date) -> bool {
         if let Some(item) = self.characters.get(item.as_str()) {
             item.is_alive(date)
@@ -296,6 +318,55 @@ impl Character {
         }
     }
 
+    pub fn key(&self) -> &Token {
+        &self.key
+    }
+
+    pub fn dna(&self) -> Option<&Token> {
+        self.block.get_field_value("dna")
+    }
+
+    /// Return the date of birth, if it is in the history.
+    pub fn birth_date(&self) -> Option<Date> {
+        for (key, block) in self.block.iter_definitions() {
+            if let Ok(date) = Date::try_from(key) {
+                if block.has_key("birth") {
+                    return Some(date);
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the date of death, if it is in the history.
+    pub fn death_date(&self) -> Option<Date> {
+        for (key, block) in self.block.iter_definitions() {
+            if let Ok(date) = Date::try_from(key) {
+                if block.has_key("death") {
+                    return Some(date);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn name(&self) -> Option<&Token> {
+        self.block.get_field_value("name")
+    }
+
+    pub fn father(&self) -> Option<&Token> {
+        self.block.get_field_value("father")
+    }
+
+    pub fn mother(&self) -> Option<&Token> {
+        self.block.get_field_value("mother")
+    }
+
+    /// Return the age in whole years on `date`, if the date of birth is known.
+    pub fn age_at(&self, date: Date) -> Option<i16> {
+        self.birth_date().map(|birth| date.years_since(birth))
+    }
+
     pub fn gender(&self) -> Gender {
         Gender::from_female_bool(self.block.get_field_bool("female").unwrap_or(false))
     }
@@ -310,6 +381,30 @@ impl Character {
         self.block.get_field_value_at_date("dynasty", date)
     }
 
+    /// Return the marriages and divorces in this character's history up to and including `date`,
+    /// in the order they happen. Each is the date, the spouse, and whether it's a marriage.
+    pub fn spouse_changes(&self, date: Date) -> Vec<(Date, &Token, bool)> {
+        let mut changes = Vec::new();
+        for (key, block) in self.block.iter_definitions() {
+            let Ok(change_date) = Date::try_from(key) else { continue };
+            if change_date > date {
+                continue;
+            }
+            for (key, token) in block.iter_assignments() {
+                match key.as_str() {
+                    "add_spouse" | "add_matrilineal_spouse" | "add_same_sex_spouse" => {
+                        changes.push((change_date, token, true));
+                    }
+                    "remove_spouse" => changes.push((change_date, token, false)),
+                    _ => (),
+                }
+            }
+        }
+        // Stable sort, to keep the order within one date
+        changes.sort_by_key(|(date, _, _)| *date);
+        changes
+    }
+
     pub fn get_house(&self, date: Date) -> Option<&Token> {
         self.block.get_field_value_at_date("dynasty_house", date)
     }
@@ -324,6 +419,39 @@ impl Character {
             .or_else(|| self.block.get_field_value_at_date("religion", date))
     }
 
+    /// Check that the parents could have had this child: they were born before it, and the
+    /// mother was old enough and still alive.
+    fn validate_parents(&self, data: &Everything) {
+        let Some(birth) = self.birth_date() else { return };
+        let key = &self.key;
+        for (relation, parent) in [("father", self.father()), ("mother", self.mother())] {
+            let Some(parent) = parent else { continue };
+            let Some(ch) = data.characters.get(parent.as_str()) else { continue };
+            let Some(parent_birth) = ch.birth_date() else { continue };
+            if parent_birth >= birth {
+                let msg = format!(
+                    "{relation} {parent} was born on {parent_birth}, not before {key} on {birth}"
+                );
+                warn(ErrorKey::History).msg(msg).loc(parent).loc_msg(&ch.key, relation).push();
+                continue;
+            }
+            if relation == "mother" {
+                let age = birth.years_since(parent_birth);
+                if age < MIN_MOTHER_AGE {
+                    let msg =
+                        format!("mother {parent} is only {age} when {key} is born on {birth}");
+                    warn(ErrorKey::History).msg(msg).loc(parent).loc_msg(&ch.key, relation).push();
+                }
+                if let Some(death) = ch.death_date().filter(|&death| death < birth) {
+                    let msg = format!(
+                        "mother {parent} died on {death}, before {key} was born on {birth}"
+                    );
+                    warn(ErrorKey::History).msg(msg).loc(parent).loc_msg(&ch.key, relation).push();
+                }
+            }
+        }
+    }
+
     fn validate_life_event(
         date: Date,
         gender: Gender,
@@ -585,6 +713,8 @@ impl Character {
             data.characters.verify_exists_gender(ch, Gender::Female);
         }
 
+        self.validate_parents(data);
+
         vd.field_bool("disallow_random_traits");
 
         // religion and faith both mean faith here
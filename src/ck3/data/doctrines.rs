@@ -4,6 +4,7 @@ use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::Block;
 use crate::ck3::validate::validate_traits;
+use crate::conflicts::record_item_override;
 use crate::context::ScopeContext;
 use crate::desc::validate_desc;
 use crate::everything::Everything;
@@ -29,6 +30,7 @@ pub struct Doctrines {
 impl Doctrines {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.categories.get(key.as_str()) {
+            record_item_override("doctrine category", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "doctrine category");
             }
@@ -41,6 +43,7 @@ impl Doctrines {
             }
 
             if let Some(other) = self.doctrines.get(doctrine.as_str()) {
+                record_item_override("doctrine", doctrine, &other.key);
                 if other.key.loc.kind >= doctrine.loc.kind {
                     dup_error(doctrine, &other.key, "doctrine");
                 }
@@ -118,6 +118,15 @@ impl Ck3Events {
         self.events.values().map(|item| &item.key)
     }
 
+    pub fn iter_key_block(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.events.values().map(|item| (&item.key, &item.block))
+    }
+
+    /// Iterate over the scripted effects that are defined in the event files.
+    pub fn iter_effects(&self) -> impl Iterator<Item = &Effect> {
+        self.effects.values()
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
             item.validate(data);
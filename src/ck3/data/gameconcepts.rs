@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
+use crate::conflicts::record_item_override;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::dup_error;
@@ -21,6 +22,7 @@ pub struct GameConcepts {
 impl GameConcepts {
     pub fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.concepts.get(key.as_str()) {
+            record_item_override("game concept", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "game concept");
             }
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use fnv::FnvHashMap;
 
@@ -68,13 +68,14 @@ impl FileHandler<Block> for Musics {
         PathBuf::from("")
     }
 
+    fn wants_path(&self, path: &Path) -> bool {
+        (path.starts_with("music")
+            || path.starts_with("dlc") && path.parent().is_some_and(|dir| dir.ends_with("music")))
+            && path.extension().is_some_and(|ext| ext == "txt")
+    }
+
     fn load_file(&self, entry: &FileEntry) -> Option<Block> {
-        if !(entry.path().starts_with("music")
-            || entry.path().starts_with("dlc") && entry.path().parent().unwrap().ends_with("music"))
-        {
-            return None;
-        }
-        if !entry.filename().to_string_lossy().ends_with(".txt") {
+        if !self.wants_path(entry.path()) {
             return None;
         }
 
@@ -3,7 +3,6 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::{Block, BV};
-use crate::ck3::data::provinces::ProvId;
 use crate::ck3::data::titles::Titles;
 use crate::date::Date;
 use crate::effect::validate_effect;
@@ -12,6 +11,7 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::dup_error;
 use crate::item::Item;
 use crate::pdxfile::PdxFile;
+use crate::province_map::ProvId;
 use crate::report::{warn, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
@@ -37,6 +37,10 @@ impl ProvinceHistories {
         }
     }
 
+    pub fn get(&self, provid: ProvId) -> Option<&ProvinceHistory> {
+        self.provinces.get(&provid)
+    }
+
     pub fn validate(&self, data: &Everything) {
         for (provid, item) in &self.provinces {
             item.validate(*provid, data);
@@ -110,6 +114,21 @@ impl ProvinceHistory {
         Self { key, block }
     }
 
+    pub fn key(&self) -> &Token {
+        &self.key
+    }
+
+    pub fn culture_at(&self, date: Date) -> Option<&Token> {
+        self.block.get_field_value_at_date("culture", date)
+    }
+
+    /// `religion` and `faith` both mean faith here.
+    pub fn faith_at(&self, date: Date) -> Option<&Token> {
+        self.block
+            .get_field_value_at_date("faith", date)
+            .or_else(|| self.block.get_field_value_at_date("religion", date))
+    }
+
     fn validate_common(vd: &mut Validator, data: &Everything) {
         vd.field_item("culture", Item::Culture);
         vd.field_item("religion", Item::Faith);
@@ -138,8 +157,6 @@ impl ProvinceHistory {
 
     fn validate(&self, provid: ProvId, data: &Everything) {
         data.provinces_ck3.verify_exists_provid(provid, &self.key, Severity::Error);
-        // TODO: verify that all county-capital provinces have a culture and religion
-        // This needs province mappings to be loaded too
         let mut vd = Validator::new(&self.block, data);
         Self::validate_common(&mut vd, data);
         vd.field_value("terrain"); // TODO: this does not seem to be an Item::Terrain
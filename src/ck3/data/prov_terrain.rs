@@ -13,7 +13,7 @@ use crate::token::{Loc, Token};
 use crate::validator::Validator;
 use crate::Severity;
 
-use super::provinces::ProvId;
+use crate::province_map::ProvId;
 
 const DEFAULT_TERRAINS: &[&str] = &["default_land", "default_sea", "default_coastal_sea"];
 
@@ -47,6 +47,14 @@ impl TitleHistories {
         self.histories.values().map(|item| &item.key)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &TitleHistory> {
+        self.histories.values()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&TitleHistory> {
+        self.histories.get(key)
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.histories.values() {
             item.validate(data);
@@ -100,6 +108,24 @@ impl TitleHistory {
         Self { key, block, tier }
     }
 
+    pub fn key(&self) -> &Token {
+        &self.key
+    }
+
+    pub fn tier(&self) -> Tier {
+        self.tier
+    }
+
+    /// Return the holder of the title on `date`, or `None` if it has no holder then.
+    pub fn holder_at(&self, date: Date) -> Option<&Token> {
+        self.block.get_field_value_at_date("holder", date).filter(|holder| !holder.is("0"))
+    }
+
+    /// Return the liege title of the title on `date`, or `None` if it's independent then.
+    pub fn liege_at(&self, date: Date) -> Option<&Token> {
+        self.block.get_field_value_at_date("liege", date).filter(|liege| !liege.is("0"))
+    }
+
     pub fn verify_has_holder(&self, token: &Token, date: Date, data: &Everything) {
         let info = "setting the liege will not have effect here";
 
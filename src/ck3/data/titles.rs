@@ -1,17 +1,21 @@
-use std::fmt::{Display, Formatter};
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter, Write as _};
+use std::fs::write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use anyhow::{Context, Result};
 use fnv::FnvHashMap;
+use serde_json::json;
 
 use crate::block::Block;
-use crate::ck3::data::provinces::ProvId;
+use crate::conflicts::record_item_override;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::dup_error;
 use crate::item::Item;
 use crate::pdxfile::PdxFile;
+use crate::province_map::ProvId;
 use crate::report::{err, warn, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
@@ -83,6 +87,7 @@ impl Titles {
         is_county_capital: bool,
     ) {
         if let Some(other) = self.titles.get(key.as_str()) {
+            record_item_override("title", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "title");
             }
@@ -138,11 +143,187 @@ impl Titles {
         for item in self.titles.values() {
             item.validate(data);
         }
+        self.validate_hierarchy();
+    }
+
+    /// Return the de jure children of each title, in the order they are defined.
+    fn children(&self) -> FnvHashMap<&str, Vec<&Title>> {
+        let mut children: FnvHashMap<&str, Vec<&Title>> = FnvHashMap::default();
+        for title in self.titles.values() {
+            if let Some(parent) = title.parent {
+                children.entry(parent).or_default().push(title);
+            }
+        }
+        for list in children.values_mut() {
+            list.sort_unstable_by_key(|title| title.key.loc);
+        }
+        children
+    }
+
+    /// Return true iff `title` is `ancestor` or is de jure inside it.
+    fn is_de_jure_part_of(&self, title: &str, ancestor: &str) -> bool {
+        let mut title = Some(title);
+        while let Some(key) = title {
+            if key == ancestor {
+                return true;
+            }
+            title = self.titles.get(key).and_then(|title| title.parent);
+        }
+        false
+    }
+
+    /// Check the de jure hierarchy as a whole, rather than each title on its own.
+    fn validate_hierarchy(&self) {
+        let children = self.children();
+        let mut titles: Vec<&Title> = self.titles.values().map(AsRef::as_ref).collect();
+        titles.sort_unstable_by_key(|title| title.key.loc);
+
+        let mut provinces: FnvHashMap<&str, &Token> = FnvHashMap::default();
+        for title in titles {
+            let key = &title.key;
+            let has_children = children.contains_key(key.as_str());
+            let parent_tier =
+                title.parent.and_then(|parent| self.titles.get(parent)).map(|p| p.tier);
+
+            // Titular duchies don't need a kingdom, but duchies with land do.
+            let needed = match title.tier {
+                Tier::County => Some(Tier::Duchy),
+                Tier::Duchy if has_children => Some(Tier::Kingdom),
+                _ => None,
+            };
+            if let Some(needed) = needed {
+                if parent_tier != Some(needed) {
+                    let msg = format!("{} {key} is not part of any {needed}", title.tier);
+                    warn(ErrorKey::TitleTier).msg(msg).loc(key).push();
+                }
+            }
+
+            if title.block.field_value_is("landless", "yes") && has_children {
+                let msg = format!("landless title {key} has de jure titles inside it");
+                let info = "landless titles can't have de jure territory";
+                warn(ErrorKey::TitleTier).msg(msg).info(info).loc(key).push();
+            }
+
+            // Titular titles can have their capital anywhere.
+            if let Some(capital) = title.block.get_field_value("capital") {
+                if has_children
+                    && self.exists(capital.as_str())
+                    && !self.is_de_jure_part_of(capital.as_str(), key.as_str())
+                {
+                    let msg = format!("capital {capital} is not de jure part of {key}");
+                    warn(ErrorKey::TitleTier).msg(msg).loc(capital).push();
+                }
+            }
+
+            if title.tier == Tier::Barony {
+                if let Some(province) = title.block.get_field_value("province") {
+                    if let Some(other) = provinces.insert(province.as_str(), key) {
+                        let msg = format!("province {province} is in both {other} and {key}");
+                        err(ErrorKey::Conflict)
+                            .msg(msg)
+                            .loc(province)
+                            .loc_msg(other, "other barony")
+                            .push();
+                    }
+                }
+            }
+        }
     }
 
     pub fn capital_of(&self, prov: ProvId) -> Option<&str> {
         self.baronies.get(&prov).and_then(|b| b.capital_of())
     }
+
+    /// Iterate over the capital provinces of all counties, together with their barony and county.
+    pub fn iter_county_capitals(&self) -> impl Iterator<Item = (ProvId, &Token, &str)> {
+        self.baronies
+            .iter()
+            .filter_map(|(provid, barony)| Some((*provid, &barony.key, barony.capital_of()?)))
+    }
+
+    /// Return the titles in depth-first order of the de jure hierarchy, with their depth.
+    fn iter_hierarchy(&self) -> Vec<(usize, &Title)> {
+        let children = self.children();
+        let mut roots: Vec<&Title> =
+            self.titles.values().filter(|t| t.parent.is_none()).map(AsRef::as_ref).collect();
+        roots.sort_unstable_by_key(|title| title.key.loc);
+
+        let mut result = Vec::new();
+        let mut stack: Vec<(usize, &Title)> = roots.into_iter().rev().map(|t| (0, t)).collect();
+        while let Some((depth, title)) = stack.pop() {
+            result.push((depth, title));
+            if let Some(list) = children.get(title.key.as_str()) {
+                stack.extend(list.iter().rev().map(|t| (depth + 1, *t)));
+            }
+        }
+        result
+    }
+
+    fn hierarchy_to_csv(&self) -> String {
+        let mut csv = String::from("title,tier,parent,capital,province,landless\n");
+        for (_, title) in self.iter_hierarchy() {
+            let field = |name| title.block.get_field_value(name).map_or("", Token::as_str);
+            _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                title.key,
+                title.tier,
+                title.parent.unwrap_or(""),
+                field("capital"),
+                field("province"),
+                title.block.field_value_is("landless", "yes"),
+            );
+        }
+        csv
+    }
+
+    fn hierarchy_to_json(&self) -> serde_json::Value {
+        // Build the nested tree bottom-up: the depth-first order guarantees that a title's
+        // children directly follow it.
+        let mut stack: Vec<(usize, serde_json::Value)> = Vec::new();
+        let mut roots = Vec::new();
+        let finish = |stack: &mut Vec<(usize, serde_json::Value)>, roots: &mut Vec<_>| {
+            let (depth, value) = stack.pop().unwrap();
+            if depth == 0 {
+                roots.push(value);
+            } else if let Some((_, parent)) = stack.last_mut() {
+                parent["children"].as_array_mut().unwrap().push(value);
+            }
+        };
+        for (depth, title) in self.iter_hierarchy() {
+            while stack.last().is_some_and(|(d, _)| *d >= depth) {
+                finish(&mut stack, &mut roots);
+            }
+            let field = |name| title.block.get_field_value(name).map(Token::as_str);
+            let value = json!({
+                "title": title.key.as_str(),
+                "tier": title.tier.to_string(),
+                "capital": field("capital"),
+                "province": field("province"),
+                "landless": title.block.field_value_is("landless", "yes"),
+                "path": title.key.loc.pathname(),
+                "linenr": title.key.loc.line,
+                "children": [],
+            });
+            stack.push((depth, value));
+        }
+        while !stack.is_empty() {
+            finish(&mut stack, &mut roots);
+        }
+        serde_json::Value::Array(roots)
+    }
+
+    /// Write the de jure hierarchy to `path`. It's written as a CSV table with one title per line
+    /// if the filename ends in `.csv`, and as nested JSON otherwise.
+    pub fn write_hierarchy(&self, path: &Path) -> Result<()> {
+        let is_csv = path.extension().is_some_and(|ext| ext == "csv");
+        let output = if is_csv {
+            self.hierarchy_to_csv()
+        } else {
+            serde_json::to_string_pretty(&self.hierarchy_to_json())?
+        };
+        write(path, output).with_context(|| format!("could not write {}", path.display()))
+    }
 }
 
 impl FileHandler<Block> for Titles {
@@ -237,7 +418,7 @@ impl Title {
         vd.field_list_items("female_names", Item::Localization);
 
         if Tier::try_from(&self.key) == Ok(Tier::Barony) {
-            // TODO: check that no two baronies have the same province
+            // Titles::validate_hierarchy checks that no two baronies have the same province
             vd.field_item("province", Item::Province);
         }
 
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::{Block, BV};
+use crate::conflicts::record_item_override;
 use crate::context::ScopeContext;
 use crate::desc::{validate_desc, validate_desc_map};
 use crate::everything::Everything;
@@ -32,6 +33,7 @@ pub struct Traits {
 impl Traits {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.traits.get(key.as_str()) {
+            record_item_override("trait", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "trait");
             }
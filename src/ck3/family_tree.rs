@@ -0,0 +1,161 @@
+//! Export of the family trees of the characters in `history/characters/`.
+//!
+//! Each character is linked to their father and mother, and is shown with their dynasty, house,
+//! and dates of birth and death. The export can be limited to the members of one dynasty or
+//! house, in which case parents from outside it are left out.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use fnv::FnvHashSet;
+use serde_json::json;
+
+use crate::ck3::data::characters::{Character, Gender};
+use crate::date::Date;
+use crate::event_graph::{dot_escape, dot_quote};
+use crate::everything::Everything;
+use crate::item::Item;
+use crate::token::Token;
+
+#[derive(Debug)]
+struct Member<'a> {
+    character: &'a Character,
+    dynasty: Option<&'a Token>,
+    house: Option<&'a Token>,
+}
+
+#[derive(Debug)]
+pub(crate) struct FamilyTree<'a> {
+    members: Vec<Member<'a>>,
+}
+
+impl<'a> FamilyTree<'a> {
+    /// Collect the characters, or only those whose dynasty or house is `filter`.
+    pub(crate) fn new(data: &'a Everything, filter: Option<&str>) -> Result<Self> {
+        if let Some(filter) = filter {
+            if !data.item_exists(Item::Dynasty, filter) && !data.item_exists(Item::House, filter) {
+                bail!("{filter} is not a dynasty or a house");
+            }
+        }
+
+        let mut members = Vec::new();
+        for character in data.characters.iter() {
+            // Use the last dynasty and house the character has in their history.
+            let date = character.death_date().unwrap_or(Date::new(i16::MAX, 12, 31));
+            let key = character.key();
+            let dynasty = data.characters.get_dynasty(key, date, data);
+            let house = data.characters.get_house(key, date);
+            if let Some(filter) = filter {
+                if !dynasty.is_some_and(|t| t.is(filter)) && !house.is_some_and(|t| t.is(filter)) {
+                    continue;
+                }
+            }
+            members.push(Member { character, dynasty, house });
+        }
+        members.sort_unstable_by_key(|member| member.character.key().loc);
+        Ok(Self { members })
+    }
+
+    /// The links from parents to children, if both are in the tree.
+    fn links(&self) -> Vec<(&'a Token, &'a Token, &'static str)> {
+        let keys: FnvHashSet<&str> =
+            self.members.iter().map(|member| member.character.key().as_str()).collect();
+        let mut links = Vec::new();
+        for member in &self.members {
+            let ch = member.character;
+            for (relation, parent) in [("father", ch.father()), ("mother", ch.mother())] {
+                if let Some(parent) = parent.filter(|parent| keys.contains(parent.as_str())) {
+                    links.push((parent, ch.key(), relation));
+                }
+            }
+        }
+        links
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph family {\n");
+        // Group the characters of each house together.
+        let mut houses: BTreeMap<Option<&str>, Vec<&Member>> = BTreeMap::new();
+        for member in &self.members {
+            houses.entry(member.house.map(Token::as_str)).or_default().push(member);
+        }
+        for (house, members) in houses {
+            let indent = if let Some(house) = house {
+                _ = writeln!(dot, "  subgraph {} {{", dot_quote(&format!("cluster_{house}")));
+                _ = writeln!(dot, "    label={};", dot_quote(house));
+                "    "
+            } else {
+                "  "
+            };
+            for member in members {
+                let ch = member.character;
+                let name = ch.name().map_or("", Token::as_str);
+                let shape = if ch.gender() == Gender::Female { "ellipse" } else { "box" };
+                _ = writeln!(
+                    dot,
+                    "{indent}{} [label=\"{} ({})\\n{}\", shape={shape}];",
+                    dot_quote(ch.key().as_str()),
+                    dot_escape(name),
+                    dot_escape(ch.key().as_str()),
+                    lifespan(ch),
+                );
+            }
+            if house.is_some() {
+                dot.push_str("  }\n");
+            }
+        }
+        for (parent, child, relation) in self.links() {
+            let style = if relation == "mother" { " [style=dashed]" } else { "" };
+            _ = writeln!(
+                dot,
+                "  {} -> {}{style};",
+                dot_quote(parent.as_str()),
+                dot_quote(child.as_str())
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let characters: Vec<_> = self
+            .members
+            .iter()
+            .map(|member| {
+                let ch = member.character;
+                json!({
+                    "id": ch.key().as_str(),
+                    "name": ch.name().map(Token::as_str),
+                    "female": ch.gender() == Gender::Female,
+                    "dynasty": member.dynasty.map(Token::as_str),
+                    "house": member.house.map(Token::as_str),
+                    "birth": ch.birth_date().map(|date| date.to_string()),
+                    "death": ch.death_date().map(|date| date.to_string()),
+                    "father": ch.father().map(Token::as_str),
+                    "mother": ch.mother().map(Token::as_str),
+                    "path": ch.key().loc.pathname(),
+                    "linenr": ch.key().loc.line,
+                })
+            })
+            .collect();
+        json!({ "characters": characters })
+    }
+
+    /// Write the tree to `path`. It's written in Graphviz DOT format if the filename ends in
+    /// `.dot` or `.gv`, and as JSON otherwise.
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let is_dot = path.extension().is_some_and(|ext| ext == "dot" || ext == "gv");
+        let output =
+            if is_dot { self.to_dot() } else { serde_json::to_string_pretty(&self.to_json())? };
+        write(path, output).with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// The dates of birth and death, such as `800.1.1 - 860.1.1`.
+fn lifespan(ch: &Character) -> String {
+    let date = |date: Option<Date>| date.map(|date| date.to_string()).unwrap_or_default();
+    format!("{} - {}", date(ch.birth_date()), date(ch.death_date()))
+}
@@ -0,0 +1,222 @@
+//! Replaying the CK3 history files to a given date, to check that the world they describe makes
+//! sense on that date.
+//!
+//! The history validators check each entry on its own date. A title holder who was alive when
+//! they got the title may still have died before a later bookmark without anyone inheriting, and
+//! that only shows up when looking at the combined history on the bookmark date.
+
+use std::collections::BTreeMap;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::block::Block;
+use crate::ck3::data::bookmarks::check_bookmark_characters;
+use crate::ck3::data::characters::Gender;
+use crate::ck3::data::titles::Tier;
+use crate::date::Date;
+use crate::everything::Everything;
+use crate::item::Item;
+use crate::report::{err, warn, ErrorKey};
+use crate::token::Token;
+
+/// The state of the world on one date, according to the character, title, and province history.
+#[derive(Debug)]
+pub struct HistoryState<'a> {
+    data: &'a Everything,
+    date: Date,
+    /// The holder of each title that has one on this date.
+    holders: FnvHashMap<&'a str, &'a Token>,
+    /// The liege title of each title that has one on this date.
+    lieges: FnvHashMap<&'a str, &'a Token>,
+    /// For each married character, their spouses on this date. Both spouses are alive.
+    /// The tokens are the ones from the history entry that married them.
+    spouses: FnvHashMap<&'a str, Vec<&'a Token>>,
+}
+
+impl<'a> HistoryState<'a> {
+    pub fn new(data: &'a Everything, date: Date) -> Self {
+        let mut holders = FnvHashMap::default();
+        let mut lieges = FnvHashMap::default();
+        for history in data.title_history.iter() {
+            let key = history.key().as_str();
+            if let Some(holder) = history.holder_at(date) {
+                holders.insert(key, holder);
+            }
+            if let Some(liege) = history.liege_at(date) {
+                lieges.insert(key, liege);
+            }
+        }
+
+        // Marriages can be recorded in the history of either spouse, so collect them from both
+        // sides.
+        let mut spouses: FnvHashMap<&str, Vec<&Token>> = FnvHashMap::default();
+        for character in data.characters.iter() {
+            if !character.is_alive(date) {
+                continue;
+            }
+            let mut current: Vec<&Token> = Vec::new();
+            for (_, spouse, added) in character.spouse_changes(date) {
+                if added {
+                    current.push(spouse);
+                } else {
+                    current.retain(|token| token.as_str() != spouse.as_str());
+                }
+            }
+            for spouse in current {
+                if data.characters.is_alive(spouse, date) {
+                    let key = character.key();
+                    spouses.entry(key.as_str()).or_default().push(spouse);
+                    spouses.entry(spouse.as_str()).or_default().push(key);
+                }
+            }
+        }
+        for list in spouses.values_mut() {
+            list.sort_unstable_by_key(|token| token.loc);
+            let mut seen = FnvHashSet::default();
+            list.retain(|token| seen.insert(token.as_str()));
+        }
+
+        Self { data, date, holders, lieges, spouses }
+    }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    /// Return the holder of `title` on this date, if it has one.
+    pub fn holder(&self, title: &str) -> Option<&'a Token> {
+        self.holders.get(title).copied()
+    }
+
+    pub fn is_alive(&self, character: &Token) -> bool {
+        self.data.characters.is_alive(character, self.date)
+    }
+
+    /// Report everything that's wrong with the world on this date.
+    pub fn check(&self) {
+        self.check_holders();
+        self.check_lieges();
+        self.check_spouses();
+        self.check_counties();
+    }
+
+    fn check_holders(&self) {
+        let date = self.date;
+        for (title, holder) in &self.holders {
+            if self.data.characters.exists(holder.as_str()) && !self.is_alive(holder) {
+                let msg = format!("holder {holder} of {title} is not alive on {date}");
+                let info = "the title history needs a new holder before then";
+                err(ErrorKey::History).msg(msg).info(info).loc(*holder).push();
+            }
+        }
+    }
+
+    fn check_lieges(&self) {
+        let date = self.date;
+        for (title, liege) in &self.lieges {
+            // Lieges are only meaningful for titles that are held.
+            if !self.holders.contains_key(title) {
+                continue;
+            }
+            let Some(history) = self.data.title_history.get(liege.as_str()) else {
+                // Missing liege titles are reported by the title history validation.
+                continue;
+            };
+            if let Ok(tier) = Tier::try_from(*liege) {
+                if self.data.title_history.get(title).is_some_and(|h| h.tier() >= tier) {
+                    // Already reported by the title history validation.
+                    continue;
+                }
+            }
+            if history.holder_at(date).is_none() {
+                let msg = format!("liege {liege} of {title} has no holder on {date}");
+                err(ErrorKey::History).msg(msg).loc(*liege).push();
+            }
+        }
+    }
+
+    fn check_spouses(&self) {
+        let date = self.date;
+        for (character, spouses) in &self.spouses {
+            let Some(ch) = self.data.characters.get(character) else { continue };
+            if ch.gender() != Gender::Female {
+                continue;
+            }
+            let husbands: Vec<&Token> = spouses
+                .iter()
+                .copied()
+                .filter(|spouse| {
+                    self.data
+                        .characters
+                        .get(spouse.as_str())
+                        .is_some_and(|spouse| spouse.gender() == Gender::Male)
+                })
+                .collect();
+            if husbands.len() > 1 {
+                let msg = format!(
+                    "{character} is married to both {} and {} on {date}",
+                    husbands[0], husbands[1]
+                );
+                warn(ErrorKey::History)
+                    .msg(msg)
+                    .loc(husbands[1])
+                    .loc_msg(husbands[0], "other marriage")
+                    .push();
+            }
+        }
+    }
+
+    fn check_counties(&self) {
+        let date = self.date;
+        for (provid, barony, county) in self.data.titles.iter_county_capitals() {
+            if let Some(holder) = self.holder(county) {
+                if self.is_alive(holder) {
+                    let characters = &self.data.characters;
+                    if characters.get_culture(holder, date).is_none() {
+                        let msg = format!("holder {holder} of {county} has no culture on {date}");
+                        err(ErrorKey::History).msg(msg).loc(holder).push();
+                    }
+                    if characters.get_faith(holder, date).is_none() {
+                        let msg = format!("holder {holder} of {county} has no faith on {date}");
+                        err(ErrorKey::History).msg(msg).loc(holder).push();
+                    }
+                }
+            }
+
+            if let Some(history) = self.data.province_histories.get(provid) {
+                let key = history.key();
+                if history.culture_at(date).is_none() {
+                    let msg =
+                        format!("capital province {key} of {county} has no culture on {date}");
+                    err(ErrorKey::History).msg(msg).loc(key).push();
+                }
+                if history.faith_at(date).is_none() {
+                    let msg = format!("capital province {key} of {county} has no faith on {date}");
+                    err(ErrorKey::History).msg(msg).loc(key).push();
+                }
+            } else {
+                let msg = format!("capital province {provid} of {county} has no history");
+                let info = "so it has no culture or faith";
+                err(ErrorKey::History).msg(msg).info(info).loc(barony).push();
+            }
+        }
+    }
+}
+
+/// Replay the history to the start date of every bookmark and check the result, together with the
+/// bookmark characters. Bookmarks that share a start date share the replayed history.
+pub fn check_bookmarks(data: &Everything) {
+    let mut bookmarks: BTreeMap<Date, Vec<&Block>> = BTreeMap::new();
+    for (_, block) in data.database.iter_key_block(Item::Bookmark) {
+        if let Some(date) = block.get_field_date("start_date") {
+            bookmarks.entry(date).or_default().push(block);
+        }
+    }
+    for (date, blocks) in bookmarks {
+        let history = HistoryState::new(data, date);
+        history.check();
+        for block in blocks {
+            check_bookmark_characters(block, &history, data);
+        }
+    }
+}
@@ -2,7 +2,10 @@
 
 pub mod data;
 pub mod effect_validation;
+pub mod family_tree;
+pub mod history;
 pub mod modif;
+pub mod scope_flow;
 pub mod scopes;
 pub mod tables;
 pub mod validate;
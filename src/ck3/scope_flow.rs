@@ -0,0 +1,469 @@
+//! Checking that named scopes are saved before they are read, following the events and
+//! `on_actions` that fire each other.
+//!
+//! A `scope:name` can only be read after something has saved it with `save_scope_as` or a similar
+//! effect, either earlier in the same event or in the event or `on_action` that fired it. The check
+//! follows the order in which the game runs the parts of an event: the triggers, then `immediate`,
+//! then the descriptions and portraits, then one of the options, then `after`. Saved scopes are
+//! passed on by `trigger_event` and by the event lists of `on_actions`. Temporary scopes are not.
+//!
+//! The check is syntactic. A save on any path counts, even if it's inside an `if` or only happens
+//! in one of the events that fire the item, and names that are checked with `exists = scope:name`
+//! are assumed to be handled. Items that can be fired from places the check doesn't follow, such
+//! as decisions, interactions, and scripted effects, are only checked for reading a scope before
+//! they save it themselves.
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::block::{Block, BlockItem, Comparator, Eq::Question, Field, BV};
+use crate::event_graph::{event_id, EventGraph, NodeKind};
+use crate::everything::Everything;
+use crate::on_action::on_action_scopecontext;
+use crate::report::{warn, ErrorKey};
+use crate::token::{Loc, Token};
+
+/// The fields of an option that are evaluated when the option is shown, before it's picked.
+const OPTION_DISPLAY_FIELDS: &[&str] =
+    &["name", "trigger", "show_as_unavailable", "flavor", "ai_chance"];
+
+/// The fields of an `on_action` that fire other events or `on_actions`.
+const ON_ACTION_HOP_FIELDS: &[&str] = &[
+    "events",
+    "first_valid",
+    "random_events",
+    "on_actions",
+    "random_on_action",
+    "first_valid_on_action",
+    "fallback",
+];
+
+#[derive(Debug)]
+enum Step<'a> {
+    /// `scope:name` is read at the token.
+    Read(&'a Token, &'a str),
+    /// The name in the token is saved. The flag is false for temporary scopes, which are not
+    /// passed on to other events.
+    Save(&'a Token, bool),
+    /// The node with this index is fired.
+    Hop(usize),
+}
+
+/// A list of alternative step sequences, of which only one runs, such as the options of an
+/// event. They all start with the names saved before them.
+type Part<'a> = Vec<Vec<Step<'a>>>;
+
+#[derive(Debug)]
+struct Node<'a> {
+    key: &'a Token,
+    /// The steps of the item, in the order the game runs them.
+    parts: Vec<Part<'a>>,
+    /// The names that are checked with `exists = scope:name` anywhere in the item.
+    guarded: FnvHashSet<&'a str>,
+    /// The names the game provides when it runs this `on_action` itself.
+    builtin: Option<FnvHashSet<String>>,
+    /// Whether the item can be fired from somewhere that this check doesn't follow.
+    external: bool,
+}
+
+/// The names an item starts out with.
+#[derive(Debug)]
+enum Entry {
+    /// The item can be fired with any names.
+    Unknown,
+    Known(FnvHashSet<String>),
+}
+
+struct Collector<'a> {
+    data: &'a Everything,
+    index: FnvHashMap<String, usize>,
+    /// The saves made by each scripted effect or trigger, by the location of its key.
+    scripted: FnvHashMap<Loc, Vec<(&'a Token, bool)>>,
+}
+
+impl<'a> Collector<'a> {
+    fn collect(
+        &mut self,
+        block: &'a Block,
+        steps: &mut Vec<Step<'a>>,
+        guarded: &mut FnvHashSet<&'a str>,
+    ) {
+        for item in block.iter_items() {
+            self.collect_item(item, steps, guarded);
+        }
+    }
+
+    fn collect_item(
+        &mut self,
+        item: &'a BlockItem,
+        steps: &mut Vec<Step<'a>>,
+        guarded: &mut FnvHashSet<&'a str>,
+    ) {
+        match item {
+            BlockItem::Value(token) => read(token, steps),
+            BlockItem::Block(block) => self.collect(block, steps, guarded),
+            BlockItem::Field(Field(key, cmp, bv)) => {
+                // `?=` only looks at the scope if it exists.
+                if *cmp == Comparator::Equals(Question) {
+                    if let BV::Block(block) = bv {
+                        self.collect(block, steps, guarded);
+                    }
+                    return;
+                }
+                match (key.as_str(), bv) {
+                    ("save_scope_as", BV::Value(name)) => steps.push(Step::Save(name, true)),
+                    ("save_temporary_scope_as", BV::Value(name)) => {
+                        steps.push(Step::Save(name, false));
+                    }
+                    (
+                        "save_scope_value_as"
+                        | "save_opinion_value_as"
+                        | "save_temporary_scope_value_as"
+                        | "save_temporary_opinion_value_as",
+                        BV::Block(block),
+                    ) => {
+                        self.collect(block, steps, guarded);
+                        if let Some(name) = block.get_field_value("name") {
+                            steps.push(Step::Save(name, !key.as_str().contains("temporary")));
+                        }
+                    }
+                    ("exists", BV::Value(token)) => {
+                        if let Some(name) = scope_name(token) {
+                            guarded.insert(name);
+                        }
+                    }
+                    ("trigger_event", BV::Value(token)) => {
+                        self.hop(NodeKind::Event, token, steps);
+                    }
+                    ("trigger_event", BV::Block(block)) => {
+                        self.collect(block, steps, guarded);
+                        if let Some(token) = block.get_field_value("id") {
+                            self.hop(NodeKind::Event, token, steps);
+                        }
+                        if let Some(token) = block.get_field_value("on_action") {
+                            self.hop(NodeKind::OnAction, token, steps);
+                        }
+                    }
+                    (_, bv) => {
+                        read(key, steps);
+                        match bv {
+                            BV::Value(token) => read(token, steps),
+                            BV::Block(block) => self.collect(block, steps, guarded),
+                        }
+                        for &(token, permanent) in self.scripted_saves(key) {
+                            steps.push(Step::Save(token, permanent));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn hop(&self, kind: NodeKind, token: &Token, steps: &mut Vec<Step<'a>>) {
+        if let Some(&idx) = self.index.get(&node_id(kind, token)) {
+            steps.push(Step::Hop(idx));
+        }
+    }
+
+    /// The saves made by the scripted effect or trigger `key`, if it is one.
+    fn scripted_saves(&mut self, key: &Token) -> &[(&'a Token, bool)] {
+        let data = self.data;
+        let definition = if let Some(effect) = data.events_ck3.get_effect(key) {
+            Some((&effect.key, &effect.block))
+        } else if let Some(trigger) = data.events_ck3.get_trigger(key) {
+            Some((&trigger.key, &trigger.block))
+        } else if let Some(effect) = data.effects.get(key.as_str()) {
+            Some((&effect.key, &effect.block))
+        } else {
+            data.triggers.get(key.as_str()).map(|trigger| (&trigger.key, &trigger.block))
+        };
+        let Some((key, block)) = definition else { return &[] };
+        if !self.scripted.contains_key(&key.loc) {
+            // Insert an empty entry first, in case the item calls itself.
+            self.scripted.insert(key.loc, Vec::new());
+            let mut steps = Vec::new();
+            self.collect(block, &mut steps, &mut FnvHashSet::default());
+            let saves = steps
+                .into_iter()
+                .filter_map(|step| match step {
+                    Step::Save(token, permanent) => Some((token, permanent)),
+                    _ => None,
+                })
+                .collect();
+            self.scripted.insert(key.loc, saves);
+        }
+        &self.scripted[&key.loc]
+    }
+
+    fn event_parts(
+        &mut self,
+        block: &'a Block,
+        guarded: &mut FnvHashSet<&'a str>,
+    ) -> Vec<Part<'a>> {
+        let mut triggers = Vec::new();
+        let mut on_trigger_fail = Vec::new();
+        let mut body = Vec::new();
+        let mut display = Vec::new();
+        let mut options = Vec::new();
+        let mut after = Vec::new();
+        for item in block.iter_items() {
+            let Some(Field(key, _, bv)) = item.get_field() else { continue };
+            match (key.as_str(), bv) {
+                ("trigger" | "major_trigger" | "weight_multiplier", BV::Block(block)) => {
+                    self.collect(block, &mut triggers, guarded);
+                }
+                ("on_trigger_fail", BV::Block(block)) => {
+                    self.collect(block, &mut on_trigger_fail, guarded);
+                }
+                ("immediate", BV::Block(block)) => self.collect(block, &mut body, guarded),
+                ("option", BV::Block(block)) => {
+                    let mut steps = Vec::new();
+                    let (shown, picked): (Vec<_>, Vec<_>) = block.iter_items().partition(|item| {
+                        item.get_field().is_some_and(|Field(key, _, _)| {
+                            OPTION_DISPLAY_FIELDS.contains(&key.as_str())
+                        })
+                    });
+                    for item in shown.into_iter().chain(picked) {
+                        self.collect_item(item, &mut steps, guarded);
+                    }
+                    options.push(steps);
+                }
+                ("after", BV::Block(block)) => self.collect(block, &mut after, guarded),
+                _ => self.collect_item(item, &mut display, guarded),
+            }
+        }
+        body.append(&mut display);
+        if options.is_empty() {
+            options.push(Vec::new());
+        }
+        vec![vec![triggers], vec![on_trigger_fail, Vec::new()], vec![body], options, vec![after]]
+    }
+
+    fn on_action_parts(
+        &mut self,
+        block: &'a Block,
+        guarded: &mut FnvHashSet<&'a str>,
+    ) -> Vec<Part<'a>> {
+        let mut triggers = Vec::new();
+        let mut effect = Vec::new();
+        let mut hops = Vec::new();
+        for Field(key, _, bv) in block.iter_fields() {
+            let field = key.as_str();
+            match bv {
+                BV::Block(block) if field == "trigger" || field == "weight_multiplier" => {
+                    self.collect(block, &mut triggers, guarded);
+                }
+                BV::Block(block) if field == "effect" => {
+                    self.collect(block, &mut effect, guarded);
+                }
+                _ if ON_ACTION_HOP_FIELDS.contains(&field) => {
+                    let kind = if field.contains("on_action") || field == "fallback" {
+                        NodeKind::OnAction
+                    } else {
+                        NodeKind::Event
+                    };
+                    match bv {
+                        BV::Value(token) => self.hop(kind, token, &mut hops),
+                        BV::Block(block) => {
+                            for token in block.iter_values() {
+                                self.hop(kind, token, &mut hops);
+                            }
+                            for (_, token) in block.iter_assignments() {
+                                self.hop(kind, token, &mut hops);
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        effect.append(&mut hops);
+        vec![vec![triggers], vec![effect]]
+    }
+}
+
+/// The name in `scope:name` or `scope:name.field`, if the token starts with a saved scope.
+fn scope_name(token: &Token) -> Option<&str> {
+    let name = token.as_str().split('.').next()?.strip_prefix("scope:")?;
+    // Parameters of scripted items are filled in by the caller.
+    (!name.is_empty() && !name.contains('$')).then_some(name)
+}
+
+fn read<'a>(token: &'a Token, steps: &mut Vec<Step<'a>>) {
+    if let Some(name) = scope_name(token) {
+        steps.push(Step::Read(token, name));
+    }
+}
+
+fn node_id(kind: NodeKind, key: &Token) -> String {
+    match kind {
+        NodeKind::Event => format!("event:{}", event_id(key.as_str())),
+        _ => format!("on_action:{key}"),
+    }
+}
+
+/// Run through the steps of `parts`, starting with the names in `entry`, and call `f` for each
+/// step with the names saved before it. The flag with each name says if it's passed on to other
+/// events.
+fn walk<'b>(
+    parts: &'b [Part<'b>],
+    entry: &'b FnvHashSet<String>,
+    mut f: impl FnMut(&Step<'b>, &FnvHashMap<&'b str, bool>),
+) {
+    let mut state: FnvHashMap<&str, bool> =
+        entry.iter().map(|name| (name.as_str(), true)).collect();
+    for part in parts {
+        let mut next = state.clone();
+        for alternative in part {
+            let mut current = state.clone();
+            for step in alternative {
+                f(step, &current);
+                if let Step::Save(token, permanent) = step {
+                    *current.entry(token.as_str()).or_default() |= permanent;
+                }
+            }
+            for (name, permanent) in current {
+                *next.entry(name).or_default() |= permanent;
+            }
+        }
+        state = next;
+    }
+}
+
+/// Report the named scopes that are read in events and `on_actions` before anything saves them.
+pub fn check_scope_flow(data: &Everything) {
+    let mut events: Vec<_> = data.events_ck3.iter_key_block().collect();
+    events.sort_unstable_by_key(|(key, _)| key.loc);
+    let mut on_actions: Vec<_> = data.on_actions.iter_key_block().collect();
+    on_actions.sort_unstable_by_key(|(key, _)| key.loc);
+
+    let mut index = FnvHashMap::default();
+    for (key, _) in &events {
+        index.insert(node_id(NodeKind::Event, key), index.len());
+    }
+    for (key, _) in &on_actions {
+        index.insert(node_id(NodeKind::OnAction, key), index.len());
+    }
+
+    let mut collector = Collector { data, index, scripted: FnvHashMap::default() };
+    let mut nodes = Vec::new();
+    for (key, block) in events {
+        let mut guarded = FnvHashSet::default();
+        let parts = collector.event_parts(block, &mut guarded);
+        nodes.push(Node { key, parts, guarded, builtin: None, external: false });
+    }
+    for (key, block) in on_actions {
+        let mut guarded = FnvHashSet::default();
+        let parts = collector.on_action_parts(block, &mut guarded);
+        let builtin = on_action_scopecontext(key, data)
+            .map(|sc| sc.iter_names().map(str::to_string).collect());
+        nodes.push(Node { key, parts, guarded, builtin, external: false });
+    }
+
+    for (from, _, to, key) in EventGraph::new(data).iter_edges() {
+        if matches!(from, NodeKind::ScriptedEffect | NodeKind::Item(_)) {
+            if let Some(&idx) = collector.index.get(&node_id(to, key)) {
+                nodes[idx].external = true;
+            }
+        }
+    }
+
+    // The names each hop passes on, apart from the ones its item started with.
+    let empty = FnvHashSet::default();
+    let mut hops = Vec::new();
+    for (from, node) in nodes.iter().enumerate() {
+        walk(&node.parts, &empty, |step, state| {
+            if let Step::Hop(to) = step {
+                let passed: Vec<&str> = state
+                    .iter()
+                    .filter(|(_, &permanent)| permanent)
+                    .map(|(&name, _)| name)
+                    .collect();
+                hops.push((from, *to, passed));
+            }
+        });
+    }
+
+    let mut entries: Vec<Entry> = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| {
+            if node.external {
+                Entry::Unknown
+            } else if let Some(builtin) = &node.builtin {
+                Entry::Known(builtin.clone())
+            } else if hops.iter().any(|(_, to, _)| *to == idx) {
+                Entry::Known(FnvHashSet::default())
+            } else {
+                Entry::Unknown
+            }
+        })
+        .collect();
+
+    // Pass the names along the hops until nothing changes. The sets only grow, so this ends.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (from, to, passed) in &hops {
+            let Entry::Known(target) = &entries[*to] else { continue };
+            let added: Vec<String> = match &entries[*from] {
+                Entry::Unknown => {
+                    entries[*to] = Entry::Unknown;
+                    changed = true;
+                    continue;
+                }
+                Entry::Known(source) => source
+                    .iter()
+                    .map(String::as_str)
+                    .chain(passed.iter().copied())
+                    .filter(|name| !target.contains(*name))
+                    .map(str::to_string)
+                    .collect(),
+            };
+            if let Entry::Known(target) = &mut entries[*to] {
+                if !added.is_empty() {
+                    target.extend(added);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..nodes.len()).collect();
+    order.sort_unstable_by_key(|&idx| nodes[idx].key.loc);
+    for idx in order {
+        let node = &nodes[idx];
+        let (entry, unknown) = match &entries[idx] {
+            Entry::Known(entry) => (entry, false),
+            Entry::Unknown => (&empty, true),
+        };
+        let mut saves: FnvHashMap<&str, &Token> = FnvHashMap::default();
+        for step in node.parts.iter().flatten().flatten() {
+            if let Step::Save(token, _) = step {
+                saves.entry(token.as_str()).or_insert(token);
+            }
+        }
+        let mut reported = FnvHashSet::default();
+        walk(&node.parts, entry, |step, state| {
+            let Step::Read(token, name) = step else { return };
+            if state.contains_key(name) || node.guarded.contains(name) {
+                return;
+            }
+            let save = saves.get(name);
+            // Whoever fires the item might have saved the name, unless the item saves it itself.
+            if unknown && save.is_none() || !reported.insert(*name) {
+                return;
+            }
+            let msg = format!("scope:{name} is read here but nothing saves it before this point");
+            let info = if unknown {
+                "it is saved later in this item"
+            } else {
+                "it has to be saved earlier in this item, or in an event or on_action that fires it"
+            };
+            let mut report = warn(ErrorKey::UnsavedScope).msg(msg).info(info).loc(*token);
+            if let Some(save) = save {
+                report = report.loc_msg(*save, "saved here");
+            }
+            report.push();
+        });
+    }
+}
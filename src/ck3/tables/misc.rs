@@ -886,3 +886,23 @@ pub const COMMON_DIRS: &[&str] = &[
     "common/vassal_contracts",
     "common/vassal_stances",
 ];
+
+// LAST UPDATED CK3 VERSION 1.12.1
+// Value triggers whose values stay within a known range, as (name, lowest, highest).
+pub const VALUE_RANGES: &[(&str, f64, f64)] = &[
+    ("age", 0.0, f64::INFINITY),
+    ("diplomacy", 0.0, f64::INFINITY),
+    ("martial", 0.0, f64::INFINITY),
+    ("stewardship", 0.0, f64::INFINITY),
+    ("intrigue", 0.0, f64::INFINITY),
+    ("learning", 0.0, f64::INFINITY),
+    ("prowess", 0.0, f64::INFINITY),
+    ("dread", 0.0, f64::INFINITY),
+    ("stress", 0.0, f64::INFINITY),
+    ("fertility", 0.0, f64::INFINITY),
+    ("realm_size", 0.0, f64::INFINITY),
+    ("number_of_knights", 0.0, f64::INFINITY),
+    ("number_of_traits", 0.0, f64::INFINITY),
+    ("piety_level", 0.0, 5.0),
+    ("prestige_level", 0.0, 5.0),
+];
@@ -0,0 +1,173 @@
+//! Analysis of the files and items of mods loaded with `load_mod` that are overridden by later
+//! mods, including the mod being validated.
+//!
+//! This is meant to help with compatibility patches between mods. The overrides are recorded
+//! while loading, and can be listed afterward with [`emit_conflicts`].
+
+use std::io::Write;
+use std::mem::take;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde_json::json;
+
+use crate::fileset::FileKind;
+use crate::report::{kind_tag, Errors};
+use crate::token::{Loc, Token};
+
+static CONFLICTS: Lazy<Mutex<Conflicts>> = Lazy::new(|| Mutex::new(Conflicts::default()));
+
+/// All the overrides between mods that were seen while loading.
+#[derive(Debug, Default)]
+struct Conflicts {
+    files: Vec<FileOverride>,
+    items: Vec<ItemOverride>,
+}
+
+/// A file from one mod that is not loaded because another mod has a file with the same path, or
+/// because another mod replaces the whole directory it's in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct FileOverride {
+    overridden: FileKind,
+    winner: FileKind,
+    pathname: PathBuf,
+    /// True iff the file was dropped because of a `replace_path` in the winner's `.mod` file.
+    replaced_dir: bool,
+}
+
+/// An item from one mod that is redefined by another mod.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ItemOverride {
+    overridden: FileKind,
+    winner: FileKind,
+    /// The kind of item, for display.
+    itype: String,
+    key: String,
+    overridden_loc: Loc,
+    winner_loc: Loc,
+}
+
+fn is_mod(kind: FileKind) -> bool {
+    matches!(kind, FileKind::LoadedMod(_) | FileKind::Mod)
+}
+
+/// Record that the file at `pathname` from the `overridden` mod is not loaded because the
+/// `winner` mod overrides it. Files that are not from mods are ignored, and so are files in the
+/// mods' root directories such as `descriptor.mod`, because the game doesn't load those.
+pub(crate) fn record_file_override(
+    pathname: &Path,
+    overridden: FileKind,
+    winner: FileKind,
+    replaced_dir: bool,
+) {
+    let in_root = pathname.parent().map_or(true, |dir| dir.as_os_str().is_empty());
+    if is_mod(overridden) && is_mod(winner) && overridden != winner && !in_root {
+        let pathname = pathname.to_path_buf();
+        let file_override = FileOverride { overridden, winner, pathname, replaced_dir };
+        CONFLICTS.lock().unwrap().files.push(file_override);
+    }
+}
+
+/// Record that `key` redefines the item `other`. `itype` describes the kind of item.
+///
+/// Files are loaded in order of their names, regardless of which mod they are from, so the
+/// winner is not necessarily the mod that was loaded last.
+/// Only redefinitions where both keys are from different mods are recorded. Redefinitions
+/// within one mod are reported as duplicate items instead.
+pub(crate) fn record_item_override(itype: &str, key: &Token, other: &Token) {
+    let (winner, overridden) = (key.loc.kind, other.loc.kind);
+    if is_mod(overridden) && is_mod(winner) && overridden != winner {
+        let item_override = ItemOverride {
+            overridden,
+            winner,
+            itype: itype.to_string(),
+            key: key.to_string(),
+            overridden_loc: other.loc,
+            winner_loc: key.loc,
+        };
+        CONFLICTS.lock().unwrap().items.push(item_override);
+    }
+}
+
+/// Print all the recorded overrides to the error output, grouped by the pair of mods involved,
+/// and forget about them.
+/// Set `json` if they should be printed as a JSON array. Otherwise they are printed as text.
+///
+/// # Panics
+/// May panic when the mutex has been poisoned by another thread.
+pub fn emit_conflicts(json: bool) {
+    let mut conflicts = take(&mut *CONFLICTS.lock().unwrap());
+    conflicts.files.sort_unstable();
+    conflicts.files.dedup();
+    conflicts.items.sort_unstable();
+    conflicts.items.dedup();
+
+    let mut pairs: Vec<(FileKind, FileKind)> = conflicts
+        .files
+        .iter()
+        .map(|f| (f.overridden, f.winner))
+        .chain(conflicts.items.iter().map(|i| (i.overridden, i.winner)))
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+
+    let mut errors = Errors::get_mut();
+    let mut groups = Vec::new();
+    for (overridden, winner) in pairs {
+        let overridden_tag = kind_tag(&errors, overridden).to_string();
+        let winner_tag = kind_tag(&errors, winner).to_string();
+        let files =
+            conflicts.files.iter().filter(|f| f.overridden == overridden && f.winner == winner);
+        let items =
+            conflicts.items.iter().filter(|i| i.overridden == overridden && i.winner == winner);
+        if json {
+            let files: Vec<_> = files
+                .map(|f| json!({ "path": f.pathname, "replaced_dir": f.replaced_dir }))
+                .collect();
+            let items: Vec<_> = items
+                .map(|i| {
+                    let overridden = i.overridden_loc;
+                    let winner = i.winner_loc;
+                    json!({
+                        "item": i.itype,
+                        "key": i.key,
+                        "overridden": { "path": overridden.pathname(), "linenr": overridden.line },
+                        "winner": { "path": winner.pathname(), "linenr": winner.line },
+                    })
+                })
+                .collect();
+            groups.push(json!({
+                "overridden": overridden_tag,
+                "winner": winner_tag,
+                "files": files,
+                "items": items,
+            }));
+        } else {
+            let out = errors.output.get_mut();
+            _ = writeln!(out, "{winner_tag} overrides {overridden_tag}:");
+            for f in files {
+                let how = if f.replaced_dir { " (directory replaced)" } else { "" };
+                _ = writeln!(out, "  file {}{how}", f.pathname.display());
+            }
+            for i in items {
+                _ = writeln!(
+                    out,
+                    "  {} {}: {}:{} overrides {}:{}",
+                    i.itype,
+                    i.key,
+                    i.winner_loc.pathname().display(),
+                    i.winner_loc.line,
+                    i.overridden_loc.pathname().display(),
+                    i.overridden_loc.line,
+                );
+            }
+        }
+    }
+    if json {
+        if let Err(e) = serde_json::to_writer_pretty(errors.output.get_mut(), &groups) {
+            eprintln!("JSON error: {e:#}");
+        }
+        _ = writeln!(errors.output.get_mut());
+    }
+}
@@ -10,6 +10,19 @@ use crate::report::{err, warn, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 
+/// What a scripted item needs from the scope context of its callers, as inferred by validating it
+/// with an unrooted `ScopeContext`. Each requirement comes with the token that caused it.
+#[derive(Clone, Debug)]
+pub struct ScopeSignature {
+    pub root: (Scopes, Token),
+    pub this: (Scopes, Token),
+    pub prev: (Scopes, Token),
+    /// The named scopes that the caller must have set, sorted by name.
+    pub named: Vec<(String, Scopes, Token)>,
+    /// The lists that the caller must have made, sorted by name.
+    pub lists: Vec<(String, Scopes, Token)>,
+}
+
 /// When reporting an unknown scope, list alternative scope names if there are not more than this.
 const MAX_SCOPE_NAME_LIST: usize = 6;
 
@@ -270,6 +283,12 @@ impl ScopeContext {
         }
     }
 
+    /// Iterate over the names of the named scopes known to this scope context.
+    #[cfg(feature = "ck3")]
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.names.keys().map(String::as_str)
+    }
+
     /// This is called when the script does `exists = scope:name`.
     ///
     /// It records `name` as "known", but with no scope type information, and records that the
@@ -921,6 +940,36 @@ impl ScopeContext {
     }
 }
 
+impl ScopeContext {
+    /// Summarize what this scope context has learned about its callers' scopes.
+    ///
+    /// This is meant for the scope contexts of scripted items after they have been validated,
+    /// and is the same information that [`Self::expect_compatibility()`] checks callers against.
+    pub fn signature(&self) -> ScopeSignature {
+        let (root, reason) = self.resolve_root();
+        let root = (root, reason.token().clone());
+        let (this, reason) = self.scopes_reason();
+        let this = (this, reason.token().clone());
+        let (prev, reason) = self.scopes_reason_internal(0);
+        let prev = (prev, reason.token().clone());
+
+        let inputs = |names: &FnvHashMap<String, usize>| {
+            let mut inputs: Vec<(String, Scopes, Token)> = names
+                .iter()
+                .filter_map(|(name, &idx)| {
+                    let token = self.is_input[idx].as_ref()?;
+                    Some((name.clone(), self.resolve_named(idx).0, token.clone()))
+                })
+                .collect();
+            inputs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            inputs
+        };
+        let named = inputs(&self.names);
+        let lists = inputs(&self.list_names);
+        ScopeSignature { root, this, prev, named, lists }
+    }
+}
+
 impl Drop for ScopeContext {
     /// This `drop` function checks that every opened scope level was also closed.
     fn drop(&mut self) {
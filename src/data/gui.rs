@@ -7,10 +7,11 @@ use std::sync::{Arc, RwLock};
 use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::{Block, BlockItem, Field, BV};
+use crate::conflicts::record_item_override;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::gui::{BuiltinWidget, GuiBlock, GuiBlockFrom};
-use crate::helpers::dup_error;
+use crate::helpers::{dup_error, report_unused_items, UsedKeys};
 use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::pdxfile::PdxFile;
@@ -32,6 +33,9 @@ pub struct Gui {
     // This is indexed by a (colorblindmode, textformatname) pair
     textformats_colorblind: FnvHashMap<(&'static str, &'static str), TextFormat>,
     widget_names: FnvHashSet<Token>,
+    // Type keys are stored in lowercase here too
+    used_types: UsedKeys,
+    used_templates: UsedKeys,
 }
 
 impl Gui {
@@ -137,6 +141,7 @@ impl Gui {
         let key_lc = Lowercase::new(key.as_str());
 
         if let Some(other) = self.types.get(&key_lc) {
+            record_item_override("gui type", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui type");
             }
@@ -146,6 +151,7 @@ impl Gui {
 
     pub fn load_template(&mut self, key: Token, block: Block) {
         if let Some(other) = self.templates.get(key.as_str()) {
+            record_item_override("gui template", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui template");
             }
@@ -155,6 +161,7 @@ impl Gui {
 
     pub fn load_layer(&mut self, key: Token, block: Block) {
         if let Some(other) = self.layers.get(key.as_str()) {
+            record_item_override("gui layer", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui layer");
             }
@@ -207,6 +214,48 @@ impl Gui {
         self.types.values().map(|item| &item.key)
     }
 
+    pub fn mark_template_used(&self, key: &str) {
+        self.used_templates.mark(key);
+    }
+
+    pub fn mark_type_used(&self, key: &str) {
+        self.used_types.mark(Lowercase::new(key).as_str());
+    }
+
+    /// Mark the types and templates that `block` refers to, including in its sub-blocks.
+    fn mark_references(&self, block: &Block) {
+        for Field(key, _, bv) in block.iter_fields() {
+            let key_lc = Lowercase::new(key.as_str());
+            if key_lc == "using" {
+                if let Some(value) = bv.get_value() {
+                    self.used_templates.mark(value.as_str());
+                }
+            } else if self.types.contains_key(key_lc.as_str()) {
+                self.used_types.mark(key_lc.as_str());
+            }
+            if let Some(block) = bv.get_block() {
+                self.mark_references(block);
+            }
+        }
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .types
+            .iter()
+            .filter(|(key_lc, _)| !self.used_types.contains(key_lc.as_str()))
+            .map(|(_, item)| &item.key)
+            .collect();
+        report_unused_items(Item::GuiType, unused);
+        let unused = self
+            .templates
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used_templates.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::GuiTemplate, unused);
+    }
+
     pub fn layer_exists(&self, key: &str) -> bool {
         self.layers.contains_key(key)
     }
@@ -403,6 +452,18 @@ impl FileHandler<Block> for Gui {
         for item in self.templates.values() {
             _ = item.gui_block(&self.types, &self.templates);
         }
+
+        for item in self.files.values().flatten() {
+            self.mark_type_used(item.key.as_str());
+            self.mark_references(&item.block);
+        }
+        for item in self.types.values() {
+            self.mark_type_used(item.base.as_str());
+            self.mark_references(&item.block);
+        }
+        for item in self.templates.values() {
+            self.mark_references(&item.block);
+        }
     }
 }
 
@@ -8,6 +8,7 @@ use crate::effect::validate_effect;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::game::Game;
+use crate::helpers::{report_unused_items, UsedKeys};
 use crate::item::Item;
 use crate::on_action::on_action_scopecontext;
 use crate::pdxfile::PdxFile;
@@ -20,9 +21,11 @@ use crate::trigger::validate_trigger;
 use crate::validate::{validate_duration, validate_modifiers_with_base};
 use crate::validator::Validator;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct OnActions {
     on_actions: FnvHashMap<&'static str, OnAction>,
+    used: UsedKeys,
 }
 
 impl OnActions {
@@ -42,6 +45,27 @@ impl OnActions {
         self.on_actions.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    /// Report the `on_actions` that are neither referred to from script nor called by the game.
+    pub fn check_unused(&self, data: &Everything) {
+        let unused = self
+            .on_actions
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .filter(|key| on_action_scopecontext(key, data).is_none())
+            .collect();
+        report_unused_items(Item::OnAction, unused);
+    }
+
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    pub fn iter_key_block(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.on_actions.values().map(|item| (&item.key, &item.block))
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.on_actions.values() {
             item.validate(data);
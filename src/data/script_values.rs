@@ -4,10 +4,12 @@ use std::sync::RwLock;
 use fnv::FnvHashMap;
 
 use crate::block::{Block, BV};
-use crate::context::ScopeContext;
+use crate::conflicts::record_item_override;
+use crate::context::{ScopeContext, ScopeSignature};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, exact_dup_error, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, report_unused_items, UsedKeys, BANNED_NAMES};
+use crate::item::Item;
 use crate::pdxfile::PdxFile;
 use crate::report::{err, warn, ErrorKey};
 use crate::scopes::Scopes;
@@ -15,14 +17,17 @@ use crate::script_value::{validate_non_dynamic_script_value, validate_script_val
 use crate::token::{Loc, Token};
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct ScriptValues {
     scope_overrides: FnvHashMap<&'static str, Scopes>,
     script_values: FnvHashMap<&'static str, ScriptValue>,
+    used: UsedKeys,
 }
 
 impl ScriptValues {
     fn load_item(&mut self, key: &Token, bv: &BV) {
         if let Some(other) = self.script_values.get(key.as_str()) {
+            record_item_override("script value", key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 if other.bv.equivalent(bv) {
                     exact_dup_error(key, &other.key, "script value");
@@ -49,6 +54,20 @@ impl ScriptValues {
         self.script_values.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .script_values
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::ScriptValue, unused);
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.script_values.values() {
             item.validate(data);
@@ -57,12 +76,23 @@ impl ScriptValues {
 
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
         if let Some(item) = self.script_values.get(key.as_str()) {
+            self.used.mark(key.as_str());
             item.validate_call(key, data, sc);
         }
     }
 
+    pub fn get_bv(&self, key: &str) -> Option<&BV> {
+        self.script_values.get(key).map(|item| &item.bv)
+    }
+
+    /// Iterate over the inferred scope signatures of the script values that have been validated.
+    pub fn iter_signatures(&self) -> impl Iterator<Item = (&Token, ScopeSignature)> {
+        self.script_values.values().filter_map(|item| Some((&item.key, item.signature()?)))
+    }
+
     pub fn validate_non_dynamic_call(&self, key: &Token, data: &Everything) {
         if let Some(item) = self.script_values.get(key.as_str()) {
+            self.used.mark(key.as_str());
             item.validate_non_dynamic_call(data);
         }
     }
@@ -164,6 +194,12 @@ impl ScriptValue {
         }
     }
 
+    /// Return what this script value needs from its callers' scopes. It's only known after the
+    /// script value has been validated.
+    pub fn signature(&self) -> Option<ScopeSignature> {
+        self.cache.read().unwrap().get(&self.key.loc).map(ScopeContext::signature)
+    }
+
     pub fn validate_non_dynamic_call(&self, data: &Everything) {
         validate_non_dynamic_script_value(&self.bv, data);
     }
@@ -4,11 +4,13 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::conflicts::record_item_override;
+use crate::context::{ScopeContext, ScopeSignature};
 use crate::effect::validate_effect;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, exact_dup_error, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, report_unused_items, UsedKeys, BANNED_NAMES};
+use crate::item::Item;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::pdxfile::PdxFile;
 use crate::report::{err, warn, ErrorKey};
@@ -17,14 +19,17 @@ use crate::token::Token;
 use crate::tooltipped::Tooltipped;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Effects {
     scope_overrides: FnvHashMap<&'static str, Scopes>,
     effects: FnvHashMap<&'static str, Effect>,
+    used: UsedKeys,
 }
 
 impl Effects {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.effects.get(key.as_str()) {
+            record_item_override("scripted effect", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 if other.block.equivalent(&block) {
                     exact_dup_error(&key, &other.key, "scripted effect");
@@ -53,6 +58,30 @@ impl Effects {
         self.effects.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .effects
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::ScriptedEffect, unused);
+    }
+
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    pub fn iter_key_block(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.effects.values().map(|item| (&item.key, &item.block))
+    }
+
+    /// Iterate over the inferred scope signatures of the scripted effects that have been validated.
+    pub fn iter_signatures(&self) -> impl Iterator<Item = (&Token, ScopeSignature)> {
+        self.effects.values().filter_map(|item| Some((&item.key, item.signature()?)))
+    }
+
     pub fn get(&self, key: &str) -> Option<&Effect> {
         self.effects.get(key)
     }
@@ -108,7 +137,7 @@ impl FileHandler<Block> for Effects {
 #[derive(Debug)]
 pub struct Effect {
     pub key: Token,
-    block: Block,
+    pub block: Block,
     cache: MacroCache<ScopeContext>,
     scope_override: Option<Scopes>,
 }
@@ -153,6 +182,16 @@ impl Effect {
         }
     }
 
+    /// Return what this effect needs from its callers' scopes. It's only known after the effect
+    /// has been validated, and not for effects with macro parameters.
+    pub fn signature(&self) -> Option<ScopeSignature> {
+        let mut signature = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |sc| {
+            signature = Some(sc.signature());
+        });
+        signature
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -4,10 +4,12 @@ use std::sync::RwLock;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
+use crate::conflicts::record_item_override;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::dup_error;
+use crate::helpers::{dup_error, report_unused_items, UsedKeys};
+use crate::item::Item;
 use crate::pdxfile::PdxFile;
 use crate::report::{err, ErrorKey};
 use crate::scopes::{scope_iterator, Scopes};
@@ -19,11 +21,13 @@ use crate::validator::Validator;
 #[derive(Debug, Default)]
 pub struct ScriptedLists {
     lists: FnvHashMap<&'static str, List>,
+    used: UsedKeys,
 }
 
 impl ScriptedLists {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.lists.get(key.as_str()) {
+            record_item_override("scripted list", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "scripted list");
             }
@@ -39,8 +43,23 @@ impl ScriptedLists {
         self.lists.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .lists
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::ScriptedList, unused);
+    }
+
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
         if let Some(item) = self.lists.get(key.as_str()) {
+            self.used.mark(key.as_str());
             item.validate_call(key, data, sc);
         }
     }
@@ -3,10 +3,12 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::conflicts::record_item_override;
+use crate::context::{ScopeContext, ScopeSignature};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, BANNED_NAMES};
+use crate::helpers::{dup_error, report_unused_items, UsedKeys, BANNED_NAMES};
+use crate::item::Item;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::pdxfile::PdxFile;
 use crate::report::{err, ErrorKey};
@@ -19,11 +21,13 @@ use crate::validator::Validator;
 #[derive(Debug, Default)]
 pub struct ScriptedModifiers {
     scripted_modifiers: FnvHashMap<&'static str, ScriptedModifier>,
+    used: UsedKeys,
 }
 
 impl ScriptedModifiers {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.scripted_modifiers.get(key.as_str()) {
+            record_item_override("scripted modifier", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "scripted modifier");
             }
@@ -47,6 +51,25 @@ impl ScriptedModifiers {
         self.scripted_modifiers.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .scripted_modifiers
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::ScriptedModifier, unused);
+    }
+
+    /// Iterate over the inferred scope signatures of the scripted modifiers that have been validated.
+    pub fn iter_signatures(&self) -> impl Iterator<Item = (&Token, ScopeSignature)> {
+        self.scripted_modifiers.values().filter_map(|item| Some((&item.key, item.signature()?)))
+    }
+
     pub fn get(&self, key: &str) -> Option<&ScriptedModifier> {
         self.scripted_modifiers.get(key)
     }
@@ -112,6 +135,16 @@ impl ScriptedModifier {
         }
     }
 
+    /// Return what this modifier needs from its callers' scopes. It's only known after the
+    /// modifier has been validated, and not for modifiers with macro parameters.
+    pub fn signature(&self) -> Option<ScopeSignature> {
+        let mut signature = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |sc| {
+            signature = Some(sc.signature());
+        });
+        signature
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -3,10 +3,12 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::conflicts::record_item_override;
+use crate::context::{ScopeContext, ScopeSignature};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler, FileKind};
-use crate::helpers::{dup_error, exact_dup_error, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, report_unused_items, UsedKeys, BANNED_NAMES};
+use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::pdxfile::PdxFile;
@@ -15,16 +17,20 @@ use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
 use crate::trigger::validate_trigger_internal;
+use crate::trigger_logic::check_trigger_logic;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Triggers {
     scope_overrides: FnvHashMap<&'static str, Scopes>,
     triggers: FnvHashMap<&'static str, Trigger>,
+    used: UsedKeys,
 }
 
 impl Triggers {
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.triggers.get(key.as_str()) {
+            record_item_override("scripted trigger", &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 if other.block.equivalent(&block) {
                     exact_dup_error(&key, &other.key, "scripted trigger");
@@ -57,6 +63,25 @@ impl Triggers {
         self.triggers.values().map(|item| &item.key)
     }
 
+    pub fn mark_used(&self, key: &str) {
+        self.used.mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self
+            .triggers
+            .values()
+            .map(|item| &item.key)
+            .filter(|key| !self.used.contains(key.as_str()))
+            .collect();
+        report_unused_items(Item::ScriptedTrigger, unused);
+    }
+
+    /// Iterate over the inferred scope signatures of the scripted triggers that have been validated.
+    pub fn iter_signatures(&self) -> impl Iterator<Item = (&Token, ScopeSignature)> {
+        self.triggers.values().filter_map(|item| Some((&item.key, item.signature()?)))
+    }
+
     pub fn get(&self, key: &str) -> Option<&Trigger> {
         self.triggers.get(key)
     }
@@ -112,7 +137,7 @@ impl FileHandler<Block> for Triggers {
 #[derive(Debug)]
 pub struct Trigger {
     pub key: Token,
-    block: Block,
+    pub block: Block,
     cache: MacroCache<ScopeContext>,
     scope_override: Option<Scopes>,
 }
@@ -132,6 +157,7 @@ impl Trigger {
                 sc.set_no_warn(true);
             }
             self.validate_call(&self.key, data, &mut sc, Tooltipped::No, false);
+            check_trigger_logic(&self.block, data);
         }
     }
 
@@ -169,6 +195,16 @@ impl Trigger {
         }
     }
 
+    /// Return what this trigger needs from its callers' scopes. It's only known after the trigger
+    /// has been validated, and not for triggers with macro parameters.
+    pub fn signature(&self) -> Option<ScopeSignature> {
+        let mut signature = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |sc| {
+            signature = Some(sc.signature());
+        });
+        signature
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -16,6 +16,18 @@ impl Date {
     pub fn new(year: i16, month: i8, day: i8) -> Self {
         Date { year, month, day }
     }
+
+    /// Return the number of whole years from `earlier` to this date.
+    /// It's negative if `earlier` is actually later.
+    #[allow(dead_code)] // Not used by all games
+    pub fn years_since(self, earlier: Date) -> i16 {
+        let years = self.year - earlier.year;
+        if (self.month, self.day) < (earlier.month, earlier.day) {
+            years - 1
+        } else {
+            years
+        }
+    }
 }
 
 impl FromStr for Date {
@@ -4,6 +4,7 @@
 
 use std::any::Any;
 use std::fmt::Debug;
+use std::path::{Path, PathBuf};
 
 use as_any::AsAny;
 use fnv::{FnvHashMap, FnvHashSet};
@@ -11,9 +12,10 @@ use rayon::prelude::*;
 use strum::IntoEnumIterator;
 
 use crate::block::Block;
+use crate::conflicts::record_item_override;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
-use crate::helpers::{dup_error, exact_dup_advice, exact_dup_error};
+use crate::helpers::{dup_error, exact_dup_advice, exact_dup_error, report_unused_items, UsedKeys};
 use crate::item::Item;
 use crate::token::Token;
 
@@ -26,14 +28,18 @@ pub struct Db {
     /// Items generated as side effects of the full items in `database`.
     /// The `Vec` is indexed with an `Item` discriminant.
     flags: Vec<FnvHashSet<Token>>,
+    /// The keys that have been referred to, for the item types that are checked by `--unused`.
+    /// The `Vec` is indexed with an `Item` discriminant.
+    used: Vec<UsedKeys>,
 }
 
 impl Default for Db {
     fn default() -> Self {
-        let mut db = Self { database: Vec::default(), flags: Vec::default() };
+        let mut db = Self { database: Vec::default(), flags: Vec::default(), used: Vec::default() };
         for _ in Item::iter() {
             db.database.push(FnvHashMap::default());
             db.flags.push(FnvHashSet::default());
+            db.used.push(UsedKeys::default());
         }
         db
     }
@@ -42,6 +48,7 @@ impl Default for Db {
 impl Db {
     pub fn add(&mut self, item: Item, key: Token, block: Block, kind: Box<dyn DbKind>) {
         if let Some(other) = self.database[item as usize].get(key.as_str()) {
+            record_item_override(&item.to_string(), &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 if other.block.equivalent(&block) {
                     exact_dup_error(&key, &other.key, &item.to_string());
@@ -61,6 +68,7 @@ impl Db {
         kind: Box<dyn DbKind>,
     ) {
         if let Some(other) = self.database[item as usize].get(key.as_str()) {
+            record_item_override(&item.to_string(), &key, &other.key);
             if other.key.loc.kind >= key.loc.kind {
                 if other.block.equivalent(&block) {
                     exact_dup_advice(&key, &other.key, &item.to_string());
@@ -76,10 +84,24 @@ impl Db {
         self.flags[item as usize].insert(key);
     }
 
-    pub fn validate(&self, data: &Everything) {
+    /// Remove all items and flags that were loaded from files under `dir`, so that the directory
+    /// can be loaded again.
+    pub fn remove_under(&mut self, dir: &Path) {
+        for hash in &mut self.database {
+            hash.retain(|_, entry| !entry.key.loc.pathname().starts_with(dir));
+        }
+        for flags in &mut self.flags {
+            flags.retain(|flag| !flag.loc.pathname().starts_with(dir));
+        }
+    }
+
+    /// Validate the items. If `only` is given, validate only the items defined in those files.
+    pub fn validate(&self, data: &Everything, only: Option<&FnvHashSet<PathBuf>>) {
         self.database.par_iter().for_each(|hash| {
             hash.par_iter().for_each(|(_, entry)| {
-                entry.kind.validate(&entry.key, &entry.block, data);
+                if only.map_or(true, |files| files.contains(entry.key.loc.pathname())) {
+                    entry.kind.validate(&entry.key, &entry.block, data);
+                }
             });
         });
     }
@@ -160,6 +182,22 @@ impl Db {
             .map(|entry| &entry.key)
             .chain(self.flags[itype as usize].iter())
     }
+
+    pub fn mark_used(&self, itype: Item, key: &str) {
+        self.used[itype as usize].mark(key);
+    }
+
+    pub fn check_unused(&self) {
+        for itype in Item::iter().filter(|itype| itype.reported_if_unused()) {
+            let used = &self.used[itype as usize];
+            let unused = self.database[itype as usize]
+                .values()
+                .map(|entry| &entry.key)
+                .filter(|key| !used.contains(key.as_str()))
+                .collect();
+            report_unused_items(itype, unused);
+        }
+    }
 }
 
 #[derive(Debug)]
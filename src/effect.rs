@@ -6,6 +6,8 @@ use crate::data::effect_localization::EffectLocalization;
 use crate::desc::validate_desc;
 use crate::everything::Everything;
 use crate::game::Game;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::helpers::renamed_to;
 use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::report::{err, fatal, tips, warn, ErrorKey};
@@ -115,6 +117,7 @@ pub fn validate_effect_field(
     tooltipped: Tooltipped,
 ) {
     if let Some(effect) = data.get_effect(key) {
+        data.mark_used(Item::ScriptedEffect, key.as_str());
         match bv {
             BV::Value(token) => {
                 if !effect.macro_parms().is_empty() {
@@ -178,6 +181,7 @@ pub fn validate_effect_field(
 
     if let Some((inscopes, effect)) = scope_effect(key, data) {
         sc.expect(inscopes, &Reason::Token(key.clone()));
+        data.variables.record_field(key, bv, sc.scopes());
         match effect {
             Effect::Yes => {
                 if let Some(token) = bv.expect_value() {
@@ -336,7 +340,11 @@ pub fn validate_effect_field(
             #[cfg(any(feature = "ck3", feature = "vic3"))]
             Effect::Removed(version, explanation) => {
                 let msg = format!("`{key}` was removed in {version}");
-                warn(ErrorKey::Removed).msg(msg).info(explanation).loc(key).push();
+                let mut builder = warn(ErrorKey::Removed).msg(msg).info(explanation).loc(key);
+                if let Some(new_name) = renamed_to(explanation) {
+                    builder = builder.fix(key, new_name);
+                }
+                builder.push();
             }
             Effect::Unchecked | Effect::UncheckedTodo => (),
         }
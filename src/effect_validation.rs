@@ -16,6 +16,8 @@ use crate::tooltipped::Tooltipped;
 use crate::trigger::{validate_target_ok_this, validate_trigger_key_bv};
 use crate::validate::validate_optional_duration;
 use crate::validator::{Validator, ValueValidator};
+#[cfg(feature = "ck3")]
+use crate::value_range::check_random_list_weight;
 
 pub fn validate_add_to_list(
     _key: &Token,
@@ -105,6 +107,10 @@ pub fn validate_random_list(
                 warn(ErrorKey::Range).strong().msg(msg).loc(key).push();
             }
             validate_effect_control(&caller, block, data, sc, tooltipped);
+            #[cfg(feature = "ck3")]
+            if Game::is_ck3() {
+                check_random_list_weight(key, n, block, data);
+            }
         }
     });
 }
@@ -0,0 +1,528 @@
+//! Export of the graph of which script items fire which events.
+//!
+//! The nodes are the events, the `on_actions`, the scripted effects, and any other items that fire
+//! events. The edges come from `trigger_event`, from the `events`, `random_events`, `first_valid`,
+//! `on_actions`, `random_on_action`, `first_valid_on_action` and `fallback` fields of `on_actions`,
+//! and from calls to scripted effects. Only edges to items that are defined are included.
+//!
+//! The graph is found by looking at the script syntactically, without regard for whether the
+//! effects can actually be executed.
+
+use std::fmt::Write as _;
+use std::fs::write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use fnv::{FnvHashMap, FnvHashSet};
+use serde_json::json;
+use strum::IntoEnumIterator;
+
+use crate::block::{Block, BlockItem, Field, BV};
+use crate::everything::Everything;
+use crate::game::Game;
+use crate::item::Item;
+use crate::report::{tips, untidy, ErrorKey};
+use crate::token::{Loc, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Event,
+    OnAction,
+    ScriptedEffect,
+    /// Any other item that fires events, such as a decision.
+    Item(Item),
+}
+
+impl NodeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeKind::Event => "event",
+            NodeKind::OnAction => "on_action",
+            NodeKind::ScriptedEffect => "scripted_effect",
+            NodeKind::Item(itype) => itype.into(),
+        }
+    }
+
+    fn dot_shape(self) -> &'static str {
+        match self {
+            NodeKind::Event => "box",
+            NodeKind::OnAction => "ellipse",
+            NodeKind::ScriptedEffect => "diamond",
+            NodeKind::Item(_) => "note",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    kind: NodeKind,
+    /// Unique among the nodes.
+    id: String,
+    /// The key where the item is defined.
+    key: Token,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: usize,
+    to: usize,
+    /// The field that makes the connection, such as `trigger_event` or `random_events`.
+    via: &'static str,
+    loc: Loc,
+}
+
+/// The graph of which script items fire which events.
+#[derive(Debug, Default)]
+pub(crate) struct EventGraph {
+    nodes: Vec<Node>,
+    index: FnvHashMap<String, usize>,
+    edges: Vec<Edge>,
+    /// The `from`, `to`, and `via` of the edges, to avoid adding the same edge twice.
+    seen_edges: FnvHashSet<(usize, usize, &'static str)>,
+}
+
+impl EventGraph {
+    pub(crate) fn new(data: &Everything) -> Self {
+        let mut graph = Self::default();
+
+        let events: Box<dyn Iterator<Item = (&Token, &Block)>> = match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => Box::new(data.events_ck3.iter_key_block()),
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => Box::new(data.events_vic3.iter_key_block()),
+        };
+        let mut events: Vec<_> = events.collect();
+        events.sort_unstable_by_key(|(key, _)| key.loc);
+        let mut on_actions: Vec<_> = data.on_actions.iter_key_block().collect();
+        on_actions.sort_unstable_by_key(|(key, _)| key.loc);
+        let mut effects: Vec<_> = data.effects.iter_key_block().collect();
+        #[cfg(feature = "ck3")]
+        effects.extend(data.events_ck3.iter_effects().map(|effect| (&effect.key, &effect.block)));
+        effects.sort_unstable_by_key(|(key, _)| key.loc);
+
+        // Add the nodes first, so that the edges can find them.
+        for (key, _) in &events {
+            graph.add_node(NodeKind::Event, &event_id(key.as_str()), key);
+        }
+        for (key, _) in &on_actions {
+            graph.add_node(NodeKind::OnAction, key.as_str(), key);
+        }
+        for (key, _) in &effects {
+            graph.add_node(NodeKind::ScriptedEffect, &effect_id(key, data), key);
+        }
+
+        for (key, block) in events {
+            graph.scan(NodeKind::Event, &event_id(key.as_str()), key, block, data);
+        }
+        for (key, block) in on_actions {
+            graph.scan(NodeKind::OnAction, key.as_str(), key, block, data);
+        }
+        for (key, block) in effects {
+            graph.scan(NodeKind::ScriptedEffect, &effect_id(key, data), key, block, data);
+        }
+        for itype in Item::iter() {
+            let mut items: Vec<_> = data.database.iter_key_block(itype).collect();
+            items.sort_unstable_by_key(|(key, _)| key.loc);
+            for (key, block) in items {
+                graph.scan(NodeKind::Item(itype), key.as_str(), key, block, data);
+            }
+        }
+        graph
+    }
+
+    fn add_node(&mut self, kind: NodeKind, id: &str, key: &Token) -> usize {
+        let id = format!("{}:{id}", kind.as_str());
+        if let Some(&idx) = self.index.get(&id) {
+            return idx;
+        }
+        self.index.insert(id.clone(), self.nodes.len());
+        self.nodes.push(Node { kind, id, key: key.clone() });
+        self.nodes.len() - 1
+    }
+
+    fn find_node(&self, kind: NodeKind, id: &str) -> Option<usize> {
+        self.index.get(&format!("{}:{id}", kind.as_str())).copied()
+    }
+
+    /// Add the edges from the item `key` to the items it fires.
+    /// The node for the item is only added if it fires anything.
+    fn scan(&mut self, kind: NodeKind, id: &str, key: &Token, block: &Block, data: &Everything) {
+        let mut targets = Vec::new();
+        if kind == NodeKind::OnAction {
+            self.scan_on_action(block, data, &mut targets);
+        } else {
+            self.scan_effect(block, data, &mut targets);
+        }
+        if targets.is_empty() {
+            return;
+        }
+        let from = self.add_node(kind, id, key);
+        for (to, via, loc) in targets {
+            if self.seen_edges.insert((from, to, via)) {
+                self.edges.push(Edge { from, to, via, loc });
+            }
+        }
+    }
+
+    fn scan_on_action(
+        &self,
+        block: &Block,
+        data: &Everything,
+        targets: &mut Vec<(usize, &'static str, Loc)>,
+    ) {
+        for Field(key, _, bv) in block.iter_fields() {
+            let via = key.as_str();
+            match (via, bv) {
+                ("events" | "first_valid", BV::Block(block)) => {
+                    for token in block.iter_values() {
+                        self.add_target(NodeKind::Event, token, via, data, targets);
+                    }
+                }
+                ("on_actions" | "first_valid_on_action", BV::Block(block)) => {
+                    for token in block.iter_values() {
+                        self.add_target(NodeKind::OnAction, token, via, data, targets);
+                    }
+                }
+                ("random_events", BV::Block(block)) => {
+                    for (_, token) in block.iter_assignments() {
+                        self.add_target(NodeKind::Event, token, via, data, targets);
+                    }
+                }
+                ("random_on_action", BV::Block(block)) => {
+                    for (_, token) in block.iter_assignments() {
+                        self.add_target(NodeKind::OnAction, token, via, data, targets);
+                    }
+                }
+                ("fallback", BV::Value(token)) => {
+                    self.add_target(NodeKind::OnAction, token, via, data, targets);
+                }
+                (_, BV::Block(block)) => self.scan_effect(block, data, targets),
+                (_, BV::Value(_)) => (),
+            }
+        }
+    }
+
+    fn scan_effect(
+        &self,
+        block: &Block,
+        data: &Everything,
+        targets: &mut Vec<(usize, &'static str, Loc)>,
+    ) {
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Field(Field(key, _, bv)) => {
+                    if key.lowercase_is("trigger_event") {
+                        match bv {
+                            BV::Value(token) => {
+                                self.add_target(
+                                    NodeKind::Event,
+                                    token,
+                                    "trigger_event",
+                                    data,
+                                    targets,
+                                );
+                            }
+                            BV::Block(block) => {
+                                if let Some(token) = block.get_field_value("id") {
+                                    self.add_target(
+                                        NodeKind::Event,
+                                        token,
+                                        "trigger_event",
+                                        data,
+                                        targets,
+                                    );
+                                }
+                                if let Some(token) = block.get_field_value("on_action") {
+                                    self.add_target(
+                                        NodeKind::OnAction,
+                                        token,
+                                        "trigger_event",
+                                        data,
+                                        targets,
+                                    );
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    self.add_target(
+                        NodeKind::ScriptedEffect,
+                        key,
+                        "scripted_effect",
+                        data,
+                        targets,
+                    );
+                    if let BV::Block(block) = bv {
+                        self.scan_effect(block, data, targets);
+                    }
+                }
+                BlockItem::Block(block) => self.scan_effect(block, data, targets),
+                BlockItem::Value(_) => (),
+            }
+        }
+    }
+
+    fn add_target(
+        &self,
+        kind: NodeKind,
+        token: &Token,
+        via: &'static str,
+        data: &Everything,
+        targets: &mut Vec<(usize, &'static str, Loc)>,
+    ) {
+        let id = match kind {
+            NodeKind::Event => event_id(token.as_str()),
+            NodeKind::ScriptedEffect => effect_id(token, data),
+            _ => token.to_string(),
+        };
+        if let Some(to) = self.find_node(kind, &id) {
+            targets.push((to, via, token.loc));
+        }
+    }
+
+    /// Iterate over the edges, as the kind and key of the item that fires and of the item that is
+    /// fired.
+    #[cfg(feature = "ck3")]
+    pub(crate) fn iter_edges(&self) -> impl Iterator<Item = (NodeKind, &Token, NodeKind, &Token)> {
+        self.edges.iter().map(|edge| {
+            let from = &self.nodes[edge.from];
+            let to = &self.nodes[edge.to];
+            (from.kind, &from.key, to.kind, &to.key)
+        })
+    }
+
+    /// The events that are not fired by anything other than themselves.
+    fn unreachable(&self) -> Vec<usize> {
+        let mut reached = vec![false; self.nodes.len()];
+        for edge in &self.edges {
+            if edge.from != edge.to {
+                reached[edge.to] = true;
+            }
+        }
+        (0..self.nodes.len())
+            .filter(|&idx| self.nodes[idx].kind == NodeKind::Event && !reached[idx])
+            .collect()
+    }
+
+    /// The groups of nodes that can fire each other in a cycle, if at least one of them is an
+    /// event. Nodes that only fire themselves are not included, because that's usually a
+    /// deliberately repeating event.
+    fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut cycles: Vec<Vec<usize>> = self
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|c| c.len() > 1 && c.iter().any(|&idx| self.nodes[idx].kind == NodeKind::Event))
+            .collect();
+        for cycle in &mut cycles {
+            cycle.sort_unstable();
+        }
+        cycles.sort_unstable();
+        cycles
+    }
+
+    /// Kosaraju's algorithm, done without recursion because the chains can be long.
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let n = self.nodes.len();
+        let mut forward = vec![Vec::new(); n];
+        let mut backward = vec![Vec::new(); n];
+        for edge in &self.edges {
+            forward[edge.from].push(edge.to);
+            backward[edge.to].push(edge.from);
+        }
+
+        // First pass: order the nodes by when their depth-first search finished.
+        let mut order = Vec::with_capacity(n);
+        let mut visited = vec![false; n];
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack = vec![(start, 0)];
+            while let Some((node, next)) = stack.last_mut() {
+                if let Some(&to) = forward[*node].get(*next) {
+                    *next += 1;
+                    if !visited[to] {
+                        visited[to] = true;
+                        stack.push((to, 0));
+                    }
+                } else {
+                    order.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+
+        // Second pass: search the reversed graph in reverse finishing order.
+        let mut components = Vec::new();
+        let mut assigned = vec![false; n];
+        for &start in order.iter().rev() {
+            if assigned[start] {
+                continue;
+            }
+            assigned[start] = true;
+            let mut component = Vec::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                component.push(node);
+                for &from in &backward[node] {
+                    if !assigned[from] {
+                        assigned[from] = true;
+                        stack.push(from);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    /// Report the events that are never fired, and the events that fire each other in a cycle.
+    pub(crate) fn report(&self) {
+        for idx in self.unreachable() {
+            let msg = "event is never triggered";
+            let info = "no event, on_action, scripted effect, or other script item fires it";
+            untidy(ErrorKey::UnreachableEvent)
+                .weak()
+                .msg(msg)
+                .info(info)
+                .loc(&self.nodes[idx].key)
+                .push();
+        }
+        for cycle in self.cycles() {
+            let first = cycle.iter().find(|&&idx| self.nodes[idx].kind == NodeKind::Event);
+            let Some(&first) = first else {
+                continue;
+            };
+            let msg = "event is part of a cycle of items that fire each other";
+            let info = "make sure the cycle has a way to end";
+            let mut report =
+                tips(ErrorKey::EventCycle).weak().msg(msg).info(info).loc(&self.nodes[first].key);
+            for &idx in cycle.iter().filter(|&&idx| idx != first) {
+                let node = &self.nodes[idx];
+                report = report.loc_msg(&node.key, format!("{} in the cycle", node.kind.as_str()));
+            }
+            report.push();
+        }
+    }
+
+    /// The nodes that are worth showing: all events, and the other items that are connected
+    /// to something.
+    fn shown(&self) -> Vec<bool> {
+        let mut shown: Vec<bool> = self.nodes.iter().map(|n| n.kind == NodeKind::Event).collect();
+        for edge in &self.edges {
+            shown[edge.from] = true;
+            shown[edge.to] = true;
+        }
+        shown
+    }
+
+    fn to_dot(&self) -> String {
+        let shown = self.shown();
+        let mut dot = String::from("digraph events {\n");
+        for (node, _) in self.nodes.iter().zip(&shown).filter(|(_, &shown)| shown) {
+            _ = writeln!(
+                dot,
+                "  {} [label={}, shape={}];",
+                dot_quote(&node.id),
+                dot_quote(node.key.as_str()),
+                node.kind.dot_shape()
+            );
+        }
+        for edge in &self.edges {
+            _ = writeln!(
+                dot,
+                "  {} -> {} [label={}];",
+                dot_quote(&self.nodes[edge.from].id),
+                dot_quote(&self.nodes[edge.to].id),
+                dot_quote(edge.via)
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let shown = self.shown();
+        let nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .zip(&shown)
+            .filter(|(_, &shown)| shown)
+            .map(|(node, _)| {
+                json!({
+                    "id": node.id,
+                    "kind": node.kind.as_str(),
+                    "key": node.key.as_str(),
+                    "path": node.key.loc.pathname(),
+                    "linenr": node.key.loc.line,
+                })
+            })
+            .collect();
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                json!({
+                    "from": self.nodes[edge.from].id,
+                    "to": self.nodes[edge.to].id,
+                    "via": edge.via,
+                    "path": edge.loc.pathname(),
+                    "linenr": edge.loc.line,
+                })
+            })
+            .collect();
+        let unreachable: Vec<_> =
+            self.unreachable().into_iter().map(|idx| &self.nodes[idx].id).collect();
+        let cycles: Vec<Vec<_>> = self
+            .cycles()
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|idx| &self.nodes[idx].id).collect())
+            .collect();
+        json!({
+            "nodes": nodes,
+            "edges": edges,
+            "unreachable": unreachable,
+            "cycles": cycles,
+        })
+    }
+
+    /// Write the graph to `path`. It's written in Graphviz DOT format if the filename ends in
+    /// `.dot` or `.gv`, and as JSON otherwise.
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let is_dot = path.extension().is_some_and(|ext| ext == "dot" || ext == "gv");
+        let output =
+            if is_dot { self.to_dot() } else { serde_json::to_string_pretty(&self.to_json())? };
+        write(path, output).with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// Event ids can have leading zeros in the number part. Return the id without them.
+pub(crate) fn event_id(key: &str) -> String {
+    if let Some((namespace, id)) = key.split_once('.') {
+        if let Ok(id) = id.parse::<u16>() {
+            return format!("{namespace}.{id}");
+        }
+    }
+    key.to_string()
+}
+
+/// Scripted effects that are defined in event files are only visible in that file, so their
+/// ids include the file's path.
+#[allow(unused_variables)] // data is only used for ck3
+fn effect_id(key: &Token, data: &Everything) -> String {
+    #[cfg(feature = "ck3")]
+    if let Some(effect) = data.events_ck3.get_effect(key) {
+        return format!("{}:{}", effect.key.loc.pathname().display(), key);
+    }
+    key.to_string()
+}
+
+pub(crate) fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", dot_escape(s))
+}
+
+/// Escape `s` for use inside a quoted Graphviz string.
+pub(crate) fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
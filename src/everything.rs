@@ -10,7 +10,6 @@ use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
 use anyhow::Result;
-#[cfg(feature = "ck3")]
 use fnv::FnvHashSet;
 use rayon::{scope, Scope};
 use strum::IntoEnumIterator;
@@ -39,6 +38,8 @@ use crate::ck3::data::{
     wars::Wars,
 };
 #[cfg(feature = "ck3")]
+use crate::ck3::family_tree::FamilyTree;
+#[cfg(feature = "ck3")]
 use crate::ck3::tables::misc::*;
 use crate::config_load::{check_for_legacy_ignore, load_filter};
 use crate::context::ScopeContext;
@@ -57,8 +58,17 @@ use crate::data::{
 };
 use crate::db::{Db, DbKind};
 use crate::dds::DdsFiles;
-use crate::fileset::{FileEntry, FileKind, Fileset};
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::event_graph::EventGraph;
+use crate::fileset::{FileEntry, FileHandler, FileKind, Fileset};
 use crate::game::Game;
+use crate::heightmap::Heightmap;
+#[cfg(feature = "imperator")]
+use crate::imperator::data::{
+    areas::{Area, Region},
+    provinces::ImperatorProvinces,
+    setup_main::SetupMain,
+};
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::misc::*;
 use crate::item::{Item, ItemLoader};
@@ -69,13 +79,15 @@ use crate::parse::json::parse_json_file;
 use crate::pdxfile::PdxFile;
 #[cfg(feature = "ck3")]
 use crate::report::err;
-use crate::report::{report, set_output_style, ErrorKey, OutputStyle, Severity};
+use crate::report::{report, set_output_style, ErrorKey, Errors, OutputStyle, Severity};
 use crate::rivers::Rivers;
+use crate::signatures::write_signatures;
 use crate::token::{Loc, Token};
+use crate::variables::Variables;
 #[cfg(feature = "vic3")]
 use crate::vic3::data::{
     buy_packages::BuyPackage, events::Vic3Events, history::History, provinces::Vic3Provinces,
-    strategic_regions::StrategicRegion, terrain::TerrainMask,
+    state_regions::StateRegion, strategic_regions::StrategicRegion, terrain::TerrainMask,
 };
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::misc::*;
@@ -116,6 +128,9 @@ pub struct Everything {
     /// Tracks specifically the .dds files, and their formats and sizes.
     pub(crate) dds: DdsFiles,
 
+    /// The heightmap.png image, shared by the checks of the other map images.
+    pub(crate) heightmap: Heightmap,
+
     /// A general database of item types. Most items go here. The ones that need special handling
     /// go in the separate databases listed below.
     pub(crate) database: Db,
@@ -142,6 +157,12 @@ pub struct Everything {
     #[cfg(feature = "vic3")]
     pub(crate) provinces_vic3: Vic3Provinces,
 
+    #[cfg(feature = "imperator")]
+    pub(crate) provinces_imperator: ImperatorProvinces,
+
+    #[cfg(feature = "imperator")]
+    pub(crate) setup_main: SetupMain,
+
     #[cfg(feature = "ck3")]
     pub(crate) province_histories: ProvinceHistories,
     #[cfg(feature = "ck3")]
@@ -190,6 +211,12 @@ pub struct Everything {
 
     #[cfg(feature = "ck3")]
     pub(crate) wars: Wars,
+
+    /// The variables and flags that are set and read, as found during validation.
+    pub(crate) variables: Variables,
+
+    /// Set if [`Everything::reload_files`] will be used after validation.
+    incremental: bool,
 }
 
 impl Everything {
@@ -239,6 +266,7 @@ impl Everything {
         Ok(Everything {
             fileset,
             dds: DdsFiles::default(),
+            heightmap: Heightmap::default(),
             config,
             #[cfg(feature = "ck3")]
             warned_defines: RwLock::new(FnvHashSet::default()),
@@ -258,6 +286,10 @@ impl Everything {
             provinces_ck3: Ck3Provinces::default(),
             #[cfg(feature = "vic3")]
             provinces_vic3: Vic3Provinces::default(),
+            #[cfg(feature = "imperator")]
+            provinces_imperator: ImperatorProvinces::default(),
+            #[cfg(feature = "imperator")]
+            setup_main: SetupMain::default(),
             #[cfg(feature = "ck3")]
             province_histories: ProvinceHistories::default(),
             #[cfg(feature = "ck3")]
@@ -292,6 +324,8 @@ impl Everything {
             history: History::default(),
             #[cfg(feature = "ck3")]
             wars: Wars::default(),
+            variables: Variables::default(),
+            incremental: false,
         })
     }
 
@@ -384,6 +418,7 @@ impl Everything {
     fn load_all_generic(&mut self) {
         scope(|s| {
             s.spawn(|_| self.fileset.handle(&mut self.dds));
+            s.spawn(|_| self.fileset.handle(&mut self.heightmap));
             s.spawn(|_| self.fileset.handle(&mut self.localization));
             s.spawn(|_| self.fileset.handle(&mut self.scripted_lists));
             s.spawn(|_| self.fileset.handle(&mut self.defines));
@@ -402,6 +437,7 @@ impl Everything {
 
     #[cfg(feature = "ck3")]
     fn load_all_ck3(&mut self) {
+        self.provinces_ck3 = Ck3Provinces::new(self.heightmap.clone());
         scope(|s| {
             s.spawn(|_| self.fileset.handle(&mut self.events_ck3));
             s.spawn(|_| self.fileset.handle(&mut self.interaction_cats));
@@ -427,12 +463,16 @@ impl Everything {
     fn load_all_vic3(&mut self) {
         self.fileset.handle(&mut self.history);
         self.fileset.handle(&mut self.events_vic3);
+        self.provinces_vic3 = Vic3Provinces::new(self.heightmap.clone());
         self.fileset.handle(&mut self.provinces_vic3);
         self.load_json(Item::TerrainMask, TerrainMask::add_json);
     }
 
     #[cfg(feature = "imperator")]
-    fn load_all_imperator(&mut self) {}
+    fn load_all_imperator(&mut self) {
+        self.fileset.handle(&mut self.provinces_imperator);
+        self.fileset.handle(&mut self.setup_main);
+    }
 
     pub fn load_all(&mut self) {
         self.load_all_generic();
@@ -446,70 +486,341 @@ impl Everything {
         }
     }
 
-    fn validate_all_generic<'a>(&'a self, s: &Scope<'a>) {
+    fn validate_generic<'a>(&'a self, s: &Scope<'a>, only: Option<&'a FnvHashSet<PathBuf>>) {
         s.spawn(|_| self.fileset.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.defines.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
-        s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.triggers.validate(self));
-        s.spawn(|_| self.effects.validate(self));
-        s.spawn(|_| self.assets.validate(self));
-        s.spawn(|_| self.gui.validate(self));
-        s.spawn(|_| self.on_actions.validate(self));
-        s.spawn(|_| self.coas.validate(self));
+        if wants(only, &self.scripted_lists.subpath()) {
+            s.spawn(|_| self.scripted_lists.validate(self));
+        }
+        if wants(only, &self.defines.subpath()) {
+            s.spawn(|_| self.defines.validate(self));
+        }
+        if wants(only, &self.scripted_modifiers.subpath()) {
+            s.spawn(|_| self.scripted_modifiers.validate(self));
+        }
+        if wants(only, &self.script_values.subpath()) {
+            s.spawn(|_| self.script_values.validate(self));
+        }
+        if wants(only, &self.triggers.subpath()) {
+            s.spawn(|_| self.triggers.validate(self));
+        }
+        if wants(only, &self.effects.subpath()) {
+            s.spawn(|_| self.effects.validate(self));
+        }
+        if wants(only, &self.assets.subpath()) {
+            s.spawn(|_| self.assets.validate(self));
+        }
+        if wants(only, &self.gui.subpath()) {
+            s.spawn(|_| self.gui.validate(self));
+        }
+        if wants(only, &self.on_actions.subpath()) {
+            s.spawn(|_| self.on_actions.validate(self));
+        }
+        if wants(only, &self.coas.subpath()) {
+            s.spawn(|_| self.coas.validate(self));
+        }
     }
 
     #[cfg(feature = "ck3")]
-    fn validate_all_ck3<'a>(&'a self, s: &Scope<'a>) {
-        s.spawn(|_| self.interaction_cats.validate(self));
-        s.spawn(|_| self.province_histories.validate(self));
-        s.spawn(|_| self.province_properties.validate(self));
-        s.spawn(|_| self.province_terrains.validate(self));
-        s.spawn(|_| self.gameconcepts.validate(self));
-        s.spawn(|_| self.titles.validate(self));
-        s.spawn(|_| self.characters.validate(self));
-        s.spawn(|_| self.traits.validate(self));
-        s.spawn(|_| self.title_history.validate(self));
-        s.spawn(|_| self.doctrines.validate(self));
-        s.spawn(|_| self.menatarmstypes.validate(self));
-        s.spawn(|_| self.data_bindings.validate(self));
-        s.spawn(|_| self.music.validate(self));
-        s.spawn(|_| self.events_ck3.validate(self));
-        s.spawn(|_| self.provinces_ck3.validate(self));
-        s.spawn(|_| self.wars.validate(self));
-        s.spawn(|_| Climate::validate_all(&self.database, self));
+    fn validate_ck3<'a>(&'a self, s: &Scope<'a>, only: Option<&'a FnvHashSet<PathBuf>>) {
+        if wants(only, &self.interaction_cats.subpath()) {
+            s.spawn(|_| self.interaction_cats.validate(self));
+        }
+        if wants(only, &self.province_histories.subpath()) {
+            s.spawn(|_| self.province_histories.validate(self));
+        }
+        if wants(only, &self.province_properties.subpath()) {
+            s.spawn(|_| self.province_properties.validate(self));
+        }
+        if wants(only, &self.province_terrains.subpath()) {
+            s.spawn(|_| self.province_terrains.validate(self));
+        }
+        if wants(only, &self.gameconcepts.subpath()) {
+            s.spawn(|_| self.gameconcepts.validate(self));
+        }
+        if wants(only, &self.titles.subpath()) {
+            s.spawn(|_| self.titles.validate(self));
+        }
+        if wants(only, &self.characters.subpath()) {
+            s.spawn(|_| self.characters.validate(self));
+        }
+        if wants(only, &self.traits.subpath()) {
+            s.spawn(|_| self.traits.validate(self));
+        }
+        if wants(only, &self.title_history.subpath()) {
+            s.spawn(|_| self.title_history.validate(self));
+        }
+        if wants(only, &self.title_history.subpath())
+            || wants(only, &self.characters.subpath())
+            || wants(only, &self.province_histories.subpath())
+            || wants(only, Path::new(Item::Bookmark.path()))
+        {
+            s.spawn(|_| crate::ck3::history::check_bookmarks(self));
+        }
+        if wants(only, &self.events_ck3.subpath()) || wants(only, &self.on_actions.subpath()) {
+            s.spawn(|_| crate::ck3::scope_flow::check_scope_flow(self));
+        }
+        if wants(only, &self.doctrines.subpath()) {
+            s.spawn(|_| self.doctrines.validate(self));
+        }
+        if wants(only, &self.menatarmstypes.subpath()) {
+            s.spawn(|_| self.menatarmstypes.validate(self));
+        }
+        if wants(only, &self.data_bindings.subpath()) {
+            s.spawn(|_| self.data_bindings.validate(self));
+        }
+        // The music files are spread out, so their subpath would match every file.
+        if only.map_or(true, |files| files.iter().any(|path| self.music.wants_path(path))) {
+            s.spawn(|_| self.music.validate(self));
+        }
+        if wants(only, &self.events_ck3.subpath()) {
+            s.spawn(|_| self.events_ck3.validate(self));
+        }
+        if wants(only, &self.provinces_ck3.subpath()) {
+            s.spawn(|_| self.provinces_ck3.validate(self));
+        }
+        if wants(only, &self.wars.subpath()) {
+            s.spawn(|_| self.wars.validate(self));
+        }
+        if wants(only, Path::new(Item::Climate.path())) {
+            s.spawn(|_| Climate::validate_all(&self.database, self));
+        }
     }
 
     #[cfg(feature = "vic3")]
-    fn validate_all_vic3<'a>(&'a self, s: &Scope<'a>) {
-        s.spawn(|_| self.events_vic3.validate(self));
-        s.spawn(|_| self.history.validate(self));
-        s.spawn(|_| self.provinces_vic3.validate(self));
-        s.spawn(|_| StrategicRegion::crosscheck(self));
-        s.spawn(|_| BuyPackage::crosscheck(self));
+    fn validate_vic3<'a>(&'a self, s: &Scope<'a>, only: Option<&'a FnvHashSet<PathBuf>>) {
+        if wants(only, &self.events_vic3.subpath()) {
+            s.spawn(|_| self.events_vic3.validate(self));
+        }
+        if wants(only, &self.history.subpath()) {
+            s.spawn(|_| self.history.validate(self));
+        }
+        if wants(only, &self.provinces_vic3.subpath()) {
+            s.spawn(|_| self.provinces_vic3.validate(self));
+        }
+        if wants(only, Path::new(Item::StrategicRegion.path())) {
+            s.spawn(|_| StrategicRegion::crosscheck(self));
+        }
+        if wants(only, Path::new(Item::StateRegion.path())) {
+            s.spawn(|_| StateRegion::crosscheck(self));
+        }
+        if wants(only, Path::new(Item::BuyPackage.path())) {
+            s.spawn(|_| BuyPackage::crosscheck(self));
+        }
     }
 
-    // Imperator one goes here when needed
+    #[cfg(feature = "imperator")]
+    fn validate_imperator<'a>(&'a self, s: &Scope<'a>, only: Option<&'a FnvHashSet<PathBuf>>) {
+        if wants(only, &self.provinces_imperator.subpath()) {
+            s.spawn(|_| self.provinces_imperator.validate(self));
+            s.spawn(|_| Area::crosscheck(self));
+            s.spawn(|_| Region::crosscheck(self));
+        }
+        if wants(only, &self.setup_main.subpath()) {
+            s.spawn(|_| self.setup_main.validate(self));
+        }
+    }
 
-    pub fn validate_all(&self) {
+    /// Validate the loaded items. If `only` is given, skip the databases that have no files in
+    /// that set, and skip the individual [`Db`] items that are not defined in one of those files.
+    fn validate(&self, only: Option<&FnvHashSet<PathBuf>>) {
         scope(|s| {
-            self.validate_all_generic(s);
+            self.validate_generic(s, only);
             match Game::game() {
                 #[cfg(feature = "ck3")]
-                Game::Ck3 => self.validate_all_ck3(s),
+                Game::Ck3 => self.validate_ck3(s, only),
                 #[cfg(feature = "vic3")]
-                Game::Vic3 => self.validate_all_vic3(s),
+                Game::Vic3 => self.validate_vic3(s, only),
                 #[cfg(feature = "imperator")]
-                Game::Imperator => (), // TODO - imperator -
+                Game::Imperator => self.validate_imperator(s, only),
             }
         });
-        self.database.validate(self);
+        self.database.validate(self, only);
+
+        if wants(only, &self.localization.subpath()) {
+            self.localization.validate_pass2(self);
+        }
+    }
+
+    pub fn validate_all(&self) {
+        self.validate(None);
+        self.variables.check();
+
+        // Clear macro map, unless the loaded items will be revalidated later. Their tokens may
+        // still refer to it.
+        if !self.incremental {
+            MACRO_MAP.clear();
+        }
+    }
+
+    /// Prepare for calling [`Everything::reload_files`] after validation.
+    /// This keeps some information around that would otherwise be dropped after validation.
+    pub fn enable_incremental(&mut self) {
+        self.incremental = true;
+    }
+
+    /// Find the [`FileEntry`] for a file on disk, if it's in one of the loaded directories.
+    pub fn file_entry(&self, fullpath: &Path) -> Option<FileEntry> {
+        self.fileset.entry_for_fullpath(fullpath)
+    }
 
-        self.localization.validate_pass2(self);
+    /// Reload the given files after they were changed, added, or deleted on disk, and revalidate
+    /// what may have been affected by the change.
+    ///
+    /// Databases that have files among the changed ones are reloaded in whole. The files that are
+    /// revalidated are the changed files, the mod files that mention any of the items defined in
+    /// the changed files (before or after the change), and the other files in the reloaded
+    /// databases.
+    ///
+    /// Returns the full paths of the revalidated files. The reports that were logged since the
+    /// last [`take_reports`](crate::take_reports) replace the earlier reports for those files.
+    /// Reports about other files should be ignored.
+    ///
+    /// This should only be called after [`Everything::enable_incremental`] and
+    /// [`Everything::validate_all`].
+    pub fn reload_files(&mut self, changed: &[FileEntry]) -> Vec<PathBuf> {
+        let changed_paths: FnvHashSet<&Path> = changed.iter().map(FileEntry::path).collect();
+        let mut words = self.keys_defined_in(&changed_paths);
+
+        let mut errors = Errors::get_mut();
+        for entry in changed {
+            errors.invalidate_file(entry.fullpath());
+        }
+        drop(errors);
+
+        self.fileset.update_entries(changed);
+        let mut dirs = Vec::new();
+        self.reload_generic(changed, &mut dirs);
+        match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => self.reload_ck3(changed, &mut dirs),
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => self.reload_vic3(changed, &mut dirs),
+            #[cfg(feature = "imperator")]
+            Game::Imperator => (),
+        }
+        words.extend(self.keys_defined_in(&changed_paths));
+
+        let mut revalidate: FnvHashSet<PathBuf> =
+            changed_paths.iter().map(|path| path.to_path_buf()).collect();
+        revalidate.extend(self.fileset.mod_files_mentioning(&words));
+        for dir in dirs {
+            for entry in self.fileset.get_files_under(&dir) {
+                revalidate.insert(entry.path().to_path_buf());
+            }
+        }
+        // The revalidation records the variable uses in these files again.
+        self.variables.forget_files(&revalidate);
+        self.validate(Some(&revalidate));
+
+        revalidate
+            .iter()
+            .filter_map(|path| {
+                self.fileset.fullpath_of(path).map(Path::to_path_buf).or_else(|| {
+                    changed.iter().find(|entry| entry.path() == path).map(|e| e.fullpath().into())
+                })
+            })
+            .collect()
+    }
+
+    /// Collect the keys of all items defined in the given files.
+    ///
+    /// Localization keys are also added with their last `_` part removed, because script often
+    /// refers to localization by adding a suffix to a key.
+    fn keys_defined_in(&self, paths: &FnvHashSet<&Path>) -> FnvHashSet<&'static str> {
+        let mut keys = FnvHashSet::default();
+        for itype in Item::iter() {
+            for key in self.iter_keys(itype) {
+                if paths.contains(key.loc.pathname()) {
+                    keys.insert(key.as_str());
+                    if itype == Item::Localization {
+                        if let Some((stem, _)) = key.as_str().rsplit_once('_') {
+                            keys.insert(stem);
+                        }
+                    }
+                }
+            }
+        }
+        keys
+    }
+
+    /// Reload the databases that have any of the changed files.
+    /// The directories or files of the reloaded databases are added to `dirs`.
+    fn reload_generic(&mut self, changed: &[FileEntry], dirs: &mut Vec<PathBuf>) {
+        let fileset = &self.fileset;
+        dirs.extend(reload_handler(fileset, &mut self.dds, changed));
+        dirs.extend(reload_handler(fileset, &mut self.heightmap, changed));
+        dirs.extend(reload_handler(fileset, &mut self.localization, changed));
+        dirs.extend(reload_handler(fileset, &mut self.scripted_lists, changed));
+        dirs.extend(reload_handler(fileset, &mut self.defines, changed));
+        dirs.extend(reload_handler(fileset, &mut self.scripted_modifiers, changed));
+        dirs.extend(reload_handler(fileset, &mut self.script_values, changed));
+        dirs.extend(reload_handler(fileset, &mut self.triggers, changed));
+        dirs.extend(reload_handler(fileset, &mut self.effects, changed));
+        dirs.extend(reload_handler(fileset, &mut self.assets, changed));
+        dirs.extend(reload_handler(fileset, &mut self.gui, changed));
+        dirs.extend(reload_handler(fileset, &mut self.on_actions, changed));
+        dirs.extend(reload_handler(fileset, &mut self.coas, changed));
+
+        let mut db_dirs = Vec::new();
+        for loader in inventory::iter::<ItemLoader> {
+            let dir = PathBuf::from(loader.itype().path());
+            if loader.for_game(Game::game())
+                && changed.iter().any(|entry| entry.path().starts_with(&dir))
+                && !db_dirs.contains(&dir)
+            {
+                db_dirs.push(dir);
+            }
+        }
+        for dir in &db_dirs {
+            self.database.remove_under(dir);
+        }
+        for loader in inventory::iter::<ItemLoader> {
+            let path = Path::new(loader.itype().path());
+            if loader.for_game(Game::game()) && db_dirs.iter().any(|dir| path.starts_with(dir)) {
+                self.load_pdx_files(loader);
+            }
+        }
+        dirs.extend(db_dirs);
+    }
+
+    #[cfg(feature = "ck3")]
+    fn reload_ck3(&mut self, changed: &[FileEntry], dirs: &mut Vec<PathBuf>) {
+        let fileset = &self.fileset;
+        dirs.extend(reload_handler(fileset, &mut self.events_ck3, changed));
+        dirs.extend(reload_handler(fileset, &mut self.interaction_cats, changed));
+        dirs.extend(reload_handler(fileset, &mut self.province_histories, changed));
+        dirs.extend(reload_handler(fileset, &mut self.province_properties, changed));
+        dirs.extend(reload_handler(fileset, &mut self.province_terrains, changed));
+        dirs.extend(reload_handler(fileset, &mut self.gameconcepts, changed));
+        dirs.extend(reload_handler(fileset, &mut self.titles, changed));
+        dirs.extend(reload_handler(fileset, &mut self.characters, changed));
+        dirs.extend(reload_handler(fileset, &mut self.traits, changed));
+        dirs.extend(reload_handler(fileset, &mut self.title_history, changed));
+        dirs.extend(reload_handler(fileset, &mut self.doctrines, changed));
+        dirs.extend(reload_handler(fileset, &mut self.menatarmstypes, changed));
+        dirs.extend(reload_handler(fileset, &mut self.data_bindings, changed));
+        dirs.extend(reload_handler(fileset, &mut self.music, changed));
+        let heightmap = self.heightmap.clone();
+        let provinces = || Ck3Provinces::new(heightmap);
+        dirs.extend(reload_handler_with(fileset, &mut self.provinces_ck3, changed, provinces));
+        dirs.extend(reload_handler(fileset, &mut self.wars, changed));
+        crate::ck3::data::buildings::Building::finalize(&mut self.database);
+    }
 
-        // Clear macro map
-        MACRO_MAP.clear();
+    #[cfg(feature = "vic3")]
+    fn reload_vic3(&mut self, changed: &[FileEntry], dirs: &mut Vec<PathBuf>) {
+        let fileset = &self.fileset;
+        dirs.extend(reload_handler(fileset, &mut self.history, changed));
+        dirs.extend(reload_handler(fileset, &mut self.events_vic3, changed));
+        let heightmap = self.heightmap.clone();
+        let provinces = || Vic3Provinces::new(heightmap);
+        dirs.extend(reload_handler_with(fileset, &mut self.provinces_vic3, changed, provinces));
+        let dir = PathBuf::from(Item::TerrainMask.path());
+        if changed.iter().any(|entry| entry.path().starts_with(&dir)) {
+            self.database.remove_under(&dir);
+            self.load_json(Item::TerrainMask, TerrainMask::add_json);
+            dirs.push(dir);
+        }
     }
 
     pub fn check_rivers(&mut self) {
@@ -518,6 +829,35 @@ impl Everything {
         rivers.validate(self);
     }
 
+    /// Write a copy of `rivers.png` with its problems marked to `path`, and a JSON list of the
+    /// problems next to it. See [`Rivers::write_debug`].
+    pub fn write_rivers_debug(&self, path: &Path) -> Result<()> {
+        let mut rivers = Rivers::default();
+        self.fileset.handle(&mut rivers);
+        rivers.write_debug(path, self)
+    }
+
+    /// Write the family trees of the characters in the history to `path`, optionally only for the
+    /// members of one dynasty or house.
+    /// The tree is written in Graphviz DOT format if `path` ends in `.dot`, and as JSON otherwise.
+    #[cfg(feature = "ck3")]
+    pub fn write_family_tree(&self, path: &Path, filter: Option<&str>) -> Result<()> {
+        FamilyTree::new(self, filter)?.write(path)
+    }
+
+    /// Write the de jure hierarchy of the landed titles to `path`.
+    /// It's written as CSV if `path` ends in `.csv`, and as JSON otherwise.
+    #[cfg(feature = "ck3")]
+    pub fn write_title_hierarchy(&self, path: &Path) -> Result<()> {
+        self.titles.write_hierarchy(path)
+    }
+
+    /// Write the inferred scope signatures of the scripted triggers, scripted effects, script
+    /// values, and scripted modifiers to `path` as JSON. Only meaningful after validation.
+    pub fn write_scope_signatures(&self, path: &Path) -> Result<()> {
+        write_signatures(self, path)
+    }
+
     #[cfg(feature = "ck3")]
     pub fn check_pod(&mut self) {
         self.province_histories.check_pod_faiths(self, &self.titles);
@@ -528,6 +868,25 @@ impl Everything {
     pub fn check_unused(&mut self) {
         self.localization.check_unused(self);
         self.fileset.check_unused_dds(self);
+        self.effects.check_unused();
+        self.triggers.check_unused();
+        self.script_values.check_unused();
+        self.scripted_modifiers.check_unused();
+        self.scripted_lists.check_unused();
+        self.on_actions.check_unused(self);
+        self.gui.check_unused();
+        self.database.check_unused();
+        self.variables.check_unused();
+    }
+
+    /// Write the graph of which events, `on_actions`, and other items fire which events to `path`,
+    /// and report the events that are never fired and the events that fire each other in a cycle.
+    /// The graph is written in Graphviz DOT format if `path` ends in `.dot`, and as JSON otherwise.
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    pub fn write_event_graph(&self, path: &Path) -> Result<()> {
+        let graph = EventGraph::new(self);
+        graph.report();
+        graph.write(path)
     }
 
     pub(crate) fn item_has_property(&self, itype: Item, key: &str, property: &str) -> bool {
@@ -625,6 +984,7 @@ impl Everything {
     fn item_exists_imperator(&self, itype: Item, key: &str) -> bool {
         match itype {
             Item::Dlc => DLC_IMPERATOR.contains(&key),
+            Item::Province => self.provinces_imperator.exists(key),
             Item::Sound => {
                 if let Some(filename) = key.strip_prefix("file://") {
                     self.fileset.exists(filename)
@@ -678,7 +1038,19 @@ impl Everything {
         match itype {
             Item::File => self.fileset.mark_used(key),
             Item::Localization => self.localization.mark_used(key),
-            _ => (),
+            Item::GuiTemplate => self.gui.mark_template_used(key),
+            Item::GuiType => self.gui.mark_type_used(key),
+            Item::OnAction => self.on_actions.mark_used(key),
+            Item::ScriptedEffect => self.effects.mark_used(key),
+            Item::ScriptedList => self.scripted_lists.mark_used(key),
+            Item::ScriptedModifier => self.scripted_modifiers.mark_used(key),
+            Item::ScriptedTrigger => self.triggers.mark_used(key),
+            Item::ScriptValue => self.script_values.mark_used(key),
+            _ => {
+                if itype.reported_if_unused() {
+                    self.database.mark_used(itype, key);
+                }
+            }
         }
     }
 
@@ -703,12 +1075,15 @@ impl Everything {
             Item::Localization => self.localization.verify_exists_implied(key, token, max_sev),
             #[cfg(feature = "ck3")]
             Item::Music => self.music.verify_exists_implied(key, token, max_sev),
-            #[cfg(any(feature = "ck3", feature = "vic3"))]
             Item::Province => match Game::game() {
                 #[cfg(feature = "ck3")]
                 Game::Ck3 => self.provinces_ck3.verify_exists_implied(key, token, max_sev),
                 #[cfg(feature = "vic3")]
                 Game::Vic3 => self.provinces_vic3.verify_exists_implied(key, token, max_sev),
+                #[cfg(feature = "imperator")]
+                Game::Imperator => {
+                    self.provinces_imperator.verify_exists_implied(key, token, max_sev);
+                }
             },
             Item::TextureFile => {
                 if let Some(entry) = self.assets.get_texture(key) {
@@ -724,7 +1099,9 @@ impl Everything {
                 }
             }
             _ => {
-                if !self.item_exists(itype, key) {
+                if self.item_exists(itype, key) {
+                    self.mark_used(itype, key);
+                } else {
                     let path = itype.path();
                     let msg = if path.is_empty() {
                         format!("unknown {itype} {key}")
@@ -891,7 +1268,10 @@ impl Everything {
 
     #[cfg(feature = "imperator")]
     fn iter_keys_imperator<'a>(&'a self, itype: Item) -> Box<dyn Iterator<Item = &Token> + 'a> {
-        Box::new(self.database.iter_keys(itype))
+        match itype {
+            Item::Province => Box::new(self.provinces_imperator.iter_keys()),
+            _ => Box::new(self.database.iter_keys(itype)),
+        }
     }
 
     pub fn iter_keys<'a>(&'a self, itype: Item) -> Box<dyn Iterator<Item = &Token> + 'a> {
@@ -930,3 +1310,41 @@ impl Everything {
         }
     }
 }
+
+/// Check if a database under `subpath` should be validated, if validation is limited to the files
+/// in `only`.
+fn wants(only: Option<&FnvHashSet<PathBuf>>, subpath: &Path) -> bool {
+    only.map_or(true, |files| files.iter().any(|path| path.starts_with(subpath)))
+}
+
+/// Reset and reload a [`FileHandler`] if any of the changed files is one of its files.
+/// Returns the paths of its files if it was reloaded.
+fn reload_handler<T: Send, H: FileHandler<T> + Default>(
+    fileset: &Fileset,
+    handler: &mut H,
+    changed: &[FileEntry],
+) -> Vec<PathBuf> {
+    reload_handler_with(fileset, handler, changed, H::default)
+}
+
+/// Like [`reload_handler`], but reset the handler to the result of `new` instead of its default.
+fn reload_handler_with<T: Send, H: FileHandler<T>>(
+    fileset: &Fileset,
+    handler: &mut H,
+    changed: &[FileEntry],
+    new: impl FnOnce() -> H,
+) -> Vec<PathBuf> {
+    if !changed.iter().any(|entry| handler.wants_path(entry.path())) {
+        return Vec::new();
+    }
+    *handler = new();
+    fileset.handle(handler);
+    // Not all handlers have a directory of their own, so ask the handler which files are its.
+    fileset
+        .get_files_under(&handler.subpath())
+        .iter()
+        .map(FileEntry::path)
+        .filter(|path| handler.wants_path(path))
+        .map(Path::to_path_buf)
+        .collect()
+}
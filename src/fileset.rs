@@ -3,6 +3,7 @@
 use std::cmp::Ordering;
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
+use std::fs::read;
 use std::path::{Path, PathBuf};
 use std::string::ToString;
 use std::sync::RwLock;
@@ -13,6 +14,7 @@ use rayon::prelude::*;
 use walkdir::WalkDir;
 
 use crate::block::Block;
+use crate::conflicts::record_file_override;
 use crate::everything::{Everything, FilesError};
 use crate::game::Game;
 use crate::item::Item;
@@ -64,6 +66,8 @@ pub struct FileEntry {
 }
 
 impl FileEntry {
+    /// # Panics
+    /// If `path` does not end in a file name.
     pub fn new(path: PathBuf, kind: FileKind, fullpath: PathBuf) -> Self {
         assert!(path.file_name().is_some());
         Self { path, kind, idx: None, fullpath }
@@ -138,6 +142,12 @@ pub trait FileHandler<T: Send>: Sync + Send {
     /// relative to the mod or vanilla root.
     fn subpath(&self) -> PathBuf;
 
+    /// Whether a file at `path`, relative to the mod or vanilla root, would be loaded by this
+    /// handler. This is decided from the path alone, without reading the file.
+    fn wants_path(&self, path: &Path) -> bool {
+        path.starts_with(self.subpath())
+    }
+
     /// This is called for each matching file, in arbitrary order.
     /// If a `T` is returned, it will be passed to `handle_file` later.
     /// Since `load_file` is executed multi-threaded while `handle_file`
@@ -327,19 +337,21 @@ impl Fileset {
         self.config = Some(config);
     }
 
-    fn should_replace(&self, path: &Path, kind: FileKind) -> bool {
+    /// Return the kind of the mod that replaces the directory `path` for files of this kind, if
+    /// any.
+    fn replaced_by(&self, path: &Path, kind: FileKind) -> Option<FileKind> {
         if kind == FileKind::Mod {
-            return false;
+            return None;
         }
         if kind < FileKind::Mod && self.the_mod.should_replace(path) {
-            return true;
+            return Some(FileKind::Mod);
         }
         for loaded_mod in &self.loaded_mods {
             if kind < loaded_mod.kind && loaded_mod.should_replace(path) {
-                return true;
+                return Some(loaded_mod.kind);
             }
         }
-        false
+        None
     }
 
     fn scan(&mut self, path: &Path, kind: FileKind) -> Result<(), walkdir::Error> {
@@ -354,7 +366,8 @@ impl Fileset {
                 continue;
             }
             let inner_dir = inner_path.parent().unwrap_or_else(|| Path::new(""));
-            if self.should_replace(inner_dir, kind) {
+            if let Some(winner) = self.replaced_by(inner_dir, kind) {
+                record_file_override(inner_path, kind, winner, true);
                 continue;
             }
             self.files.push(FileEntry::new(
@@ -425,6 +438,7 @@ impl Fileset {
         for entry in self.files.drain(..) {
             if let Some(prev) = self.ordered_files.last_mut() {
                 if entry.path == prev.path {
+                    record_file_override(&entry.path, prev.kind, entry.kind, false);
                     *prev = entry;
                 } else {
                     self.ordered_files.push(entry);
@@ -442,6 +456,114 @@ impl Fileset {
         }
     }
 
+    /// Find the `FileEntry` for a file on disk, based on which root directory it's under.
+    /// The file does not need to exist; this can be used for files that were just deleted.
+    pub fn entry_for_fullpath(&self, fullpath: &Path) -> Option<FileEntry> {
+        for (root, kind) in self.roots() {
+            if let Ok(path) = fullpath.strip_prefix(root) {
+                if path.file_name().is_some() {
+                    return Some(FileEntry::new(path.to_path_buf(), kind, fullpath.to_path_buf()));
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the root directories that files are loaded from, with the ones that take
+    /// precedence first.
+    fn roots(&self) -> Vec<(&Path, FileKind)> {
+        let mut roots = vec![(self.the_mod.root(), FileKind::Mod)];
+        for loaded_mod in self.loaded_mods.iter().rev().chain(self.loaded_dlcs.iter().rev()) {
+            roots.push((loaded_mod.root(), loaded_mod.kind()));
+        }
+        roots.extend(self.vanilla_root.as_deref().map(|root| (root, FileKind::Vanilla)));
+        roots.extend(self.jomini_root.as_deref().map(|root| (root, FileKind::Jomini)));
+        roots.extend(self.clausewitz_root.as_deref().map(|root| (root, FileKind::Clausewitz)));
+        roots
+    }
+
+    /// Find the file on disk that `path` would come from if there were no file of kind `kind`
+    /// or above.
+    fn overridden_entry(&self, path: &Path, kind: FileKind) -> Option<FileEntry> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        self.roots()
+            .into_iter()
+            .filter(|(_, root_kind)| *root_kind < kind)
+            .find(|(root, root_kind)| {
+                root.join(path).is_file() && self.replaced_by(dir, *root_kind).is_none()
+            })
+            .map(|(root, root_kind)| FileEntry::new(path.to_path_buf(), root_kind, root.join(path)))
+    }
+
+    /// Update the list of files after the given files were added, changed, or removed on disk.
+    ///
+    /// Files that now exist are added if they override what was there before. Files that no
+    /// longer exist are removed, or replaced by the file that they overrode.
+    pub fn update_entries(&mut self, changed: &[FileEntry]) {
+        for entry in changed {
+            let pos = self.ordered_files.binary_search_by(|fe| fe.path.cmp(&entry.path));
+            if entry.fullpath.is_file() {
+                match pos {
+                    Ok(idx) if self.ordered_files[idx].kind > entry.kind => (),
+                    Ok(idx) => {
+                        let mut entry = entry.clone();
+                        entry.store_in_pathtable();
+                        self.ordered_files[idx] = entry;
+                    }
+                    Err(idx) => {
+                        let mut entry = entry.clone();
+                        entry.store_in_pathtable();
+                        let token =
+                            Token::new(&entry.filename().to_string_lossy(), (&entry).into());
+                        self.filename_tokens.push(token);
+                        self.filenames.insert(entry.path.clone());
+                        self.ordered_files.insert(idx, entry);
+                    }
+                }
+            } else if let Ok(idx) = pos {
+                if self.ordered_files[idx].kind == entry.kind {
+                    self.filename_tokens.retain(|token| token.loc.pathname() != entry.path);
+                    if let Some(mut overridden) = self.overridden_entry(&entry.path, entry.kind) {
+                        overridden.store_in_pathtable();
+                        let token = Token::new(
+                            &overridden.filename().to_string_lossy(),
+                            (&overridden).into(),
+                        );
+                        self.filename_tokens.push(token);
+                        self.ordered_files[idx] = overridden;
+                    } else {
+                        self.ordered_files.remove(idx);
+                        self.filenames.remove(&entry.path);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return the paths of the mod files (including loaded mods) that contain any of the given
+    /// words. Words are runs of the characters that can make up a key in script or localization.
+    pub fn mod_files_mentioning(&self, words: &FnvHashSet<&str>) -> Vec<PathBuf> {
+        self.ordered_files
+            .par_iter()
+            .filter(|entry| matches!(entry.kind, FileKind::LoadedMod(_) | FileKind::Mod))
+            .filter(|entry| {
+                read(&entry.fullpath).is_ok_and(|bytes| {
+                    String::from_utf8_lossy(&bytes)
+                        .split(|c: char| !(c.is_alphanumeric() || "_.-:/".contains(c)))
+                        .any(|word| words.contains(word))
+                })
+            })
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    /// Return the full path of the file that is used for this path, if there is one.
+    pub fn fullpath_of(&self, path: &Path) -> Option<&Path> {
+        let idx =
+            self.ordered_files.binary_search_by(|entry| entry.path.as_path().cmp(path)).ok()?;
+        Some(&self.ordered_files[idx].fullpath)
+    }
+
     pub fn get_files_under<'a>(&'a self, subpath: &'a Path) -> &[FileEntry] {
         let start = self.ordered_files.partition_point(|entry| entry.path < subpath);
         let end = start
@@ -77,6 +77,18 @@ impl Game {
         #[cfg(all(feature = "imperator", any(feature = "ck3", feature = "vic3")))]
         return GAME.get() == Some(&Game::Imperator);
     }
+
+    /// The name of the validator for the game we are validating, such as `ck3-tiger`.
+    pub(crate) fn tool_name() -> &'static str {
+        match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => "ck3-tiger",
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => "vic3-tiger",
+            #[cfg(feature = "imperator")]
+            Game::Imperator => "imperator-tiger",
+        }
+    }
 }
 
 bitflags! {
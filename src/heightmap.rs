@@ -0,0 +1,131 @@
+//! Support for checking the other map images against `heightmap.png`.
+//!
+//! The heightmap is a grayscale image where each pixel value is a height. The defines in
+//! `NJominiMap` say how those values translate to heights in the world, and where the water
+//! surface is.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use fnv::{FnvHashMap, FnvHashSet};
+use image::GrayImage;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use image::{Rgb, RgbImage};
+
+use crate::block::BV;
+use crate::everything::Everything;
+use crate::fileset::{FileEntry, FileHandler};
+use crate::report::{err, ErrorKey};
+
+/// The `map_data/heightmap.png` image. It's decoded once, and cloning this only clones a
+/// reference to the image, so that everything that checks against it can share it.
+#[derive(Clone, Debug, Default)]
+pub struct Heightmap(Option<(FileEntry, Arc<GrayImage>)>);
+
+impl Heightmap {
+    /// Return the heightmap's file entry, for error reporting, together with the image.
+    pub fn get(&self) -> Option<(&FileEntry, &GrayImage)> {
+        self.0.as_ref().map(|(entry, img)| (entry, &**img))
+    }
+}
+
+impl FileHandler<GrayImage> for Heightmap {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from("map_data/heightmap.png")
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<GrayImage> {
+        load_heightmap(entry)
+    }
+
+    fn handle_file(&mut self, entry: &FileEntry, img: GrayImage) {
+        self.0 = Some((entry.clone(), Arc::new(img)));
+    }
+}
+
+/// Load `heightmap.png` as a grayscale image, or report why it can't be loaded.
+fn load_heightmap(entry: &FileEntry) -> Option<GrayImage> {
+    match image::open(entry.fullpath()) {
+        Ok(img) => Some(img.into_luma8()),
+        Err(e) => {
+            let msg = format!("could not read `{}`: {e:#}", entry.path().display());
+            err(ErrorKey::ReadError).msg(msg).loc(entry).push();
+            None
+        }
+    }
+}
+
+/// Return the highest heightmap value that is still under water, according to the
+/// `NJominiMap|WATER_HEIGHT` and `NJominiMap|WORLD_EXTENTS_Y` defines.
+/// Returns `None` if those defines are missing or not numbers.
+pub fn water_level(data: &Everything) -> Option<u8> {
+    let define = |key| data.defines.get_bv(key).and_then(BV::get_value)?.get_number();
+    let water_height = define("NJominiMap|WATER_HEIGHT")?;
+    let world_height = define("NJominiMap|WORLD_EXTENTS_Y")?;
+    if world_height <= 0.0 {
+        return None;
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Some((water_height / world_height * 255.0).clamp(0.0, 255.0) as u8)
+}
+
+/// Report if the image in `entry` does not have the same width and height as `other`.
+/// Returns true if they match.
+pub fn check_same_size(
+    entry: &FileEntry,
+    dimensions: (u32, u32),
+    other: &FileEntry,
+    other_dimensions: (u32, u32),
+) -> bool {
+    if dimensions == other_dimensions {
+        return true;
+    }
+    let msg = format!(
+        "{} is {}x{} but {} is {}x{}",
+        entry.filename().to_string_lossy(),
+        dimensions.0,
+        dimensions.1,
+        other.filename().to_string_lossy(),
+        other_dimensions.0,
+        other_dimensions.1,
+    );
+    let info = "all the map images must have the same size";
+    err(ErrorKey::ImageFormat).msg(msg).info(info).loc(entry).loc_msg(other, "other image").push();
+    false
+}
+
+/// How many pixels of a province are at each height.
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+#[derive(Clone, Debug)]
+pub struct HeightHistogram([u32; 256]);
+
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+impl HeightHistogram {
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    /// The number of pixels that are higher than `level`.
+    pub fn count_above(&self, level: u8) -> u32 {
+        self.0[usize::from(level) + 1..].iter().sum()
+    }
+}
+
+/// Collect the heights of the pixels of each of the given province colors.
+/// The images must already be known to be the same size.
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+pub fn height_histograms(
+    provinces: &RgbImage,
+    heightmap: &GrayImage,
+    colors: &FnvHashSet<Rgb<u8>>,
+) -> FnvHashMap<Rgb<u8>, HeightHistogram> {
+    let mut histograms = FnvHashMap::default();
+    for (color, height) in provinces.pixels().zip(heightmap.pixels()) {
+        if colors.contains(color) {
+            let histogram = histograms.entry(*color).or_insert_with(|| HeightHistogram([0; 256]));
+            histogram.0[usize::from(height.0[0])] += 1;
+        }
+    }
+    histograms
+}
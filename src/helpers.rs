@@ -1,10 +1,12 @@
 //! Miscellaneous convenience functions.
 use bimap::BiHashMap;
-use fnv::FnvBuildHasher;
+use fnv::{FnvBuildHasher, FnvHashSet};
 
 use std::fmt::{Display, Formatter};
+use std::sync::RwLock;
 
-use crate::report::{tips, warn, ErrorKey};
+use crate::item::Item;
+use crate::report::{tips, warn, warn_abbreviated, warn_header, will_maybe_log, ErrorKey};
 use crate::token::Token;
 
 /// Warns about a redefinition of a database item
@@ -86,6 +88,56 @@ pub fn stringify_list(v: &[&str]) -> String {
     format!("{}", Choices::AndChoices(v))
 }
 
+/// If an explanation of why an item was removed or replaced just names its replacement, return
+/// that name. It recognizes a bare name, and forms like "replaced by `name`" and "replaced with
+/// name". Anything more involved, such as a name with further explanation, is not recognized.
+pub fn renamed_to(explanation: &str) -> Option<&str> {
+    let name = explanation
+        .strip_prefix("replaced by ")
+        .or_else(|| explanation.strip_prefix("replaced with "))
+        .unwrap_or(explanation);
+    let name = name.strip_prefix('`').and_then(|n| n.strip_suffix('`')).unwrap_or(name);
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then_some(name)
+}
+
+/// The keys of the items of one type that are referred to somewhere, for the `--unused` checks.
+/// Keys can be marked from several threads at once during validation.
+#[derive(Debug, Default)]
+pub struct UsedKeys(RwLock<FnvHashSet<String>>);
+
+impl UsedKeys {
+    pub fn mark(&self, key: &str) {
+        if !self.contains(key) {
+            self.0.write().unwrap().insert(key.to_string());
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.read().unwrap().contains(key)
+    }
+}
+
+/// Print a list of the unused items of type `itype`, in the same abbreviated format as the
+/// unused localization. Nothing is printed if `itype` opts out of the `--unused` checks.
+pub fn report_unused_items(itype: Item, mut unused: Vec<&Token>) {
+    if !itype.reported_if_unused() {
+        return;
+    }
+    unused.sort_unstable_by_key(|key| key.loc);
+    let mut printed_header = false;
+    for key in unused {
+        if !printed_header && will_maybe_log(key, ErrorKey::UnusedItem) {
+            warn_header(ErrorKey::UnusedItem, &format!("Unused items - {itype}:\n"));
+            printed_header = true;
+        }
+        warn_abbreviated(key, ErrorKey::UnusedItem);
+    }
+    if printed_header {
+        warn_header(ErrorKey::UnusedItem, "");
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TriBool {
     True,
@@ -0,0 +1,110 @@
+use fnv::FnvHashMap;
+
+use crate::block::Block;
+use crate::db::{Db, DbKind};
+use crate::everything::Everything;
+use crate::game::GameFlags;
+use crate::item::{Item, ItemLoader};
+use crate::report::{err, ErrorKey};
+use crate::token::Token;
+use crate::validator::Validator;
+
+#[derive(Clone, Debug)]
+pub struct Area {}
+
+inventory::submit! {
+    ItemLoader::Normal(GameFlags::Imperator, Item::Area, Area::add)
+}
+
+impl Area {
+    pub fn add(db: &mut Db, key: Token, block: Block) {
+        db.add(Item::Area, key, block, Box::new(Self {}));
+    }
+
+    pub fn crosscheck(data: &Everything) {
+        // Each province must be part of one and only one area.
+        let mut seen = FnvHashMap::default();
+        for (key, block) in data.database.iter_key_block(Item::Area) {
+            for token in block.get_field_list("provinces").unwrap_or_default() {
+                if let Some(&other) = seen.get(token.as_str()) {
+                    let msg = format!("province {token} is part of more than one area");
+                    err(ErrorKey::Conflict)
+                        .strong()
+                        .msg(msg)
+                        .loc(&token)
+                        .loc_msg(other, "the other one")
+                        .push();
+                } else {
+                    seen.insert(token.as_str(), key);
+                }
+            }
+        }
+        let mut missing: Vec<&Token> = data
+            .provinces_imperator
+            .iter_land_keys()
+            .filter(|key| !seen.contains_key(key.as_str()))
+            .collect();
+        missing.sort_unstable_by_key(|key| key.loc);
+        for key in missing {
+            let msg = format!("province {key} is not part of any area");
+            err(ErrorKey::Validation).strong().msg(msg).loc(key).push();
+        }
+    }
+}
+
+impl DbKind for Area {
+    fn validate(&self, _key: &Token, block: &Block, data: &Everything) {
+        let mut vd = Validator::new(block, data);
+
+        vd.req_field("provinces");
+        vd.field_list_items("provinces", Item::Province);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Region {}
+
+inventory::submit! {
+    ItemLoader::Normal(GameFlags::Imperator, Item::Region, Region::add)
+}
+
+impl Region {
+    pub fn add(db: &mut Db, key: Token, block: Block) {
+        db.add(Item::Region, key, block, Box::new(Self {}));
+    }
+
+    pub fn crosscheck(data: &Everything) {
+        // Each area must be part of one and only one region.
+        let mut seen = FnvHashMap::default();
+        for (key, block) in data.database.iter_key_block(Item::Region) {
+            for token in block.get_field_list("areas").unwrap_or_default() {
+                if let Some(&other) = seen.get(token.as_str()) {
+                    let msg = format!("area {token} is part of more than one region");
+                    err(ErrorKey::Conflict)
+                        .strong()
+                        .msg(msg)
+                        .loc(&token)
+                        .loc_msg(other, "the other one")
+                        .push();
+                } else {
+                    seen.insert(token.as_str(), key);
+                }
+            }
+        }
+        for key in data.database.iter_keys(Item::Area) {
+            if !seen.contains_key(key.as_str()) {
+                let msg = format!("area {key} is not part of any region");
+                err(ErrorKey::Validation).strong().msg(msg).loc(key).push();
+            }
+        }
+    }
+}
+
+impl DbKind for Region {
+    fn validate(&self, _key: &Token, block: &Block, data: &Everything) {
+        let mut vd = Validator::new(block, data);
+
+        vd.req_field("areas");
+        vd.field_list_items("areas", Item::Area);
+    }
+}
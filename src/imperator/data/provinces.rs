@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use fnv::FnvHashSet;
+
+use crate::block::Block;
+use crate::everything::Everything;
+use crate::fileset::{FileEntry, FileHandler};
+use crate::province_map::{load_file, FileContent, ProvId, ProvinceMap};
+use crate::report::{err, ErrorKey, Severity};
+use crate::token::Token;
+
+#[derive(Debug, Default)]
+pub struct ImperatorProvinces {
+    /// The provinces and adjacencies from definition.csv, adjacencies.csv, and provinces.png.
+    map: ProvinceMap,
+
+    /// The `sea_zones`, `river_provinces`, and `lakes` from default.map.
+    water: FnvHashSet<ProvId>,
+
+    /// The `impassable_terrain` from default.map.
+    impassable: FnvHashSet<ProvId>,
+}
+
+impl ImperatorProvinces {
+    fn load_default_map(&mut self, block: &Block) {
+        enum Expecting<'a> {
+            Range(&'a Token),
+            List(&'a Token),
+            Nothing,
+        }
+
+        let mut expecting = Expecting::Nothing;
+        for item in block.iter_items() {
+            let mut provids = Vec::new();
+            match expecting {
+                Expecting::Nothing => {
+                    if let Some((key, token)) = item.expect_assignment() {
+                        if key.is("sea_zones")
+                            || key.is("river_provinces")
+                            || key.is("lakes")
+                            || key.is("impassable_terrain")
+                            || key.is("uninhabitable")
+                        {
+                            if token.is("LIST") {
+                                expecting = Expecting::List(key);
+                            } else if token.is("RANGE") {
+                                expecting = Expecting::Range(key);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                Expecting::Range(key) => {
+                    if let Some(block) = item.expect_block() {
+                        let vec: Vec<&Token> = block.iter_values().collect();
+                        let range = if vec.len() == 2 {
+                            vec[0].as_str().parse::<ProvId>().ok().zip(vec[1].as_str().parse().ok())
+                        } else {
+                            None
+                        };
+                        if let Some((from, to)) = range {
+                            provids.extend((from..=to).map(|provid| (key, provid)));
+                        } else {
+                            err(ErrorKey::Validation).msg("invalid RANGE").loc(block).push();
+                        }
+                    }
+                }
+                Expecting::List(key) => {
+                    if let Some(block) = item.expect_block() {
+                        for token in block.iter_values() {
+                            if let Ok(provid) = token.as_str().parse::<ProvId>() {
+                                provids.push((key, provid));
+                            } else {
+                                let msg = "invalid LIST item";
+                                err(ErrorKey::Validation).msg(msg).loc(token).push();
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            expecting = Expecting::Nothing;
+            for (key, provid) in provids {
+                if key.is("impassable_terrain") {
+                    self.impassable.insert(provid);
+                } else if !key.is("uninhabitable") {
+                    self.water.insert(provid);
+                }
+            }
+        }
+    }
+
+    pub fn verify_exists_implied(&self, key: &str, item: &Token, max_sev: Severity) {
+        self.map.verify_exists_implied(key, item, max_sev);
+    }
+
+    pub fn exists(&self, key: &str) -> bool {
+        self.map.exists(key)
+    }
+
+    pub(crate) fn is_water(&self, provid: ProvId) -> bool {
+        self.water.contains(&provid)
+    }
+
+    fn is_land(&self, provid: ProvId) -> bool {
+        !self.water.contains(&provid) && !self.impassable.contains(&provid)
+    }
+
+    pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
+        self.map.iter_keys()
+    }
+
+    /// Iterate over the provinces that should be part of an area: the ones that are neither water
+    /// nor impassable terrain. Province 0 is never used by the game.
+    pub fn iter_land_keys(&self) -> impl Iterator<Item = &Token> {
+        self.map
+            .iter_provinces()
+            .filter(|item| item.id != 0 && self.is_land(item.id))
+            .map(|item| &item.key)
+    }
+
+    pub fn validate(&self, _data: &Everything) {
+        // TODO: check the adjacency types. Not sure which types Imperator supports.
+        for item in self.map.iter_adjacencies() {
+            item.validate(
+                &self.map,
+                |provid| self.is_water(provid),
+                "`sea_zones`, `river_provinces`, or `lakes`",
+            );
+        }
+    }
+}
+
+impl FileHandler<FileContent> for ImperatorProvinces {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from("map_data")
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<FileContent> {
+        load_file(entry)
+    }
+
+    fn handle_file(&mut self, entry: &FileEntry, content: FileContent) {
+        if let Some(block) = self.map.handle_file(entry, content) {
+            self.load_default_map(&block);
+        }
+    }
+
+    fn finalize(&mut self) {
+        let Self { map, water, impassable } = self;
+        map.finalize(|provid| !water.contains(&provid) && !impassable.contains(&provid));
+    }
+}
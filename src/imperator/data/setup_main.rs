@@ -1,59 +1,77 @@
+use std::path::PathBuf;
+
+use fnv::FnvHashMap;
+
 use crate::block::Block;
-use crate::context::ScopeContext;
-use crate::db::{Db, DbKind};
-use crate::effect::validate_effect;
 use crate::everything::Everything;
-use crate::game::GameFlags;
-use crate::item::{Item, ItemLoader};
-use crate::scopes::Scopes;
+use crate::fileset::{FileEntry, FileHandler};
+use crate::item::Item;
+use crate::modif::{validate_modifs, ModifKinds};
+use crate::pdxfile::PdxFile;
+use crate::report::{err, ErrorKey};
 use crate::token::Token;
-use crate::tooltipped::Tooltipped;
-use crate::trigger::validate_trigger;
-use crate::validate::validate_modifiers_with_base;
 use crate::validator::Validator;
-use crate::modif::{validate_modifs, ModifKinds};
-use crate::imperator::tables::misc::{DLC_IMPERATOR};
 
-#[derive(Clone, Debug)]
-pub struct SetupMain {}
+/// The files in `setup/main/` describe the state of the world at game start: the countries and
+/// what they own, the families, diplomacy, treasures, great works, and the province setup.
+///
+/// TODO: country tags, modifiers, and the great work items are not loaded for Imperator yet, so
+/// the fields that refer to them are accepted without checking.
+#[derive(Clone, Debug, Default)]
+pub struct SetupMain {
+    blocks: Vec<Block>,
+}
 
 impl SetupMain {
-    pub fn add(db: &mut Db, key: Token, block: Block) {
-        db.add(Item::Decision, key, block, Box::new(Self {}));
+    pub fn validate(&self, data: &Everything) {
+        for block in &self.blocks {
+            validate_setup(block, data);
+        }
+        self.check_owners();
     }
-}
 
-impl DbKind for SetupMain {
-    fn validate(&self, key: &Token, block: &Block, data: &Everything) {
-        let mut vd = Validator::new(block, data);
+    /// Each province can be owned by only one country.
+    fn check_owners(&self) {
+        let mut owners: FnvHashMap<&str, &Token> = FnvHashMap::default();
 
-        vd.field_validated_block("treasure_manager", |block, data| {
-            validate_treasures(block, data);
-        });
-        vd.field_validated_block("family", |block, data| {
-            validate_families(block, data);
-        });
-        vd.field_validated_block("diplomacy", |block, data| {
-            validate_diplomacy(block, data);
-        });
-        vd.field_validated_block("provinces", |block, data| {
-            validate_provinces(block, data);
-        });
-        vd.field_validated_block("road_network", |block, data| {
-            validate_roads(block, data);
-        });
-        vd.field_validated_block("country", |block, data| {
-            validate_countries(block, data);
-        });
-        vd.field_validated_block("trade", |block, data| {
-            validate_trade(block, data);
-        });
-        vd.field_validated_block("great_work_manager", |block, data| {
-            validate_great_works(block, data);
-        });
+        for block in &self.blocks {
+            let Some(block) = block.get_field_block("country") else { continue };
+            let Some(block) = block.get_field_block("countries") else { continue };
+            for (tag, block) in block.iter_definitions() {
+                let Some(block) = block.get_field_block("own_control_core") else { continue };
+                for token in block.iter_values() {
+                    if let Some(other) = owners.get(token.as_str()) {
+                        let msg = format!("province {token} is owned by both {other} and {tag}");
+                        err(ErrorKey::Conflict)
+                            .msg(msg)
+                            .loc(token)
+                            .loc_msg(*other, "the other owner")
+                            .push();
+                    } else {
+                        owners.insert(token.as_str(), tag);
+                    }
+                }
+            }
+        }
     }
 }
 
+fn validate_setup(block: &Block, data: &Everything) {
+    let mut vd = Validator::new(block, data);
+
+    vd.field_validated_block("treasure_manager", validate_treasures);
+    vd.field_validated_block("family", validate_families);
+    vd.field_validated_block("diplomacy", validate_diplomacy);
+    vd.field_validated_block("provinces", validate_provinces);
+    vd.field_validated_block("road_network", validate_roads);
+    vd.field_validated_block("country", validate_countries);
+    vd.field_validated_block("trade", validate_trade);
+    vd.field_validated_block("great_work_manager", validate_great_works);
+
+    // TODO: validate the characters and the other sections
+    vd.no_warn_remaining();
+}
+
 fn validate_treasures(block: &Block, data: &Everything) {
     let mut vd = Validator::new(block, data);
     vd.field_validated_block("database", |block, data| {
@@ -61,10 +79,11 @@ fn validate_treasures(block: &Block, data: &Everything) {
         for (_, block) in vd.integer_blocks() {
             let mut vd = Validator::new(block, data);
             vd.field_item("key", Item::Localization);
-            vd.choice(DLC_IMPERATOR);
-            vd.field("icon");
+            vd.field_value("icon");
             vd.multi_field_validated_block("state_modifier", |block, data| {
-                validate_modifs(block, data, ModifKinds::Country | ModifKinds::Province | ModifKinds::State, vd);
+                let vd = Validator::new(block, data);
+                let kinds = ModifKinds::Country | ModifKinds::Province | ModifKinds::State;
+                validate_modifs(block, data, kinds, vd);
             });
         }
     });
@@ -77,42 +96,45 @@ fn validate_families(block: &Block, data: &Everything) {
         for (_, block) in vd.integer_blocks() {
             let mut vd = Validator::new(block, data);
             vd.field_item("key", Item::Localization);
-            vd.field_item("owner", Item::Country); // can be any country tag declared in setup
-            vd.field_item("culture", Item::Country);
+            // can be any country tag declared in setup
+            vd.field_value("owner");
+            vd.field_item("culture", Item::Culture);
             vd.field_integer("prestige");
             vd.field_integer("color");
         }
     });
 }
+
 fn validate_diplomacy(block: &Block, data: &Everything) {
     let mut vd = Validator::new(block, data);
     vd.multi_field_validated_block("defensive_league", |block, data| {
         let mut vd = Validator::new(block, data);
-        vd.multi_field_item("member", Item::Country);
+        vd.multi_field_value("member");
     });
     for field in &["dependency", "guarantee", "alliance"] {
         vd.multi_field_validated_block(field, |block, data| {
             let mut vd = Validator::new(block, data);
-            vd.field_item("first", Item::Country);
-            vd.field_item("second", Item::Country);
-            if field == "dependency" {
+            vd.field_value("first");
+            vd.field_value("second");
+            if *field == "dependency" {
                 vd.field_item("subject_type", Item::SubjectType);
             }
         });
     }
-    vd.unknown_block_fields(|key, block| {
-        data.verify_exists(Item::Country, key);
+    vd.unknown_block_fields(|_, block| {
         let mut vd = Validator::new(block, data);
-        vd.unknown_value_fields(|key, value| {
-            data.verify_exists(Item::Country, key);
+        vd.unknown_block_fields(|_, block| {
+            let mut vd = Validator::new(block, data);
             vd.field_bool("trade_access");
         });
     });
 }
+
 fn validate_provinces(block: &Block, data: &Everything) {
     let mut vd = Validator::new(block, data);
 
-    for (_, block) in vd.integer_blocks() {
+    for (key, block) in vd.integer_blocks() {
+        data.verify_exists(Item::Province, key);
         let mut vd = Validator::new(block, data);
         vd.field_validated_block("treasure_slots", |block, data| {
             let mut vd = Validator::new(block, data);
@@ -123,7 +145,7 @@ fn validate_provinces(block: &Block, data: &Everything) {
         });
         vd.field_validated_block("modifier", |block, data| {
             let mut vd = Validator::new(block, data);
-            vd.multi_field_item("modifier", Item::Modifier);
+            vd.multi_field_value("modifier");
             vd.field_bool("always");
         });
         vd.field_integer("great_work");
@@ -133,8 +155,9 @@ fn validate_provinces(block: &Block, data: &Everything) {
         });
     }
 }
+
 fn validate_roads(block: &Block, data: &Everything) {
-    // This is just 2 provinces connected like
+    // This is just pairs of connected provinces, like
     /*
         1 = 2
         100 = 110
@@ -142,9 +165,11 @@ fn validate_roads(block: &Block, data: &Everything) {
     let mut vd = Validator::new(block, data);
 
     vd.unknown_value_fields(|key, value| {
-        value.expect_number();
+        data.verify_exists(Item::Province, key);
+        data.verify_exists(Item::Province, value);
     });
 }
+
 fn validate_countries(block: &Block, data: &Everything) {
     /*
         Example:
@@ -156,31 +181,31 @@ fn validate_countries(block: &Block, data: &Everything) {
             diplomatic_stance=<diplo stance>
             primary_culture = <culture>
             religion = <religion>
-            
+
             technology={
                 military_tech={ level=2 progress=0 }
                 civic_tech={ level=2 progress=0 }
                 oratory_tech={ level=2 progress=0 }
                 religious_tech= { level=2 progress=0  }
             }
-            
+
             capital = 1
             pantheon = {
-            
+
             { deity = 1 }
-            { deity = 2 } 
-            { deity = 6 } 
+            { deity = 2 }
+            { deity = 6 }
             { deity = 4 }
-            
+
             }
             is_antagonist = yes
-        
+
             treasures = { 201 61 39 }
-        
+
             own_control_core =  {
                 1 2 3 4 5 6 7 8 15 16 18 19 20 24 25 26 27 31 37 40 36 39 50
             }
-            
+
             <law_name> = yes
             #professional_soldiers = yes
             #organized_recruitment = yes
@@ -192,28 +217,34 @@ fn validate_countries(block: &Block, data: &Everything) {
     vd.field_validated_block("countries", |block, data| {
         let mut vd = Validator::new(block, data);
 
-        vd.validated_blocks(|block, data| {
+        vd.unknown_block_fields(|_, block| {
             let mut vd = Validator::new(block, data);
 
-            vd.field_item("government", Item::Government);
+            vd.field_item("government", Item::GovernmentType);
             vd.field_item("diplomatic_stance", Item::DiplomaticStance);
             vd.field_item("religion", Item::Religion);
-            vd.field_item("culture", Item::Culture);
+            vd.field_item("primary_culture", Item::Culture);
 
-            vd.field_integer("family");
-            vd.field_integer("capital");
+            for token in vd.multi_field_value("family") {
+                token.expect_integer();
+            }
+            vd.field_item("capital", Item::Province);
             vd.field_bool("is_antagonist");
 
             vd.field_validated_block("treasures", |block, data| {
                 let mut vd = Validator::new(block, data);
                 vd.req_tokens_integers_at_least(1);
-            )};
+            });
             vd.field_validated_block("own_control_core", |block, data| {
                 let mut vd = Validator::new(block, data);
                 vd.req_tokens_integers_at_least(1);
+                for token in block.iter_values() {
+                    data.verify_exists(Item::Province, token);
+                }
             });
 
             // TODO - laws, deities, and technology
+            vd.no_warn_remaining();
         });
     });
 }
@@ -224,8 +255,8 @@ fn validate_trade(block: &Block, data: &Everything) {
     vd.multi_field_validated_block("route", |block, data| {
         let mut vd = Validator::new(block, data);
 
-        vd.field_integer("from");
-        vd.field_integer("to");
+        vd.field_item("from", Item::Province);
+        vd.field_item("to", Item::Province);
         vd.field_item("trade_goods", Item::TradeGood);
     });
 }
@@ -256,20 +287,20 @@ fn validate_great_works(block: &Block, data: &Everything) {
                 }
             }
         }
-
-        How to match the loose brackets in the great_work_components and great_work_effect_selections blocks???
     */
     let mut vd = Validator::new(block, data);
     vd.field_validated_block("great_works_database", |block, data| {
         let mut vd = Validator::new(block, data);
         for (_, block) in vd.integer_blocks() {
+            let mut vd = Validator::new(block, data);
             vd.field_bool("ancient_wonder");
-            vd.field("key");
-            vd.field("great_work_state_completed");
-            vd.field_item("great_work_category", Item::GreatWorkCategory);
+            vd.field_value("key");
+            vd.field_value("great_work_state");
+            vd.field_value("great_work_category");
             vd.field_date("finished_date");
             // Validate name
             vd.field_validated_block("great_work_name", |block, data| {
+                let mut vd = Validator::new(block, data);
                 vd.field_item("name", Item::Localization);
             });
             // Validate components
@@ -278,8 +309,8 @@ fn validate_great_works(block: &Block, data: &Everything) {
                 vd.validated_blocks(|block, data| {
                     let mut vd = Validator::new(block, data);
 
-                    vd.field_item("great_work_module", Item::GreatWorkModule);
-                    vd.field_item("great_work_material", Item::GreatWorkMaterial);
+                    vd.field_value("great_work_module");
+                    vd.field_value("great_work_material");
                 });
             });
             // Validate selections
@@ -288,10 +319,28 @@ fn validate_great_works(block: &Block, data: &Everything) {
                 vd.validated_blocks(|block, data| {
                     let mut vd = Validator::new(block, data);
 
-                    vd.field_item("great_work_effect", Item::GreatWorkEffect);
-                    vd.field_item("great_work_effect_tier", Item::GreatWorkEffectTier);
+                    vd.field_value("great_work_effect");
+                    vd.field_value("great_work_effect_tier");
                 });
             });
         }
     });
-}
\ No newline at end of file
+}
+
+impl FileHandler<Block> for SetupMain {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from("setup/main/")
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<Block> {
+        if !entry.filename().to_string_lossy().ends_with(".txt") {
+            return None;
+        }
+
+        PdxFile::read_optional_bom(entry)
+    }
+
+    fn handle_file(&mut self, _entry: &FileEntry, block: Block) {
+        self.blocks.push(block);
+    }
+}
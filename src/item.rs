@@ -1306,6 +1306,31 @@ impl Item {
             _ => Severity::Error,
         }
     }
+
+    /// Whether `--unused` should warn about items of this type that are never referred to.
+    ///
+    /// Only item types that the game uses solely through references from script are checked.
+    /// All others opt out, because the game engine uses them by itself (such as decisions) or
+    /// because they can be referred to in ways that are not tracked.
+    pub fn reported_if_unused(self) -> bool {
+        match self {
+            Item::EffectLocalization
+            | Item::GuiTemplate
+            | Item::GuiType
+            | Item::Modifier
+            | Item::OnAction
+            | Item::ScriptedEffect
+            | Item::ScriptedList
+            | Item::ScriptedModifier
+            | Item::ScriptedTrigger
+            | Item::ScriptValue => true,
+
+            #[cfg(feature = "ck3")]
+            Item::OpinionModifier => true,
+
+            _ => false,
+        }
+    }
 }
 
 /// The callback type for adding one item instance to the database.
@@ -32,19 +32,22 @@ compile_error!("features \"ck3\", \"vic3\", and \"imperator\" cannot be enabled
 compile_error!("exactly one of the features \"ck3\", \"vic3\", \"imperator\" must be enabled");
 
 pub use crate::config_load::validate_config_file;
+pub use crate::conflicts::emit_conflicts;
 pub use crate::everything::Everything;
-pub use crate::fileset::FileKind;
+pub use crate::fileset::{FileEntry, FileKind};
 pub use crate::game::Game;
 pub use crate::gamedir::{find_game_directory_steam, find_paradox_directory};
 pub use crate::item::Item;
+pub use crate::lsp::LspServer;
 #[cfg(feature = "vic3")]
 pub use crate::mod_metadata::ModMetadata;
 #[cfg(any(feature = "ck3", feature = "imperator"))]
 pub use crate::modfile::ModFile;
 pub use crate::report::{
-    add_loaded_mod_root, disable_ansi_colors, emit_reports, log, set_output_file, set_output_style,
-    set_show_loaded_mods, set_show_vanilla, take_reports, Confidence, LogReport, PointedMessage,
-    Severity,
+    add_loaded_mod_root, apply_fixes, disable_ansi_colors, emit_reports, emit_reports_diff,
+    emit_reports_sarif, finish_baseline, log, report_unused_suppressions, set_baseline,
+    set_output_file, set_output_style, set_show_loaded_mods, set_show_vanilla, take_reports,
+    Confidence, Fix, LogReport, PointedMessage, Severity,
 };
 pub use crate::token::{Loc, Token};
 
@@ -57,6 +60,7 @@ mod vic3;
 
 mod block;
 mod config_load;
+mod conflicts;
 mod context;
 mod data;
 mod datatype;
@@ -66,14 +70,18 @@ mod dds;
 mod desc;
 mod effect;
 mod effect_validation;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+mod event_graph;
 mod everything;
 mod fileset;
 mod game;
 mod gamedir;
 mod gui;
+mod heightmap;
 mod helpers;
 mod item;
 mod lowercase;
+mod lsp;
 mod macros;
 #[cfg(feature = "vic3")]
 mod mod_metadata;
@@ -84,13 +92,21 @@ mod on_action;
 mod parse;
 mod pathtable;
 mod pdxfile;
+#[cfg(any(feature = "ck3", feature = "imperator"))]
+mod province_map;
+#[cfg(any(feature = "ck3", feature = "imperator"))]
+mod province_topology;
 mod report;
 mod rivers;
 mod scopes;
 mod script_value;
+mod signatures;
 mod token;
 mod tooltipped;
 mod trigger;
+mod trigger_logic;
 mod util;
 mod validate;
 mod validator;
+mod value_range;
+mod variables;
@@ -0,0 +1,343 @@
+//! A Language Server Protocol frontend for the validator.
+//!
+//! The server keeps an [`Everything`] loaded between edits, reloads the affected parts when the
+//! client reports that files were saved, and publishes the resulting [`LogReport`]s as LSP
+//! diagnostics.
+//!
+//! Only the small subset of the protocol needed for that is implemented. Messages are exchanged
+//! as JSON-RPC over any reader and writer, which is normally stdin and stdout of the server
+//! process.
+
+use std::io::{BufRead, Write};
+use std::mem::take;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use fnv::{FnvHashMap, FnvHashSet};
+use serde_json::{json, Value};
+
+use crate::everything::Everything;
+use crate::game::Game;
+use crate::report::{take_reports, LogReport, PointedMessage, Severity};
+use crate::util::{path_to_uri, uri_to_path};
+
+/// JSON-RPC error code for requests whose method is not supported.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The LSP server state.
+#[derive(Debug)]
+pub struct LspServer {
+    /// Path to the custom `.conf` file, if any.
+    config: Option<PathBuf>,
+    /// Path to the base game directory.
+    vanilla_dir: Option<PathBuf>,
+    /// Path to the mod. If it's `None`, the workspace root sent by the client is used.
+    mod_root: Option<PathBuf>,
+    /// The `replace_paths` from the mod's metadata.
+    replace_paths: Vec<PathBuf>,
+    /// The loaded game and mod files. It's `None` until the client has finished initializing.
+    everything: Option<Everything>,
+    /// Full paths of files that currently have diagnostics published in the client.
+    published: FnvHashSet<PathBuf>,
+    /// Set when the client has sent the `shutdown` request.
+    shutdown: bool,
+}
+
+impl LspServer {
+    /// Create a new server. It will not load anything until a client connects.
+    ///
+    /// `mod_root` may be `None`, in which case the root of the client's workspace is validated.
+    pub fn new(
+        config: Option<PathBuf>,
+        vanilla_dir: Option<PathBuf>,
+        mod_root: Option<PathBuf>,
+        replace_paths: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            config,
+            vanilla_dir,
+            mod_root,
+            replace_paths,
+            everything: None,
+            published: FnvHashSet::default(),
+            shutdown: false,
+        }
+    }
+
+    /// Serve one client until it sends the `exit` notification or closes the input stream.
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+            let id = message.get("id");
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            match method {
+                "initialize" => {
+                    self.initialize(&params);
+                    let result = json!({
+                        "capabilities": {
+                            "textDocumentSync": { "openClose": true, "change": 0, "save": true },
+                        },
+                        "serverInfo": { "name": Game::tool_name() },
+                    });
+                    write_message(&mut output, &response(id, &result))?;
+                }
+                "initialized" => self.reload(&mut output)?,
+                "textDocument/didSave" => {
+                    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                    if let Some(path) = uri_to_path(uri) {
+                        self.files_changed(&[path], &mut output)?;
+                    }
+                }
+                "workspace/didChangeWatchedFiles" => {
+                    let paths: Vec<PathBuf> = params["changes"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|change| change["uri"].as_str().and_then(uri_to_path))
+                        .collect();
+                    self.files_changed(&paths, &mut output)?;
+                }
+                "shutdown" => {
+                    self.shutdown = true;
+                    write_message(&mut output, &response(id, &Value::Null))?;
+                }
+                "exit" => {
+                    if !self.shutdown {
+                        bail!("client exited without shutting down the server");
+                    }
+                    return Ok(());
+                }
+                _ => {
+                    // Requests must be answered; unknown notifications are ignored.
+                    if let Some(id) = id {
+                        let error = json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": {
+                                "code": METHOD_NOT_FOUND,
+                                "message": format!("unsupported method {method}"),
+                            },
+                        });
+                        write_message(&mut output, &error)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pick up the workspace root from the `initialize` request if no mod root was configured.
+    fn initialize(&mut self, params: &Value) {
+        if self.mod_root.is_none() {
+            let uri = params["rootUri"]
+                .as_str()
+                .or_else(|| params["workspaceFolders"][0]["uri"].as_str());
+            self.mod_root = uri
+                .and_then(uri_to_path)
+                .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+        }
+    }
+
+    /// React to the client saying that these files have changed on disk.
+    ///
+    /// Only the files affected by the change are revalidated. Changes to the config file cause a
+    /// full reload.
+    fn files_changed<W: Write>(&mut self, paths: &[PathBuf], output: &mut W) -> Result<()> {
+        let Some(everything) = &mut self.everything else {
+            return Ok(());
+        };
+        let mut changed = Vec::new();
+        for path in paths {
+            if path.extension().is_some_and(|ext| ext == "conf") {
+                return self.reload(output);
+            }
+            if let Some(entry) = everything.file_entry(path) {
+                changed.push(entry);
+            }
+        }
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let revalidated: FnvHashSet<PathBuf> =
+            everything.reload_files(&changed).iter().map(|path| absolute(path)).collect();
+        self.publish(&take_reports(), Some(&revalidated), output)
+    }
+
+    /// Load and validate everything from scratch, then publish the diagnostics.
+    ///
+    /// If the mod can't be loaded, the client is told why and the server keeps going, so that it
+    /// can try again when the config file is fixed.
+    fn reload<W: Write>(&mut self, output: &mut W) -> Result<()> {
+        if let Err(e) = self.load() {
+            return show_error(output, &format!("could not load the mod: {e:#}"));
+        }
+        self.publish(&take_reports(), None, output)
+    }
+
+    fn load(&mut self) -> Result<()> {
+        let mod_root =
+            self.mod_root.clone().ok_or_else(|| anyhow!("client did not send a workspace root"))?;
+        let vanilla_dir = self.vanilla_dir.as_deref().map(absolute);
+        let mut everything = Everything::new(
+            self.config.as_deref(),
+            vanilla_dir.as_deref(),
+            &absolute(&mod_root),
+            self.replace_paths.clone(),
+        )?;
+        everything.load_config_filtering_rules();
+        everything.enable_incremental();
+        let everything = self.everything.insert(everything);
+        everything.load_all();
+        everything.validate_all();
+        Ok(())
+    }
+
+    /// Send `textDocument/publishDiagnostics` for every file that has reports, and send empty
+    /// diagnostics for files that had reports before but don't anymore.
+    ///
+    /// If `only` is given, the reports replace the diagnostics of just those files. Reports about
+    /// other files are ignored then.
+    fn publish<W: Write>(
+        &mut self,
+        reports: &[LogReport],
+        only: Option<&FnvHashSet<PathBuf>>,
+        output: &mut W,
+    ) -> Result<()> {
+        let mut by_file: FnvHashMap<PathBuf, Vec<Value>> = FnvHashMap::default();
+        for report in reports {
+            let path = absolute(report.primary().loc.fullpath());
+            if only.map_or(true, |only| only.contains(&path)) {
+                by_file.entry(path).or_default().push(diagnostic(report));
+            }
+        }
+
+        let previous = take(&mut self.published);
+        for path in previous {
+            if by_file.contains_key(&path) {
+                continue;
+            }
+            if only.map_or(true, |only| only.contains(&path)) {
+                publish_diagnostics(output, &path, &[])?;
+            } else {
+                self.published.insert(path);
+            }
+        }
+        for (path, diagnostics) in by_file {
+            publish_diagnostics(output, &path, &diagnostics)?;
+            self.published.insert(path);
+        }
+        output.flush()?;
+        Ok(())
+    }
+}
+
+fn response(id: Option<&Value>, result: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// Show an error message to the user with `window/showMessage`.
+fn show_error<W: Write>(output: &mut W, message: &str) -> Result<()> {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "window/showMessage",
+        "params": { "type": 1, "message": message },
+    });
+    write_message(output, &notification)
+}
+
+fn publish_diagnostics<W: Write>(output: &mut W, path: &Path, diagnostics: &[Value]) -> Result<()> {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": path_to_uri(path), "diagnostics": diagnostics },
+    });
+    write_message(output, &notification)
+}
+
+/// Convert a report to an LSP `Diagnostic`. The secondary pointers become related information.
+fn diagnostic(report: &LogReport) -> Value {
+    let severity = match report.severity {
+        Severity::Fatal | Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Untidy => 3,
+        Severity::Tips => 4,
+    };
+    let mut message = report.msg.clone();
+    if let Some(info) = &report.info {
+        message.push('\n');
+        message.push_str(info);
+    }
+    let related: Vec<Value> = report.pointers[1..]
+        .iter()
+        .map(|pointer| {
+            json!({
+                "location": {
+                    "uri": path_to_uri(&absolute(pointer.loc.fullpath())),
+                    "range": range(pointer),
+                },
+                "message": pointer.msg.as_deref().unwrap_or(""),
+            })
+        })
+        .collect();
+    json!({
+        "range": range(report.primary()),
+        "severity": severity,
+        "code": report.key,
+        "source": Game::tool_name(),
+        "message": message,
+        "relatedInformation": related,
+    })
+}
+
+/// Convert a pointer's location to an LSP `Range`.
+///
+/// LSP lines and characters are 0-based while a `Loc` is 1-based. A line of 0 means the whole
+/// file, which is reported as the start of the file.
+// TODO: LSP counts characters in UTF-16 code units, while `Loc` counts chars.
+fn range(pointer: &PointedMessage) -> Value {
+    let line = pointer.loc.line.saturating_sub(1);
+    let column = usize::from(pointer.loc.column.saturating_sub(1));
+    json!({
+        "start": { "line": line, "character": column },
+        "end": { "line": line, "character": column + pointer.length },
+    })
+}
+
+/// Read one JSON-RPC message. Returns `None` at the end of the input.
+fn read_message<R: BufRead>(input: &mut R) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| anyhow!("message without Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()?;
+    Ok(())
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().map_or_else(|_| path.to_path_buf(), |dir| dir.join(path))
+    }
+}
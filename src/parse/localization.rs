@@ -5,7 +5,7 @@ use std::str::Chars;
 use crate::data::localization::{LocaEntry, LocaValue, MacroValue};
 use crate::datatype::{Code, CodeArg, CodeChain};
 use crate::fileset::FileEntry;
-use crate::report::{untidy, warn, ErrorKey};
+use crate::report::{check_comment, untidy, warn, ErrorKey};
 use crate::token::{Loc, Token};
 
 fn is_key_char(c: char) -> bool {
@@ -47,7 +47,8 @@ impl<'a> LocaParser<'a> {
                 chars.next();
             }
         } else {
-            warn(ErrorKey::Encoding).msg("Expected UTF-8 BOM encoding").loc(loc).push();
+            let msg = "Expected UTF-8 BOM encoding";
+            warn(ErrorKey::Encoding).msg(msg).loc(loc).fix(loc, '\u{feff}').push();
         }
         LocaParser {
             loc,
@@ -104,6 +105,20 @@ impl<'a> LocaParser<'a> {
         self.next_char(); // Eat the newline
     }
 
+    /// Skip a `#` comment till the end of the line, and check it for `tiger-ignore` markers.
+    fn skip_comment(&mut self, own_line: bool) {
+        let start = self.offset;
+        let loc = self.loc;
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.next_char();
+        }
+        check_comment(&self.content[start..self.offset], loc, own_line);
+        self.next_char(); // Eat the newline
+    }
+
     // This function returns an Option so that the caller can return
     // its value without further boilerplate.
     #[allow(clippy::unnecessary_wraps)]
@@ -239,7 +254,7 @@ impl<'a> LocaParser<'a> {
             // Skip comments and blank lines
             self.skip_whitespace();
             if self.chars.peek() == Some(&'#') {
-                self.skip_line();
+                self.skip_comment(true);
                 continue;
             }
 
@@ -342,14 +357,15 @@ impl<'a> LocaParser<'a> {
 
         self.skip_linear_whitespace();
         match self.chars.peek() {
-            None | Some('#' | '\n') => (),
+            None | Some('\n') => self.skip_line(),
+            Some('#') => self.skip_comment(false),
             _ => {
                 let msg = "content after final `\"` on line";
                 warn(ErrorKey::Localization).strong().msg(msg).loc(self.loc).push();
+                self.skip_line();
             }
         }
 
-        self.skip_line();
         let value = if self.value.len() == 1 {
             take(&mut self.value[0])
         } else {
@@ -11,7 +11,7 @@ use fnv::FnvHashMap;
 use crate::block::Eq::Single;
 use crate::block::{Block, Comparator, BV};
 use crate::fileset::{FileEntry, FileKind};
-use crate::report::{err, fatal, untidy, warn, ErrorKey};
+use crate::report::{check_comment, err, fatal, untidy, warn, ErrorKey};
 use crate::token::{bump, leak, Loc, Token};
 
 /// ^Z is by convention an end-of-text marker, and the game engine treats it as such.
@@ -873,7 +873,10 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
                 }
                 '{' => parser.open_brace(loc, i),
                 '}' => parser.close_brace(loc, content, i),
-                '#' => state = State::Comment,
+                '#' => {
+                    index_loc = IndexLoc(i, loc);
+                    state = State::Comment;
+                }
                 '"' => {
                     index_loc = IndexLoc(i, loc).next();
                     state = State::QString;
@@ -891,6 +894,7 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
             },
             State::Comment => {
                 if c == '\n' {
+                    comment(content, index_loc, i);
                     state = State::Neutral;
                 }
             }
@@ -930,7 +934,10 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
                             parser.close_brace(loc, content, i);
                             state = State::Neutral;
                         }
-                        '#' => state = State::Comment,
+                        '#' => {
+                            index_loc = IndexLoc(i, loc);
+                            state = State::Comment;
+                        }
                         '"' => {
                             index_loc = IndexLoc(i, loc).next();
                             state = State::QString;
@@ -983,7 +990,10 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
                             parser.close_brace(loc, content, i);
                             state = State::Neutral;
                         }
-                        '#' => state = State::Comment,
+                        '#' => {
+                            index_loc = IndexLoc(i, loc);
+                            state = State::Comment;
+                        }
                         ';' => state = State::Neutral,
                         '$' => {
                             parser.current.contains_macro_parms = true;
@@ -1061,7 +1071,10 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
                             parser.close_brace(loc, content, i);
                             state = State::Neutral;
                         }
-                        '#' => state = State::Comment,
+                        '#' => {
+                            index_loc = IndexLoc(i, loc);
+                            state = State::Comment;
+                        }
                         '"' => {
                             index_loc = IndexLoc(i, loc).next();
                             state = State::QString;
@@ -1127,12 +1140,22 @@ fn parse_pdx(entry: &FileEntry, content: &'static str) -> Block {
         State::Comparator => {
             parser.comparator(&content[index_loc.0..], index_loc.1);
         }
-        _ => (),
+        State::Comment => comment(content, index_loc, content.len()),
+        State::Neutral => (),
     }
 
     parser.eof()
 }
 
+/// Check the comment that runs from `start` to `end` for `tiger-ignore` markers.
+fn comment(content: &str, start: IndexLoc, end: usize) {
+    let text = &content[start.0..end];
+    if text.contains("tiger-ignore") {
+        let before = content[..start.0].rsplit('\n').next().unwrap_or("");
+        check_comment(text, start.1, before.trim().is_empty());
+    }
+}
+
 /// Parse the content associated with the [`FileEntry`].
 pub fn parse_pdx_file(entry: &FileEntry, content: String, offset: usize) -> Block {
     let content = leak(content);
@@ -49,7 +49,7 @@ impl PdxFile {
             Some(parse_pdx_file(entry, contents, BOM_UTF8_LEN))
         } else {
             let msg = "file must start with a UTF-8 BOM";
-            warn(ErrorKey::Encoding).msg(msg).loc(entry).push();
+            warn(ErrorKey::Encoding).msg(msg).loc(entry).fix(entry, BOM_CHAR).push();
             Some(parse_pdx_file(entry, contents, 0))
         }
     }
@@ -0,0 +1,535 @@
+//! The `map_data` files that CK3 and Imperator handle the same way: `definition.csv`,
+//! `adjacencies.csv`, and `provinces.png`.
+//!
+//! The games differ in how `default.map` classifies the provinces, so each game has its own
+//! handler for `map_data` that keeps a [`ProvinceMap`] and adds its own checks.
+
+use std::str::FromStr;
+
+use bitvec::bitbox;
+use bitvec::boxed::BitBox;
+use fnv::{FnvHashMap, FnvHashSet};
+use image::{DynamicImage, Rgb, RgbImage};
+
+use crate::block::Block;
+use crate::fileset::FileEntry;
+use crate::game::GameFlags;
+use crate::item::Item;
+use crate::parse::csv::{parse_csv, read_csv};
+use crate::pdxfile::PdxFile;
+use crate::province_topology::ProvinceTopology;
+use crate::report::{err, fatal, report, untidy, warn, will_maybe_log, ErrorKey, Severity};
+use crate::token::{Loc, Token};
+
+pub type ProvId = u32;
+
+/// Provinces with fewer pixels than this are reported as too small.
+const MIN_PROVINCE_PIXELS: u32 = 5;
+
+const COLOUR_COUNT: usize = 256 * 256 * 256;
+
+#[derive(Clone, Debug)]
+struct ColorBitArray(BitBox);
+
+impl Default for ColorBitArray {
+    fn default() -> Self {
+        Self(bitbox![0; COLOUR_COUNT])
+    }
+}
+
+impl ColorBitArray {
+    fn get_index(color: Rgb<u8>) -> usize {
+        let Rgb([r, g, b]) = color;
+        (r as usize) << 16 | (g as usize) << 8 | b as usize
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn get_color(index: usize) -> Rgb<u8> {
+        let r = (index >> 16) as u8;
+        let g = (index >> 8) as u8;
+        let b = index as u8;
+        Rgb([r, g, b])
+    }
+}
+
+impl std::ops::Deref for ColorBitArray {
+    type Target = BitBox;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for ColorBitArray {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Debug)]
+pub enum FileContent {
+    Adjacencies(String),
+    Definitions(String),
+    Provinces(DynamicImage),
+    DefaultMap(Block),
+}
+
+/// Load one of the `map_data` files that the province handlers are interested in.
+pub fn load_file(entry: &FileEntry) -> Option<FileContent> {
+    if entry.path().components().count() == 2 {
+        match &*entry.filename().to_string_lossy() {
+            "adjacencies.csv" | "definition.csv" => {
+                let content = match read_csv(entry.fullpath()) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        let msg = format!("could not read `{}`: {:#}", entry.path().display(), e);
+                        err(ErrorKey::ReadError).msg(msg).loc(entry).push();
+                        return None;
+                    }
+                };
+                if entry.filename() == "adjacencies.csv" {
+                    return Some(FileContent::Adjacencies(content));
+                }
+                return Some(FileContent::Definitions(content));
+            }
+
+            "provinces.png" => {
+                let img = match image::open(entry.fullpath()) {
+                    Ok(img) => img,
+                    Err(e) => {
+                        let msg = format!("could not read `{}`: {e:#}", entry.path().display());
+                        err(ErrorKey::ReadError).msg(msg).loc(entry).push();
+                        return None;
+                    }
+                };
+                if let DynamicImage::ImageRgb8(_) = img {
+                    return Some(FileContent::Provinces(img));
+                }
+                let msg = format!(
+                    "`{}` has wrong color format `{:?}`, should be Rgb8",
+                    entry.path().display(),
+                    img.color()
+                );
+                err(ErrorKey::ImageFormat).msg(msg).loc(entry).push();
+            }
+
+            "default.map" => {
+                return PdxFile::read_optional_bom(entry).map(FileContent::DefaultMap);
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+#[derive(Debug, Default)]
+pub struct ProvinceMap {
+    /// Colors in the provinces.png
+    colors: ColorBitArray,
+
+    /// Kept and used for error reporting.
+    provinces_png: Option<FileEntry>,
+
+    /// The provinces.png image itself. It's only kept until `finalize`, because it's big.
+    provinces_img: Option<RgbImage>,
+
+    /// Provinces defined in definition.csv.
+    /// The games require uninterrupted indices starting at 0, but we want to be able to warn
+    /// and continue if they're not, so it's a hashmap.
+    provinces: FnvHashMap<ProvId, Province>,
+
+    /// Kept and used for error reporting.
+    definition_csv: Option<FileEntry>,
+
+    adjacencies: Vec<Adjacency>,
+}
+
+impl ProvinceMap {
+    fn parse_definition(&mut self, csv: &[Token]) {
+        if let Some(province) = Province::parse(csv) {
+            if self.provinces.contains_key(&province.id) {
+                err(ErrorKey::DuplicateItem)
+                    .msg("duplicate entry for this province id")
+                    .loc(&province.comment)
+                    .push();
+            }
+            self.provinces.insert(province.id, province);
+        }
+    }
+
+    /// Take in the contents of one file. The `default.map` is handed back, because what it
+    /// means depends on the game.
+    pub fn handle_file(&mut self, entry: &FileEntry, content: FileContent) -> Option<Block> {
+        match content {
+            FileContent::Adjacencies(content) => {
+                let mut seen_terminator = false;
+                for csv in parse_csv(entry, 1, &content) {
+                    if csv[0].is("-1") {
+                        seen_terminator = true;
+                    } else if seen_terminator {
+                        let msg = "the line with all `-1;` should be the last line in the file";
+                        warn(ErrorKey::ParseError).msg(msg).loc(&csv[0]).push();
+                        break;
+                    } else {
+                        self.adjacencies.extend(Adjacency::parse(&csv));
+                    }
+                }
+                if !seen_terminator {
+                    let msg = format!(
+                        "{} needs a line with all `-1;` at the end of this file",
+                        GameFlags::game()
+                    );
+                    err(ErrorKey::ParseError).msg(msg).loc(entry).push();
+                }
+            }
+            FileContent::Definitions(content) => {
+                self.definition_csv = Some(entry.clone());
+                for csv in parse_csv(entry, 0, &content) {
+                    self.parse_definition(&csv);
+                }
+            }
+            FileContent::Provinces(img) => {
+                if let DynamicImage::ImageRgb8(img) = img {
+                    for pixel in img.pixels().copied() {
+                        unsafe {
+                            // SAFETY: `ColorBitArray::index` is guaranteed to return a valid index
+                            self.colors
+                                .get_unchecked_mut(ColorBitArray::get_index(pixel))
+                                .commit(true);
+                        }
+                    }
+                    self.provinces_png = Some(entry.clone());
+                    self.provinces_img = Some(img);
+                }
+            }
+            FileContent::DefaultMap(block) => return Some(block),
+        }
+        None
+    }
+
+    /// Check the files against each other, once they have all been handled. `is_land` says which
+    /// provinces should be reachable over land.
+    ///
+    /// Returns the provinces.png image and its file entry, for checks of the other map images.
+    pub fn finalize(&mut self, is_land: impl Fn(ProvId) -> bool) -> Option<(&FileEntry, RgbImage)> {
+        let Some(definition_csv) = &self.definition_csv else {
+            // Shouldn't happen, it should come from vanilla if not from the mod
+            eprintln!("map_data/definition.csv is missing?!?");
+            return None;
+        };
+        self.check_definitions(definition_csv);
+
+        let img = self.provinces_img.take()?;
+        let color_ids = self.color_ids();
+        for adjacency in &self.adjacencies {
+            adjacency.validate_coords(&img, &color_ids);
+        }
+        self.check_topology(&img, &color_ids, is_land);
+        Some((self.provinces_png.as_ref()?, img))
+    }
+
+    /// Report province ids that are not sequential, colors used by more than one province, and
+    /// colors in provinces.png that have no province.
+    fn check_definitions(&self, definition_csv: &FileEntry) {
+        let mut seen_colors = FnvHashMap::default();
+        #[allow(clippy::cast_possible_truncation)]
+        for i in 1..self.provinces.len() as u32 {
+            if let Some(province) = self.provinces.get(&i) {
+                if let Some(k) = seen_colors.get(&province.color) {
+                    let msg = format!("color was already used for id {k}");
+                    warn(ErrorKey::Colors).msg(msg).loc(&province.comment).push();
+                } else {
+                    seen_colors.insert(province.color, i);
+                }
+            } else {
+                let msg = format!("province ids must be sequential, but {i} is missing");
+                err(ErrorKey::Validation).msg(msg).loc(definition_csv).push();
+                return;
+            }
+        }
+        for color_index in self.colors.iter_ones() {
+            let color = ColorBitArray::get_color(color_index);
+            if !seen_colors.contains_key(&color) {
+                let Rgb(rgb) = color;
+                let msg = format!(
+                    "definitions.csv lacks entry for color ({}, {}, {})",
+                    rgb[0], rgb[1], rgb[2]
+                );
+                untidy(ErrorKey::Colors).msg(msg).loc(definition_csv).push();
+            }
+        }
+    }
+
+    /// Report provinces whose pixels in provinces.png are likely to cause crashes or broken
+    /// pathing: provinces split into several areas, stray single pixels, tiny provinces, and land
+    /// provinces that can't be reached from any other land province.
+    fn check_topology(
+        &self,
+        img: &RgbImage,
+        color_ids: &FnvHashMap<Rgb<u8>, ProvId>,
+        is_land: impl Fn(ProvId) -> bool,
+    ) {
+        let Some(png) = &self.provinces_png else { return };
+        // Early exit before expensive loop, if errors won't be logged anyway
+        if !will_maybe_log(png, ErrorKey::ProvinceMap) {
+            return;
+        }
+        let topology = ProvinceTopology::new(img);
+        let in_adjacency: FnvHashSet<ProvId> =
+            self.adjacencies.iter().flat_map(|item| [item.from, item.to]).collect();
+
+        let mut provinces: Vec<&Province> = self.provinces.values().collect();
+        provinces.sort_unstable_by_key(|province| province.id);
+        for province in provinces {
+            let Some(shape) = topology.shape(province.color) else { continue };
+            let id = province.id;
+            let (min, max) = (shape.min, shape.max);
+            let bbox =
+                format!("its pixels are within ({}, {}) - ({}, {})", min.0, min.1, max.0, max.1);
+
+            if shape.pixels < MIN_PROVINCE_PIXELS {
+                let msg =
+                    format!("province {id} has only {} pixels in provinces.png", shape.pixels);
+                warn(ErrorKey::ProvinceMap).msg(msg).info(&bbox).loc(&province.key).push();
+            } else {
+                let (strays, parts): (Vec<_>, Vec<_>) =
+                    shape.components.iter().partition(|component| component.pixels == 1);
+                for stray in strays {
+                    let (x, y) = stray.first;
+                    let msg = format!("({x}, {y}) stray pixel of province {id}");
+                    warn(ErrorKey::ProvinceMap).msg(msg).loc(png).push();
+                }
+                if parts.len() > 1 {
+                    let msg = format!("province {id} is split into {} separate areas", parts.len());
+                    let starts: Vec<String> = parts
+                        .iter()
+                        .map(|part| format!("({}, {})", part.first.0, part.first.1))
+                        .collect();
+                    let info = format!("the areas start at {}", starts.join(", "));
+                    warn(ErrorKey::ProvinceMap).msg(msg).info(info).loc(&province.key).push();
+                }
+            }
+
+            if is_land(id)
+                && !in_adjacency.contains(&id)
+                && !topology
+                    .neighbors(province.color)
+                    .filter_map(|color| color_ids.get(&color))
+                    .any(|&other| is_land(other))
+            {
+                let msg = format!("land province {id} does not border any other land province");
+                let info = "it needs an entry in adjacencies.csv to be reachable";
+                warn(ErrorKey::ProvinceMap).msg(msg).info(info).loc(&province.key).push();
+            }
+        }
+    }
+
+    /// Map the colors in provinces.png to the provinces they belong to.
+    pub fn color_ids(&self) -> FnvHashMap<Rgb<u8>, ProvId> {
+        self.provinces.values().map(|province| (province.color, province.id)).collect()
+    }
+
+    pub(crate) fn verify_exists_provid(&self, provid: ProvId, item: &Token, max_sev: Severity) {
+        if !self.provinces.contains_key(&provid) {
+            let msg = format!("province {provid} not defined in map_data/definition.csv");
+            report(ErrorKey::MissingItem, Item::Province.severity().at_most(max_sev))
+                .msg(msg)
+                .loc(item)
+                .push();
+        }
+    }
+
+    pub fn verify_exists_implied(&self, key: &str, item: &Token, max_sev: Severity) {
+        if let Ok(provid) = key.parse::<ProvId>() {
+            self.verify_exists_provid(provid, item, max_sev);
+        } else {
+            let msg = "province id should be numeric";
+            let sev = Item::Province.severity().at_most(max_sev);
+            report(ErrorKey::Validation, sev).msg(msg).loc(item).push();
+        }
+    }
+
+    pub fn exists(&self, key: &str) -> bool {
+        if let Ok(provid) = key.parse::<ProvId>() {
+            self.provinces.contains_key(&provid)
+        } else {
+            false
+        }
+    }
+
+    #[allow(dead_code)] // Not used by all games
+    pub fn get(&self, provid: ProvId) -> Option<&Province> {
+        self.provinces.get(&provid)
+    }
+
+    pub fn iter_provinces(&self) -> impl Iterator<Item = &Province> {
+        self.provinces.values()
+    }
+
+    pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
+        self.provinces.values().map(|item| &item.key)
+    }
+
+    pub fn iter_adjacencies(&self) -> impl Iterator<Item = &Adjacency> {
+        self.adjacencies.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Coords {
+    x: Token,
+    y: Token,
+}
+
+impl Coords {
+    fn parse(x: &Token, y: &Token) -> Option<Self> {
+        let x_ok = verify_field::<i64>(x, "expected x coordinate").is_some();
+        let y_ok = verify_field::<i64>(y, "expected y coordinate").is_some();
+        (x_ok && y_ok).then(|| Coords { x: x.clone(), y: y.clone() })
+    }
+
+    /// Return the pixel in the provinces.png that these map coordinates refer to, or `None` if
+    /// they are `-1 -1` which means the game picks the points itself.
+    ///
+    /// Map coordinates count `y` from the bottom of the map, while image coordinates count from
+    /// the top.
+    fn pixel(&self, img: &RgbImage) -> Option<Result<Rgb<u8>, String>> {
+        let x = self.x.as_str().parse::<i64>().ok()?;
+        let y = self.y.as_str().parse::<i64>().ok()?;
+        if x == -1 && y == -1 {
+            return None;
+        }
+        let (width, height) = img.dimensions();
+        if x < 0 || y < 0 || x >= i64::from(width) || y >= i64::from(height) {
+            return Some(Err(format!("coordinates ({x}, {y}) are outside the map")));
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some(Ok(*img.get_pixel(x as u32, height - 1 - y as u32)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Adjacency {
+    line: Loc,
+    from: ProvId,
+    to: ProvId,
+    /// The type of crossing. Which types are allowed depends on the game.
+    #[allow(dead_code)] // Not used by all games
+    pub(crate) kind: Token,
+    through: ProvId,
+    /// The points where the crossing starts and stops. They should be on the `from` and `to`
+    /// provinces. They can be `-1 -1` though.
+    start: Coords,
+    stop: Coords,
+}
+
+fn verify_field<T: FromStr>(v: &Token, msg: &str) -> Option<T> {
+    let r = v.as_str().parse().ok();
+    if r.is_none() {
+        err(ErrorKey::ParseError).msg(msg).loc(v).push();
+    }
+    r
+}
+
+impl Adjacency {
+    pub fn parse(csv: &[Token]) -> Option<Self> {
+        if csv.is_empty() {
+            return None;
+        }
+
+        let line = csv[0].loc;
+
+        if csv.len() != 9 {
+            let msg = "wrong number of fields for this line, expected 9";
+            err(ErrorKey::ParseError).msg(msg).loc(&csv[0]).push();
+            return None;
+        }
+
+        let from = verify_field(&csv[0], "expected province id");
+        let to = verify_field(&csv[1], "expected province id");
+        let through = verify_field(&csv[3], "expected province id");
+        let start = Coords::parse(&csv[4], &csv[5]);
+        let stop = Coords::parse(&csv[6], &csv[7]);
+
+        Some(Adjacency {
+            line,
+            from: from?,
+            to: to?,
+            kind: csv[2].clone(),
+            through: through?,
+            start: start?,
+            stop: stop?,
+        })
+    }
+
+    /// Check that the provinces exist, and that the crossing goes through water.
+    /// `water` describes the `default.map` lists that `is_water` checks.
+    pub fn validate(&self, map: &ProvinceMap, is_water: impl Fn(ProvId) -> bool, water: &str) {
+        for prov in &[self.from, self.to, self.through] {
+            if !map.provinces.contains_key(prov) {
+                let msg = format!("province id {prov} not defined in definitions.csv");
+                fatal(ErrorKey::Crash).msg(msg).loc(self.line).push();
+            }
+        }
+        if map.provinces.contains_key(&self.through) && !is_water(self.through) {
+            let msg = format!("province {} is not a sea zone or river province", self.through);
+            let info = format!(
+                "the crossing should go through a province listed in {water} in default.map"
+            );
+            err(ErrorKey::Validation).msg(msg).info(info).loc(self.line).push();
+        }
+    }
+
+    /// Check that the start and stop coordinates are on the pixels of the provinces they connect.
+    fn validate_coords(&self, img: &RgbImage, color_ids: &FnvHashMap<Rgb<u8>, ProvId>) {
+        for (coords, provid, what) in
+            [(&self.start, self.from, "start"), (&self.stop, self.to, "stop")]
+        {
+            match coords.pixel(img) {
+                Some(Err(msg)) => {
+                    err(ErrorKey::Validation).msg(format!("{what} {msg}")).loc(&coords.x).push();
+                }
+                Some(Ok(color)) if color_ids.get(&color) != Some(&provid) => {
+                    let msg = format!("{what} coordinates are not on province {provid}");
+                    let info = match color_ids.get(&color) {
+                        Some(other) => format!("they are on province {other} in provinces.png"),
+                        None => {
+                            "they are on a color without a province in provinces.png".to_string()
+                        }
+                    };
+                    warn(ErrorKey::Validation).msg(msg).info(info).loc(&coords.x).push();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Province {
+    pub(crate) key: Token,
+    pub(crate) id: ProvId,
+    pub(crate) color: Rgb<u8>,
+    pub(crate) comment: Token,
+}
+
+impl Province {
+    fn parse(csv: &[Token]) -> Option<Self> {
+        if csv.is_empty() {
+            return None;
+        }
+
+        if csv.len() < 5 {
+            let msg = "too few fields for this line, expected 5";
+            err(ErrorKey::ParseError).msg(msg).loc(&csv[0]).push();
+            return None;
+        }
+
+        let id = verify_field(&csv[0], "expected province id")?;
+        let r = verify_field(&csv[1], "expected red value")?;
+        let g = verify_field(&csv[2], "expected green value")?;
+        let b = verify_field(&csv[3], "expected blue value")?;
+        let color = Rgb::from([r, g, b]);
+        Some(Province { key: csv[0].clone(), id, color, comment: csv[4].clone() })
+    }
+}
@@ -0,0 +1,119 @@
+//! Analysis of the shapes of the provinces in `provinces.png`.
+//!
+//! Every color in the image is one province. This module works out where each color's pixels
+//! are, which separate areas ("components") they form, and which colors border each other.
+//! Deciding which of those facts are problems is left to the game-specific province code.
+
+use bitvec::bitvec;
+use fnv::{FnvHashMap, FnvHashSet};
+use image::{Rgb, RgbImage};
+
+/// A set of pixels of one color that are connected horizontally or vertically.
+#[derive(Clone, Debug)]
+pub struct Component {
+    /// The number of pixels in this component.
+    pub pixels: u32,
+    /// The first pixel of this component when scanning the image row by row.
+    /// Useful for telling the user where to look.
+    pub first: (u32, u32),
+}
+
+/// Everything known about the pixels of one color.
+#[derive(Clone, Debug)]
+pub struct ColorShape {
+    /// The number of pixels of this color.
+    pub pixels: u32,
+    /// The top-left corner of the bounding box.
+    pub min: (u32, u32),
+    /// The bottom-right corner of the bounding box.
+    pub max: (u32, u32),
+    /// The separate areas of this color, in the order they were found.
+    pub components: Vec<Component>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProvinceTopology {
+    shapes: FnvHashMap<Rgb<u8>, ColorShape>,
+    /// For each color, the colors that have pixels next to it.
+    borders: FnvHashMap<Rgb<u8>, FnvHashSet<Rgb<u8>>>,
+}
+
+impl ProvinceTopology {
+    pub fn new(img: &RgbImage) -> Self {
+        let (width, height) = img.dimensions();
+        let mut topology = Self::default();
+        let mut seen = bitvec![0; width as usize * height as usize];
+        let mut stack = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let color = *img.get_pixel(x, y);
+                if x + 1 < width {
+                    topology.add_border(color, *img.get_pixel(x + 1, y));
+                }
+                if y + 1 < height {
+                    topology.add_border(color, *img.get_pixel(x, y + 1));
+                }
+
+                let idx = (y * width + x) as usize;
+                if seen[idx] {
+                    continue;
+                }
+
+                // Flood fill the component that starts at this pixel.
+                let shape = topology.shapes.entry(color).or_insert_with(|| ColorShape {
+                    pixels: 0,
+                    min: (x, y),
+                    max: (x, y),
+                    components: Vec::new(),
+                });
+                let mut component = Component { pixels: 0, first: (x, y) };
+                seen.set(idx, true);
+                stack.push((x, y));
+                while let Some((x, y)) = stack.pop() {
+                    component.pixels += 1;
+                    shape.min = (shape.min.0.min(x), shape.min.1.min(y));
+                    shape.max = (shape.max.0.max(x), shape.max.1.max(y));
+                    let mut visit = |x: u32, y: u32| {
+                        let idx = (y * width + x) as usize;
+                        if !seen[idx] && *img.get_pixel(x, y) == color {
+                            seen.set(idx, true);
+                            stack.push((x, y));
+                        }
+                    };
+                    if x > 0 {
+                        visit(x - 1, y);
+                    }
+                    if y > 0 {
+                        visit(x, y - 1);
+                    }
+                    if x + 1 < width {
+                        visit(x + 1, y);
+                    }
+                    if y + 1 < height {
+                        visit(x, y + 1);
+                    }
+                }
+                shape.pixels += component.pixels;
+                shape.components.push(component);
+            }
+        }
+        topology
+    }
+
+    fn add_border(&mut self, a: Rgb<u8>, b: Rgb<u8>) {
+        if a != b {
+            self.borders.entry(a).or_default().insert(b);
+            self.borders.entry(b).or_default().insert(a);
+        }
+    }
+
+    pub fn shape(&self, color: Rgb<u8>) -> Option<&ColorShape> {
+        self.shapes.get(&color)
+    }
+
+    /// Iterate over the colors that border `color`.
+    pub fn neighbors(&self, color: Rgb<u8>) -> impl Iterator<Item = Rgb<u8>> + '_ {
+        self.borders.get(&color).into_iter().flatten().copied()
+    }
+}
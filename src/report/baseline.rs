@@ -0,0 +1,107 @@
+//! Support for baseline files, which record the reports that are already known so that later runs
+//! only show new reports.
+
+use std::fs::{read_to_string, write};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use fnv::{FnvHashSet, FnvHasher};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{ErrorKey, LogReport};
+
+/// Identifies a report in a way that survives unrelated edits to the file it's in.
+///
+/// Line numbers are not part of it. Instead, it has a hash of the text of the line that the report
+/// points to, with the surrounding whitespace removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Fingerprint {
+    key: ErrorKey,
+    message: String,
+    path: PathBuf,
+    context: String,
+}
+
+impl Fingerprint {
+    /// Make a fingerprint for the report. `line` is the text of the line its primary pointer
+    /// points to, if any.
+    pub fn new(report: &LogReport, line: Option<&str>) -> Self {
+        let mut hasher = FnvHasher::default();
+        if let Some(line) = line {
+            hasher.write(line.trim().as_bytes());
+        }
+        Self {
+            key: report.key,
+            message: report.msg.clone(),
+            path: report.primary().loc.pathname().to_path_buf(),
+            context: format!("{:016x}", hasher.finish()),
+        }
+    }
+}
+
+/// The fingerprints from a baseline file, and the ones seen during the current run.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// The baseline file.
+    path: PathBuf,
+    /// Whether the baseline file existed. If not, it will be created from the seen reports.
+    existed: bool,
+    /// The fingerprints read from the baseline file.
+    known: FnvHashSet<Fingerprint>,
+    /// The fingerprints of all reports that were not filtered out otherwise.
+    seen: FnvHashSet<Fingerprint>,
+}
+
+impl Baseline {
+    /// Read the baseline file, or prepare to create it if it doesn't exist yet.
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut baseline = Self { path: path.to_path_buf(), ..Default::default() };
+        if path.is_file() {
+            let contents = read_to_string(path)
+                .with_context(|| format!("could not read baseline {}", path.display()))?;
+            let fingerprints: Vec<Fingerprint> = serde_json::from_str(&contents)
+                .with_context(|| format!("could not parse baseline {}", path.display()))?;
+            baseline.known = fingerprints.into_iter().collect();
+            baseline.existed = true;
+        }
+        Ok(baseline)
+    }
+
+    /// Record that a report with this fingerprint was logged.
+    /// Returns true iff the report is new, meaning it's not in the baseline.
+    pub fn check(&mut self, fingerprint: Fingerprint) -> bool {
+        let new = !self.known.contains(&fingerprint);
+        self.seen.insert(fingerprint);
+        new
+    }
+
+    /// If the baseline file did not exist, write all the seen reports to it.
+    /// Otherwise, print a summary of the reports in it that were not seen anymore.
+    pub fn finish(&self) -> Result<()> {
+        if self.existed {
+            let mut fixed: Vec<&Fingerprint> = self.known.difference(&self.seen).collect();
+            if !fixed.is_empty() {
+                fixed.sort_unstable_by_key(|fp| (&fp.path, fp.key.to_string(), &fp.message));
+                eprintln!("{} reports from the baseline have been fixed:", fixed.len());
+                for fp in fixed {
+                    eprintln!("  {}: {}: {}", fp.path.display(), fp.key, fp.message);
+                }
+            }
+        } else {
+            let mut fingerprints: Vec<&Fingerprint> = self.seen.iter().collect();
+            fingerprints.sort_unstable_by_key(|fp| {
+                (&fp.path, fp.key.to_string(), &fp.message, &fp.context)
+            });
+            let json = serde_json::to_string_pretty(&fingerprints)?;
+            write(&self.path, json)
+                .with_context(|| format!("could not write baseline {}", self.path.display()))?;
+            eprintln!(
+                "Wrote {} reports to the baseline {}",
+                fingerprints.len(),
+                self.path.display()
+            );
+        }
+        Ok(())
+    }
+}
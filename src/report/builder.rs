@@ -4,7 +4,9 @@
 //! - The user is forced to add at least one pointer, making it impossible to create a report
 //!     without pointers, which would lead to panics.
 
-use crate::report::{log, Confidence, ErrorKey, ErrorLoc, LogReport, PointedMessage, Severity};
+use crate::report::{
+    log, Confidence, ErrorKey, ErrorLoc, Fix, LogReport, PointedMessage, Severity,
+};
 
 // =================================================================================================
 // =============== Starting points:
@@ -101,6 +103,7 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: None }],
+            fixes: Vec::new(),
         }
     }
 
@@ -112,11 +115,18 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: Some(msg.into()) }],
+            fixes: Vec::new(),
         }
     }
 
     pub fn pointers(self, pointers: Vec<PointedMessage>) -> ReportBuilderStage3 {
-        ReportBuilderStage3 { stage1: self.stage1, msg: self.msg, info: self.info, pointers }
+        ReportBuilderStage3 {
+            stage1: self.stage1,
+            msg: self.msg,
+            info: self.info,
+            pointers,
+            fixes: Vec::new(),
+        }
     }
 }
 
@@ -127,6 +137,7 @@ pub struct ReportBuilderStage3 {
     msg: String,
     info: Option<String>,
     pointers: Vec<PointedMessage>,
+    fixes: Vec<Fix>,
 }
 
 impl ReportBuilderStage3 {
@@ -146,7 +157,13 @@ impl ReportBuilderStage3 {
         }
         self
     }
-    /// Build the report and return it.
+    /// Optional step. Suggests replacing the text at `eloc` with `replacement` to fix the problem.
+    /// If `eloc` points to a whole file, the replacement is inserted at the start of the file.
+    pub fn fix<E: ErrorLoc, S: Into<String>>(mut self, eloc: E, replacement: S) -> Self {
+        let length = eloc.loc_length();
+        self.fixes.push(Fix { loc: eloc.into_loc(), length, replacement: replacement.into() });
+        self
+    }
     /// Build the report and return it.
     pub fn build(self) -> LogReport {
         LogReport {
@@ -156,6 +173,7 @@ impl ReportBuilderStage3 {
             msg: self.msg,
             info: self.info,
             pointers: self.pointers,
+            fixes: self.fixes,
         }
     }
     /// Build the report and push it to be printed.
@@ -0,0 +1,63 @@
+//! Comparing the reports about two versions of a mod, so that only the differences are shown.
+
+use std::path::Path;
+
+use fnv::FnvHashMap;
+
+use crate::report::errors::Errors;
+use crate::report::{ErrorKey, LogReport};
+
+/// Split the reports into the ones that are only in `new` (the introduced reports) and the ones
+/// that are only in `old` (the resolved reports). The order of the reports is kept.
+///
+/// Reports match if they have the same key, message, and file. Their line numbers may differ,
+/// because edits elsewhere in the file shift the lines. When there are several candidates, reports
+/// that point to the same line text are paired first, and the rest are paired in order.
+pub(crate) fn diff_reports(
+    errors: &mut Errors,
+    old: Vec<LogReport>,
+    new: Vec<LogReport>,
+) -> (Vec<LogReport>, Vec<LogReport>) {
+    let old_lines: Vec<Option<String>> = old.iter().map(|report| line(errors, report)).collect();
+    let new_lines: Vec<Option<String>> = new.iter().map(|report| line(errors, report)).collect();
+
+    let mut candidates: FnvHashMap<(ErrorKey, &str, &Path), Vec<usize>> = FnvHashMap::default();
+    for (i, report) in old.iter().enumerate() {
+        candidates.entry(group(report)).or_default().push(i);
+    }
+
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![false; new.len()];
+    for same_line in [true, false] {
+        for (i, report) in new.iter().enumerate() {
+            if new_matched[i] {
+                continue;
+            }
+            let Some(indexes) = candidates.get(&group(report)) else {
+                continue;
+            };
+            let found = indexes
+                .iter()
+                .find(|&&j| !old_matched[j] && (!same_line || old_lines[j] == new_lines[i]));
+            if let Some(&j) = found {
+                old_matched[j] = true;
+                new_matched[i] = true;
+            }
+        }
+    }
+
+    let introduced =
+        new.into_iter().zip(new_matched).filter(|(_, matched)| !matched).map(|(r, _)| r).collect();
+    let resolved =
+        old.into_iter().zip(old_matched).filter(|(_, matched)| !matched).map(|(r, _)| r).collect();
+    (introduced, resolved)
+}
+
+fn group(report: &LogReport) -> (ErrorKey, &str, &Path) {
+    (report.key, &report.msg, report.primary().loc.pathname())
+}
+
+/// The text of the line the report points to, without surrounding whitespace.
+fn line(errors: &mut Errors, report: &LogReport) -> Option<String> {
+    errors.get_line(report.primary().loc).map(|line| line.trim().to_string())
+}
@@ -1,7 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Hash, Serialize, Deserialize)]
 #[strum(use_phf)]
 #[strum(serialize_all = "kebab-case")]
 #[serde(rename_all = "kebab-case")]
@@ -42,6 +42,7 @@ pub enum ErrorKey {
     Tooltip,
     IfElse,
     Rivers,
+    ProvinceMap,
     Modifiers,
     Macro,
     History,
@@ -55,6 +56,13 @@ pub enum ErrorKey {
     Colors,
     UnusedLocalization,
     UnusedFile,
+    UnusedItem,
+    Suppression,
+    UnreachableEvent,
+    EventCycle,
+    UnsavedScope,
+    UnsetVariable,
+    UnusedVariable,
     UnknownList,
     Choice,
     UseOfThis,
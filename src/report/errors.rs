@@ -13,13 +13,19 @@ use encoding_rs::{UTF_8, WINDOWS_1252};
 use fnv::{FnvHashMap, FnvHashSet};
 use once_cell::sync::Lazy;
 
+use crate::fileset::FileKind;
 use crate::macros::MACRO_MAP;
+use crate::report::baseline::{Baseline, Fingerprint};
+use crate::report::diff::diff_reports;
 use crate::report::error_loc::ErrorLoc;
 use crate::report::filter::ReportFilter;
+use crate::report::fix::apply_to_file;
+use crate::report::suppress::Suppressions;
 use crate::report::writer::log_report;
 use crate::report::writer_json::log_report_json;
+use crate::report::writer_sarif::log_reports_sarif;
 use crate::report::{
-    err, tips, warn, ErrorKey, FilterRule, LogReport, OutputStyle, PointedMessage,
+    err, tips, warn, ErrorKey, FilterRule, Fix, LogReport, OutputStyle, PointedMessage,
 };
 use crate::token::Loc;
 
@@ -48,6 +54,9 @@ pub struct Errors {
     /// The "abbreviated" reports don't participate in this. They are still emitted immediately.
     /// It's a `HashSet` because duplicate reports are fairly common due to macro expansion and other revalidations.
     storage: FnvHashSet<LogReport>,
+
+    /// The `tiger-ignore` comments found in the script and localization files.
+    pub(crate) suppressions: Suppressions,
 }
 
 impl Default for Errors {
@@ -60,6 +69,7 @@ impl Default for Errors {
             filter: ReportFilter::default(),
             styles: OutputStyle::default(),
             storage: FnvHashSet::default(),
+            suppressions: Suppressions::default(),
         }
     }
 }
@@ -86,12 +96,25 @@ impl Errors {
         line
     }
 
+    /// Drop the cached contents and the suppression comments of a file, because it has changed on
+    /// disk.
+    pub(crate) fn invalidate_file(&mut self, fullpath: &Path) {
+        self.filecache.remove(fullpath);
+        self.suppressions.forget_file(fullpath);
+    }
+
     /// Perform some checks to see whether the report should actually be logged.
     /// If yes, it will add it to the storage.
     fn push_report(&mut self, report: LogReport) {
-        if !self.filter.should_print_report(&report) {
+        if self.suppressions.suppresses(&report) || !self.filter.should_print_report(&report) {
             return;
         }
+        if self.filter.baseline.is_some() {
+            let line = self.get_line(report.primary().loc);
+            if !self.filter.check_baseline(Fingerprint::new(&report, line.as_deref())) {
+                return;
+            }
+        }
         self.storage.insert(report);
     }
 
@@ -180,6 +203,82 @@ impl Errors {
         }
     }
 
+    /// Compare the stored reports with the reports `old` about an earlier version of the mod, and
+    /// print only the differences: first the reports that were introduced, then the ones that
+    /// were resolved.
+    /// Set `json` if they should be printed as a JSON object with `introduced` and `resolved`
+    /// arrays. Otherwise they are printed in the default output format.
+    pub fn emit_reports_diff(&mut self, old: Vec<LogReport>, json: bool) {
+        let new = self.take_reports();
+        let (introduced, resolved) = diff_reports(self, old, new);
+        if json {
+            _ = writeln!(self.output.get_mut(), "{{");
+            for (name, reports) in [("introduced", &introduced), ("resolved", &resolved)] {
+                _ = writeln!(self.output.get_mut(), "\"{name}\": [");
+                let mut first = true;
+                for report in reports {
+                    if !first {
+                        _ = writeln!(self.output.get_mut(), ",");
+                    }
+                    first = false;
+                    log_report_json(self, report);
+                }
+                let sep = if name == "introduced" { "," } else { "" };
+                _ = writeln!(self.output.get_mut(), "\n]{sep}");
+            }
+            _ = writeln!(self.output.get_mut(), "}}");
+        } else {
+            for (name, reports) in [("Introduced", &introduced), ("Resolved", &resolved)] {
+                _ = writeln!(self.output.get_mut(), "{name} reports: {}", reports.len());
+                for report in reports {
+                    log_report(self, report);
+                }
+            }
+        }
+    }
+
+    /// Apply the fixes suggested by the stored reports to the mod files, and drop the reports
+    /// that were completely fixed. Returns the number of fixes applied.
+    ///
+    /// Only files of the mod under scrutiny are changed. Fixes that overlap other fixes are
+    /// skipped; running again will apply them.
+    pub fn apply_fixes(&mut self) -> Result<usize> {
+        let mut by_file: FnvHashMap<&'static Path, Vec<&Fix>> = FnvHashMap::default();
+        for report in &self.storage {
+            for fix in &report.fixes {
+                if fix.loc.kind == FileKind::Mod {
+                    by_file.entry(fix.loc.fullpath()).or_default().push(fix);
+                }
+            }
+        }
+        let mut files: Vec<_> = by_file.into_iter().collect();
+        files.sort_unstable_by_key(|(fullpath, _)| *fullpath);
+
+        let mut applied: FnvHashSet<Fix> = FnvHashSet::default();
+        let mut changed = Vec::new();
+        for (fullpath, fixes) in files {
+            let done = apply_to_file(fullpath, &fixes)?;
+            if !done.is_empty() {
+                changed.push(fullpath);
+            }
+            applied.extend(done.into_iter().cloned());
+        }
+        for fullpath in changed {
+            self.invalidate_file(fullpath);
+        }
+        self.storage.retain(|report| {
+            report.fixes.is_empty() || !report.fixes.iter().all(|fix| applied.contains(fix))
+        });
+        Ok(applied.len())
+    }
+
+    /// Print all the stored reports to the error output, as a SARIF 2.1.0 log.
+    pub fn emit_reports_sarif(&mut self) {
+        let reports = self.take_reports();
+        log_reports_sarif(self, &reports);
+        _ = writeln!(self.output.get_mut());
+    }
+
     /// Get a mutable lock on the global ERRORS struct.
     ///
     /// # Panics
@@ -265,6 +364,25 @@ pub fn emit_reports(json: bool) {
     Errors::get_mut().emit_reports(json);
 }
 
+/// Print all the stored reports to the error output, as a SARIF 2.1.0 log.
+pub fn emit_reports_sarif() {
+    Errors::get_mut().emit_reports_sarif();
+}
+
+/// Compare the stored reports with the reports `old` about an earlier version of the mod, and
+/// print only the reports that were introduced or resolved.
+/// Set `json` if they should be printed as a JSON object. Otherwise they are printed in the
+/// default output format.
+pub fn emit_reports_diff(old: Vec<LogReport>, json: bool) {
+    Errors::get_mut().emit_reports_diff(old, json);
+}
+
+/// Apply the fixes suggested by the stored reports to the mod files, and drop the reports that
+/// were completely fixed. Returns the number of fixes applied.
+pub fn apply_fixes() -> Result<usize> {
+    Errors::get_mut().apply_fixes()
+}
+
 /// Extract the stored reports, sort them, and return them as a vector of [`LogReport`].
 /// The stored reports will be left empty.
 pub fn take_reports() -> Vec<LogReport> {
@@ -375,6 +493,24 @@ pub fn set_show_loaded_mods(v: bool) {
     Errors::get_mut().filter.show_loaded_mods = v;
 }
 
+/// Configure the error reporter to use a baseline file of already known reports.
+///
+/// If the file exists, reports that are in it will not be shown. Otherwise, it will be created by
+/// [`finish_baseline`] from all the reports that were shown.
+pub fn set_baseline(path: &Path) -> Result<()> {
+    Errors::get_mut().filter.baseline = Some(Baseline::read(path)?);
+    Ok(())
+}
+
+/// Write the baseline file if it did not exist yet. Otherwise, print a summary of the reports in
+/// it that did not occur anymore. Should be called after all reports have been emitted.
+pub fn finish_baseline() -> Result<()> {
+    if let Some(baseline) = &Errors::get().filter.baseline {
+        baseline.finish()?;
+    }
+    Ok(())
+}
+
 /// Configure the error reporter to only show errors that match this [`FilterRule`].
 pub(crate) fn set_predicate(predicate: FilterRule) {
     Errors::get_mut().filter.predicate = predicate;
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use crate::block::Comparator;
 
 use crate::fileset::FileKind;
+use crate::report::baseline::{Baseline, Fingerprint};
 use crate::report::{Confidence, ErrorKey, LogReport, Severity};
 use crate::token::Loc;
 
@@ -16,6 +17,8 @@ pub struct ReportFilter {
     pub show_loaded_mods: bool,
     /// A complex trigger that evaluates a report to assess whether it should be printed.
     pub predicate: FilterRule,
+    /// Reports that are already known, and should not be printed again.
+    pub baseline: Option<Baseline>,
 }
 
 impl ReportFilter {
@@ -41,6 +44,12 @@ impl ReportFilter {
         self.predicate.apply(report)
     }
 
+    /// Returns true iff a report with this fingerprint should be printed, because it's not in the
+    /// baseline. Reports that pass [`ReportFilter::should_print_report`] should be checked here.
+    pub fn check_baseline(&mut self, fingerprint: Fingerprint) -> bool {
+        self.baseline.as_mut().map_or(true, |baseline| baseline.check(fingerprint))
+    }
+
     /// TODO: Check the filter rules to be more sure.
     pub fn should_maybe_print(&self, key: ErrorKey, loc: Loc) -> bool {
         if key == ErrorKey::Config {
@@ -0,0 +1,76 @@
+//! Applying the machine-applicable [`Fix`]es that some reports suggest.
+
+use std::fs::{read, write};
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::report::Fix;
+
+const BOM_UTF8_BYTES: &[u8] = b"\xef\xbb\xbf";
+
+/// Find the byte range in `contents` that the fix replaces.
+///
+/// Returns `None` if the fix does not fit the contents, for example because the file has changed
+/// since it was validated, or if the fix points into a macro expansion.
+pub(crate) fn byte_range(contents: &[u8], fix: &Fix) -> Option<Range<usize>> {
+    if fix.loc.link_idx.is_some() {
+        return None;
+    }
+    if fix.loc.line == 0 {
+        return Some(0..0);
+    }
+    let text = std::str::from_utf8(contents).ok()?;
+    // The parser does not count the BOM as part of the first line.
+    let bom = if contents.starts_with(BOM_UTF8_BYTES) { BOM_UTF8_BYTES.len() } else { 0 };
+    let mut line_start = bom;
+    let mut lines = text[bom..].split_inclusive('\n');
+    for _ in 1..fix.loc.line {
+        line_start += lines.next()?.len();
+    }
+    let line = lines.next()?.trim_end_matches(['\n', '\r']);
+
+    let mut offsets = line.char_indices().map(|(i, _)| i).chain(std::iter::once(line.len()));
+    let start = offsets.nth(usize::from(fix.loc.column.max(1)) - 1)?;
+    let end = if fix.length == 0 { start } else { offsets.nth(fix.length - 1)? };
+    Some(line_start + start..line_start + end)
+}
+
+/// Apply the fixes to the file at `fullpath`, and return the fixes that were applied.
+///
+/// Fixes that overlap an earlier fix, or that don't fit the file, are skipped.
+pub(crate) fn apply_to_file<'a>(fullpath: &Path, fixes: &[&'a Fix]) -> Result<Vec<&'a Fix>> {
+    let mut contents =
+        read(fullpath).with_context(|| format!("could not read {}", fullpath.display()))?;
+    let mut edits: Vec<(Range<usize>, &Fix)> =
+        fixes.iter().filter_map(|fix| Some((byte_range(&contents, fix)?, *fix))).collect();
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    edits.dedup_by(|(range, fix), (prev, prev_fix)| {
+        range == prev && fix.replacement == prev_fix.replacement
+    });
+
+    let mut applied: Vec<(Range<usize>, &Fix)> = Vec::new();
+    for (range, fix) in edits {
+        if let Some((prev, _)) = applied.last() {
+            if range.start < prev.end || (range.start == prev.start && range.is_empty()) {
+                continue;
+            }
+        }
+        applied.push((range, fix));
+    }
+    if applied.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    for (range, fix) in applied.iter().rev() {
+        contents.splice(range.clone(), fix.replacement.bytes());
+    }
+    write(fullpath, contents).with_context(|| format!("could not write {}", fullpath.display()))?;
+    Ok(applied.into_iter().map(|(_, fix)| fix).collect())
+}
+
+/// Like [`byte_range`], but reads the contents from the file the fix is for.
+pub(crate) fn file_byte_range(fix: &Fix) -> Option<Range<usize>> {
+    byte_range(&read(fix.loc.fullpath()).ok()?, fix)
+}
@@ -6,14 +6,22 @@ pub(crate) use error_loc::ErrorLoc;
 pub use errors::*;
 pub(crate) use filter::FilterRule;
 pub(crate) use output_style::OutputStyle;
-pub use report_struct::{Confidence, LogReport, PointedMessage, Severity};
+pub use report_struct::{Confidence, Fix, LogReport, PointedMessage, Severity};
+pub(crate) use suppress::check_comment;
+pub use suppress::report_unused_suppressions;
+pub(crate) use writer::kind_tag;
 
+mod baseline;
 mod builder;
+mod diff;
 mod error_key;
 mod error_loc;
 mod errors;
 mod filter;
+mod fix;
 mod output_style;
 mod report_struct;
+mod suppress;
 mod writer;
 mod writer_json;
+mod writer_sarif;
@@ -19,6 +19,8 @@ pub struct LogReport {
     pub info: Option<String>,
     /// Should contain one or more elements.
     pub pointers: Vec<PointedMessage>,
+    /// Edits that would fix the problem. Usually empty.
+    pub fixes: Vec<Fix>,
 }
 
 impl LogReport {
@@ -57,6 +59,19 @@ impl PointedMessage {
     }
 }
 
+/// A machine-applicable edit to a file, suggested by a report.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Fix {
+    /// Where the text to be replaced starts.
+    /// If it points to a whole file, the replacement is inserted at the start of the file.
+    pub loc: Loc,
+    /// The length of the text to be replaced, in characters.
+    /// Set this to 0 to insert the replacement at `loc`.
+    pub length: usize,
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
 /// Determines the output colour.
 /// User can also filter by minimum severity level: e.g. don't show me Info-level messages.
 ///
@@ -0,0 +1,114 @@
+//! Inline suppression of reports with `# tiger-ignore: key` comments in script and localization
+//! files.
+//!
+//! A suppression comment that is on a line by itself applies to the next line. A suppression
+//! comment at the end of a line applies to that line. Several keys can be given, separated by
+//! commas or spaces.
+
+use std::mem::take;
+use std::path::Path;
+use std::str::FromStr;
+
+use fnv::FnvHashMap;
+
+use crate::pathtable::{PathTable, PathTableIndex};
+use crate::report::{untidy, warn, ErrorKey, Errors, LogReport};
+use crate::token::Loc;
+
+const MARKER: &str = "tiger-ignore";
+
+/// One key in a suppression comment.
+#[derive(Debug)]
+struct Suppression {
+    key: ErrorKey,
+    /// The location of the comment.
+    loc: Loc,
+    /// Whether any report was suppressed by it.
+    used: bool,
+}
+
+/// The suppression comments, indexed by the file and line they apply to.
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    lines: FnvHashMap<(PathTableIndex, u32), Vec<Suppression>>,
+}
+
+impl Suppressions {
+    fn add(&mut self, key: ErrorKey, loc: Loc, line: u32) {
+        let suppression = Suppression { key, loc, used: false };
+        self.lines.entry((loc.idx, line)).or_default().push(suppression);
+    }
+
+    /// Returns true iff the report should be dropped because of a suppression comment.
+    /// The matching suppressions are marked as used.
+    pub fn suppresses(&mut self, report: &LogReport) -> bool {
+        let loc = report.primary().loc;
+        let mut suppressed = false;
+        if let Some(suppressions) = self.lines.get_mut(&(loc.idx, loc.line)) {
+            for suppression in suppressions {
+                if suppression.key == report.key {
+                    suppression.used = true;
+                    suppressed = true;
+                }
+            }
+        }
+        suppressed
+    }
+
+    /// Forget the suppression comments in a file, because it has changed and will be parsed again.
+    pub(crate) fn forget_file(&mut self, fullpath: &Path) {
+        self.lines.retain(|(idx, _), _| PathTable::lookup_fullpath(*idx) != fullpath);
+    }
+
+    fn unused(&self) -> Vec<(ErrorKey, Loc)> {
+        let mut unused: Vec<_> = self
+            .lines
+            .values()
+            .flatten()
+            .filter(|suppression| !suppression.used)
+            .map(|suppression| (suppression.key, suppression.loc))
+            .collect();
+        unused.sort_unstable_by_key(|(_, loc)| (loc.idx, loc.line, loc.column));
+        unused
+    }
+}
+
+/// Check if a comment is a suppression comment, and if so then record it.
+///
+/// `comment` is the text of the comment, starting with the `#`, and `loc` is where it starts.
+/// `own_line` is whether there is nothing but whitespace before the comment on its line.
+pub(crate) fn check_comment(comment: &str, loc: Loc, own_line: bool) {
+    let comment = comment.trim_start_matches('#').trim();
+    let Some(keys) = comment.strip_prefix(MARKER) else {
+        return;
+    };
+    let Some(keys) = keys.trim_start().strip_prefix(':') else {
+        let msg = format!("expected `{MARKER}: key`");
+        warn(ErrorKey::Suppression).msg(msg).loc(loc).push();
+        return;
+    };
+    let line = if own_line { loc.line + 1 } else { loc.line };
+    let mut found = Vec::new();
+    for word in keys.split(|c: char| c == ',' || c.is_whitespace()).filter(|w| !w.is_empty()) {
+        if let Ok(key) = ErrorKey::from_str(word) {
+            found.push(key);
+        } else {
+            let msg = format!("unknown error key `{word}` in {MARKER} comment");
+            warn(ErrorKey::Suppression).msg(msg).loc(loc).push();
+        }
+    }
+    let mut errors = Errors::get_mut();
+    for key in found {
+        errors.suppressions.add(key, loc, line);
+    }
+}
+
+/// Report the suppression comments that did not suppress any report, and forget about all the
+/// suppression comments. This should be called after all the validation is done.
+pub fn report_unused_suppressions() {
+    let unused = take(&mut Errors::get_mut().suppressions).unused();
+    for (key, loc) in unused {
+        let msg = format!("`{MARKER}: {key}` did not suppress any report");
+        untidy(ErrorKey::Suppression).msg(msg).loc(loc).push();
+    }
+}
@@ -1,6 +1,7 @@
 use serde_json::json;
 
 use crate::report::errors::Errors;
+use crate::report::fix::file_byte_range;
 use crate::report::writer::kind_tag;
 use crate::report::LogReport;
 
@@ -24,6 +25,20 @@ pub fn log_report_json(errors: &mut Errors, report: &LogReport) {
             })
         })
         .collect();
+    let fixes: Vec<_> = report
+        .fixes
+        .iter()
+        .filter_map(|fix| {
+            let range = file_byte_range(fix)?;
+            Some(json!({
+                "path": fix.loc.pathname(),
+                "fullpath": fix.loc.fullpath(),
+                "start": range.start,
+                "end": range.end,
+                "replacement": &fix.replacement,
+            }))
+        })
+        .collect();
     let report = json!({
         "severity": report.severity,
         "confidence": report.confidence,
@@ -31,6 +46,7 @@ pub fn log_report_json(errors: &mut Errors, report: &LogReport) {
         "message": &report.msg,
         "info": &report.info,
         "locations": pointers,
+        "fixes": fixes,
     });
 
     if let Err(e) = serde_json::to_writer_pretty(errors.output.get_mut(), &report) {
@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use fnv::FnvHashMap;
+use serde_json::{json, Value};
+
+use crate::game::Game;
+use crate::report::errors::Errors;
+use crate::report::writer::kind_tag;
+use crate::report::{Confidence, ErrorKey, LogReport, PointedMessage, Severity};
+use crate::util::{encode_uri_path, path_to_uri};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Log the reports as a single SARIF 2.1.0 log, for use by static analysis dashboards.
+///
+/// Each [`ErrorKey`] becomes a rule. The primary pointer of a report becomes its location, and
+/// the other pointers become related locations. File paths are relative to a base id that is
+/// named after the file's origin (such as `MOD` or the game name), and those base ids are mapped
+/// to the directories they stand for.
+pub fn log_reports_sarif(errors: &mut Errors, reports: &[LogReport]) {
+    let mut rules: Vec<ErrorKey> = Vec::new();
+    let mut roots: FnvHashMap<String, PathBuf> = FnvHashMap::default();
+    let mut results = Vec::new();
+
+    for report in reports {
+        let rule_index = rules.iter().position(|key| *key == report.key).unwrap_or_else(|| {
+            rules.push(report.key);
+            rules.len() - 1
+        });
+        let mut text = report.msg.clone();
+        if let Some(info) = &report.info {
+            text.push('\n');
+            text.push_str(info);
+        }
+        let locations: Vec<Value> = report
+            .pointers
+            .iter()
+            .enumerate()
+            .map(|(id, pointer)| location(errors, &mut roots, id, pointer))
+            .collect();
+        results.push(json!({
+            "ruleId": report.key,
+            "ruleIndex": rule_index,
+            "level": level(report.severity),
+            "rank": rank(report.confidence),
+            "message": { "text": text },
+            "locations": &locations[..1],
+            "relatedLocations": &locations[1..],
+            "properties": { "severity": report.severity, "confidence": report.confidence },
+        }));
+    }
+
+    let rules: Vec<Value> = rules.iter().map(|key| json!({ "id": key })).collect();
+    let original_uri_base_ids: FnvHashMap<String, Value> = roots
+        .into_iter()
+        .map(|(tag, root)| (tag, json!({ "uri": directory_uri(&root) })))
+        .collect();
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": Game::tool_name(),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": original_uri_base_ids,
+            "results": results,
+        }],
+    });
+
+    if let Err(e) = serde_json::to_writer_pretty(errors.output.get_mut(), &log) {
+        eprintln!("JSON error: {e:#}");
+    }
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Untidy | Severity::Tips => "note",
+    }
+}
+
+/// SARIF ranks go from 0.0 to 100.0, with higher ranks being more important.
+fn rank(confidence: Confidence) -> f64 {
+    match confidence {
+        Confidence::Weak => 25.0,
+        Confidence::Reasonable => 50.0,
+        Confidence::Strong => 75.0,
+    }
+}
+
+/// Convert a pointer to a SARIF `location`. Its file's root directory is recorded in `roots`.
+fn location(
+    errors: &mut Errors,
+    roots: &mut FnvHashMap<String, PathBuf>,
+    id: usize,
+    pointer: &PointedMessage,
+) -> Value {
+    let tag = kind_tag(errors, pointer.loc.kind).to_string();
+    let pathname = pointer.loc.pathname();
+    if let Some(root) = root_of(pointer.loc.fullpath(), pathname) {
+        roots.entry(tag.clone()).or_insert(root);
+    }
+
+    let mut region = json!({});
+    if pointer.loc.line > 0 {
+        region["startLine"] = json!(pointer.loc.line);
+        if pointer.loc.column > 0 {
+            region["startColumn"] = json!(pointer.loc.column);
+            if pointer.length > 0 {
+                region["endColumn"] = json!(usize::from(pointer.loc.column) + pointer.length);
+            }
+        }
+        if let Some(line) = errors.get_line(pointer.loc) {
+            region["snippet"] = json!({ "text": line });
+        }
+    }
+
+    let mut physical = json!({
+        "artifactLocation": {
+            "uri": encode_uri_path(pathname),
+            "uriBaseId": tag,
+        },
+    });
+    if pointer.loc.line > 0 {
+        physical["region"] = region;
+    }
+    let mut location = json!({ "id": id, "physicalLocation": physical });
+    if let Some(msg) = &pointer.msg {
+        location["message"] = json!({ "text": msg });
+    }
+    location
+}
+
+/// Find the directory that `pathname` is relative to, given the full path of the same file.
+fn root_of(fullpath: &Path, pathname: &Path) -> Option<PathBuf> {
+    let mut root = fullpath;
+    for _ in pathname.components() {
+        root = root.parent()?;
+    }
+    Some(root.to_path_buf())
+}
+
+/// Convert a directory to a `file://` URI. SARIF requires base URIs to end with a slash.
+fn directory_uri(dir: &Path) -> String {
+    let dir = std::env::current_dir().map_or_else(|_| dir.to_path_buf(), |cwd| cwd.join(dir));
+    let mut uri = path_to_uri(&dir);
+    if !uri.ends_with('/') {
+        uri.push('/');
+    }
+    uri
+}
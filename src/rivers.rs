@@ -2,16 +2,19 @@
 //!
 //! The `rivers.png` file has detailed requirements for its image format and the layout of every pixel.
 
-use std::fs::File;
+use std::fs::{write, File};
 use std::ops::{RangeInclusive, RangeToInclusive};
 use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use fnv::{FnvHashMap, FnvHashSet};
+use image::{GrayImage, ImageFormat, Rgb, RgbImage};
 use png::{ColorType, Decoder};
+use serde::Serialize;
 
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
+use crate::heightmap::{check_same_size, water_level};
 use crate::report::{err, warn, will_maybe_log, ErrorKey};
 
 /// The `rivers.png` has an indexed palette where the colors don't matter, only the index values
@@ -38,6 +41,63 @@ impl RiverPixels {
     const FIRST_IGNORE: u8 = 16;
 }
 
+/// The kinds of problems that can be found in `rivers.png`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum RiverProblemKind {
+    /// A source pixel that does not have exactly one river neighbor.
+    Source,
+    /// A tributary pixel that does not have at least two river neighbors.
+    Tributary,
+    /// A split pixel that does not have at least two river neighbors.
+    Split,
+    /// A segment that loops back on itself.
+    Loop,
+    /// A river pixel with more than two river neighbors.
+    Branch,
+    /// A single-pixel segment between two special pixels.
+    TwoSpecials,
+    /// A single river pixel not connected to any special pixel.
+    OrphanPixel,
+    /// A special pixel that ends more than one segment.
+    MultipleTerminations,
+    /// A segment not connected to any special pixel.
+    OrphanSegment,
+    /// A segment with special pixels at both ends.
+    TwoTerminators,
+    /// A river pixel surrounded by sea in the heightmap.
+    AtSea,
+    /// A river pixel that is higher than the land around it in the heightmap.
+    Ridge,
+}
+
+/// One problem found in `rivers.png`, with the pixels involved.
+/// For segment problems, `pixels` holds the whole segment from one end to the other.
+#[derive(Clone, Debug, Serialize)]
+struct RiverProblem {
+    kind: RiverProblemKind,
+    #[serde(rename = "message")]
+    msg: String,
+    pixels: Vec<(u32, u32)>,
+}
+
+impl RiverProblem {
+    fn new(kind: RiverProblemKind, msg: String, pixels: Vec<(u32, u32)>) -> Self {
+        Self { kind, msg, pixels }
+    }
+}
+
+/// How much higher than all the land around it a river pixel may be before it's reported as
+/// being on a ridge. This leaves some room for rounding in the heightmap.
+const RIDGE_TOLERANCE: u8 = 2;
+
+/// The color used to mark problems in the debug image. Magenta is not used in the usual
+/// `rivers.png` palette.
+const MARKER: Rgb<u8> = Rgb([255, 0, 255]);
+/// How far from a problem pixel to draw the square around it, so that single pixels stand out
+/// on a large map.
+const MARKER_RADIUS: u32 = 4;
+
 #[derive(Clone, Debug, Default)]
 pub struct Rivers {
     /// for error reporting
@@ -118,6 +178,7 @@ impl Rivers {
         &self,
         river_segments: FnvHashMap<(u32, u32), (u32, u32)>,
         mut specials: FnvHashMap<(u32, u32), bool>,
+        problems: &mut Vec<RiverProblem>,
     ) {
         let mut seen = FnvHashSet::default();
 
@@ -135,16 +196,25 @@ impl Rivers {
                         "({}, {}) river pixel connects two special pixels",
                         start.0, start.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                    problems.push(RiverProblem::new(
+                        RiverProblemKind::TwoSpecials,
+                        msg,
+                        vec![start],
+                    ));
                 } else if special_neighbors.is_empty() {
                     let msg = format!("({}, {}) orphan river pixel", start.0, start.1);
-                    warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                    problems.push(RiverProblem::new(
+                        RiverProblemKind::OrphanPixel,
+                        msg,
+                        vec![start],
+                    ));
                 } else {
                     let s = special_neighbors[0];
                     if specials[&s] {
                         let msg =
                             format!("({}, {}) pixel terminates multiple river segments", s.0, s.1);
-                        warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                        let kind = RiverProblemKind::MultipleTerminations;
+                        problems.push(RiverProblem::new(kind, msg, vec![s]));
                     } else {
                         specials.insert(s, true);
                     }
@@ -157,19 +227,23 @@ impl Rivers {
                         "({}, {}) - ({}, {}) orphan river segment",
                         start.0, start.1, end.0, end.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                    let pixels = self.trace_segment(start, end);
+                    problems.push(RiverProblem::new(RiverProblemKind::OrphanSegment, msg, pixels));
                 } else if special_neighbors.len() > 1 {
                     let msg = format!(
                         "({}, {}) - ({}, {}) river segment has two terminators",
                         start.0, start.1, end.0, end.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                    let pixels = self.trace_segment(start, end);
+                    let kind = RiverProblemKind::TwoTerminators;
+                    problems.push(RiverProblem::new(kind, msg, pixels));
                 } else {
                     let s = special_neighbors[0];
                     if specials[&s] {
                         let msg =
                             format!("({}, {}) pixel terminates multiple river segments", s.0, s.1);
-                        warn(ErrorKey::Rivers).msg(msg).loc(self.entry.as_ref().unwrap()).push();
+                        let kind = RiverProblemKind::MultipleTerminations;
+                        problems.push(RiverProblem::new(kind, msg, vec![s]));
                     } else {
                         specials.insert(s, true);
                     }
@@ -178,9 +252,34 @@ impl Rivers {
         }
     }
 
-    pub fn validate(&self, _data: &Everything) {
+    /// Follow a river segment from `start` to `end` and return all its pixels in order.
+    /// This is only meaningful once every normal river pixel is known to have at most two river
+    /// neighbors, which is the case by the time `validate_segments` is called.
+    fn trace_segment(&self, start: (u32, u32), end: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut pixels = vec![start];
+        let mut prev = None;
+        let mut current = start;
+        let mut river_neighbors = Vec::new();
+        // The length check is just a guard against looping forever on a malformed segment.
+        while current != end && pixels.len() < self.pixels.len() {
+            self.river_neighbors(current.0, current.1, &mut river_neighbors);
+            let Some(&next) = river_neighbors.iter().find(|&&c| Some(c) != prev) else {
+                break;
+            };
+            prev = Some(current);
+            current = next;
+            pixels.push(current);
+        }
+        pixels
+    }
+
+    pub fn validate(&self, data: &Everything) {
         // TODO: check image width and height against world defines
 
+        if self.entry.is_none() {
+            return;
+        }
+
         if self.color_type != Some(ColorType::Indexed) {
             let msg = "rivers.png should be in indexed color format (with 8-bit palette)";
             err(ErrorKey::ImageFormat).msg(msg).loc(self.entry.as_ref().unwrap()).push();
@@ -193,11 +292,37 @@ impl Rivers {
             return;
         }
 
+        if let Some((heightmap_png, heightmap)) = data.heightmap.get() {
+            let entry = self.entry.as_ref().unwrap();
+            check_same_size(
+                entry,
+                (self.width, self.height),
+                heightmap_png,
+                heightmap.dimensions(),
+            );
+        }
+
         // Early exit before expensive loop, if errors won't be logged anyway
         if !will_maybe_log(self.entry.as_ref().unwrap(), ErrorKey::Rivers) {
             return;
         }
 
+        for problem in self.find_problems(data) {
+            warn(ErrorKey::Rivers).msg(problem.msg).loc(self.entry.as_ref().unwrap()).push();
+        }
+    }
+
+    /// Return the heightmap if it's there and has the same size as `rivers.png`.
+    fn matching_heightmap<'a>(&self, data: &'a Everything) -> Option<&'a GrayImage> {
+        let (_, img) = data.heightmap.get()?;
+        (img.dimensions() == (self.width, self.height)).then_some(img)
+    }
+
+    /// Examine every pixel and return the problems found, in the order they were found.
+    /// The image must already be known to be in indexed color format.
+    fn find_problems(&self, data: &Everything) -> Vec<RiverProblem> {
+        let mut problems = Vec::new();
+
         // Maps each endpoint of a segment to the other endpoint.
         // Single-pixel segments map that coordinate to itself.
         // The river pixels that connect the endpoints are not remembered.
@@ -224,10 +349,11 @@ impl Rivers {
                         } else {
                             let msg =
                                 format!("({x}, {y}) river source (green) not at source of a river");
-                            warn(ErrorKey::Rivers)
-                                .msg(msg)
-                                .loc(self.entry.as_ref().unwrap())
-                                .push();
+                            problems.push(RiverProblem::new(
+                                RiverProblemKind::Source,
+                                msg,
+                                vec![(x, y)],
+                            ));
                             bad_problem = true;
                         }
                     }
@@ -239,10 +365,11 @@ impl Rivers {
                             let msg = format!(
                                 "({x}, {y}) river tributary (red) not joining another river",
                             );
-                            warn(ErrorKey::Rivers)
-                                .msg(msg)
-                                .loc(self.entry.as_ref().unwrap())
-                                .push();
+                            problems.push(RiverProblem::new(
+                                RiverProblemKind::Tributary,
+                                msg,
+                                vec![(x, y)],
+                            ));
                             bad_problem = true;
                         }
                     }
@@ -254,10 +381,11 @@ impl Rivers {
                             let msg = format!(
                                 "({x}, {y}) river split (yellow) not splitting off from a river",
                             );
-                            warn(ErrorKey::Rivers)
-                                .msg(msg)
-                                .loc(self.entry.as_ref().unwrap())
-                                .push();
+                            problems.push(RiverProblem::new(
+                                RiverProblemKind::Split,
+                                msg,
+                                vec![(x, y)],
+                            ));
                             bad_problem = true;
                         }
                     }
@@ -275,10 +403,11 @@ impl Rivers {
                                         // though.
                                         if third_end == (x, y) {
                                             let msg = format!("({x}, {y}) river forms a loop");
-                                            warn(ErrorKey::Rivers)
-                                                .msg(msg)
-                                                .loc(self.entry.as_ref().unwrap())
-                                                .push();
+                                            problems.push(RiverProblem::new(
+                                                RiverProblemKind::Loop,
+                                                msg,
+                                                vec![(x, y)],
+                                            ));
                                             bad_problem = true;
                                         } else {
                                             river_segments.insert(other_end, third_end);
@@ -303,10 +432,11 @@ impl Rivers {
                                 "({x}, {y}) river pixel has {} neighbors",
                                 river_neighbors.len()
                             );
-                            warn(ErrorKey::Rivers)
-                                .msg(msg)
-                                .loc(self.entry.as_ref().unwrap())
-                                .push();
+                            problems.push(RiverProblem::new(
+                                RiverProblemKind::Branch,
+                                msg,
+                                vec![(x, y)],
+                            ));
                             bad_problem = true;
                         }
                     }
@@ -315,7 +445,116 @@ impl Rivers {
             }
         }
         if !bad_problem {
-            self.validate_segments(river_segments, specials);
+            self.validate_segments(river_segments, specials, &mut problems);
+        }
+        if let Some(heightmap) = self.matching_heightmap(data) {
+            self.find_height_problems(heightmap, water_level(data), &mut problems);
+        }
+        problems
+    }
+
+    /// Check the normal river pixels against the heightmap. Rivers should run through valleys
+    /// and end where they reach the sea, so a river pixel that sits on a ridge or far out at sea
+    /// is probably misplaced.
+    fn find_height_problems(
+        &self,
+        heightmap: &GrayImage,
+        water_level: Option<u8>,
+        problems: &mut Vec<RiverProblem>,
+    ) {
+        let height = |x: u32, y: u32| heightmap.get_pixel(x, y).0[0];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !RiverPixels::NORMAL.contains(&self.pixel(x, y)) {
+                    continue;
+                }
+                let h = height(x, y);
+
+                let mut max_land = None;
+                let mut all_water = true;
+                for (nx, ny) in self.surrounding(x, y) {
+                    let nh = height(nx, ny);
+                    let is_orthogonal = nx == x || ny == y;
+                    if is_orthogonal && water_level.is_some_and(|level| nh > level) {
+                        all_water = false;
+                    }
+                    if self.pixel(nx, ny) >= RiverPixels::FIRST_IGNORE {
+                        max_land = max_land.max(Some(nh));
+                    }
+                }
+
+                if water_level.is_some_and(|level| h <= level) && all_water {
+                    let msg = format!("({x}, {y}) river pixel is out at sea in heightmap.png");
+                    problems.push(RiverProblem::new(RiverProblemKind::AtSea, msg, vec![(x, y)]));
+                } else if max_land.is_some_and(|max| h > max.saturating_add(RIDGE_TOLERANCE)) {
+                    let msg = format!(
+                        "({x}, {y}) river pixel is higher than the land around it in heightmap.png"
+                    );
+                    problems.push(RiverProblem::new(RiverProblemKind::Ridge, msg, vec![(x, y)]));
+                }
+            }
+        }
+    }
+
+    /// Iterate over the up to 8 pixels that surround the given pixel.
+    fn surrounding(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let xs = x.saturating_sub(1)..=(x + 1).min(self.width - 1);
+        let ys = y.saturating_sub(1)..=(y + 1).min(self.height - 1);
+        ys.flat_map(move |ny| xs.clone().map(move |nx| (nx, ny)))
+            .filter(move |&(nx, ny)| (nx, ny) != (x, y))
+    }
+
+    /// Write a copy of `rivers.png` to `path` with every problem marked, and a JSON list of the
+    /// problems and their pixel coordinates next to it, with the extension changed to `.json`.
+    ///
+    /// Unlike `validate`, this ignores the report filters: the point is to see every problem.
+    pub fn write_debug(&self, path: &Path, data: &Everything) -> Result<()> {
+        let Some(entry) = &self.entry else {
+            bail!("there is no map_data/rivers.png to check");
+        };
+        if self.color_type != Some(ColorType::Indexed) {
+            bail!("{entry} is not in indexed color format");
+        }
+        let Some(palette) = &self.palette else {
+            bail!("{entry} does not have a palette");
+        };
+        let problems = self.find_problems(data);
+
+        let mut img = RgbImage::from_fn(self.width, self.height, |x, y| {
+            let idx = usize::from(self.pixel(x, y)) * 3;
+            palette.get(idx..idx + 3).map_or(Rgb([0, 0, 0]), |rgb| Rgb([rgb[0], rgb[1], rgb[2]]))
+        });
+        for problem in &problems {
+            for &(x, y) in &problem.pixels {
+                img.put_pixel(x, y, MARKER);
+            }
+            // Draw a square around each end of the problem, so it can be spotted when zoomed out.
+            let ends = [problem.pixels.first(), problem.pixels.last()];
+            for &(x, y) in ends.into_iter().flatten() {
+                self.draw_square(&mut img, x, y);
+            }
+        }
+        img.save_with_format(path, ImageFormat::Png)
+            .with_context(|| format!("could not write {}", path.display()))?;
+
+        let json_path = path.with_extension("json");
+        let output = serde_json::to_string_pretty(&problems)?;
+        write(&json_path, output)
+            .with_context(|| format!("could not write {}", json_path.display()))
+    }
+
+    fn draw_square(&self, img: &mut RgbImage, x: u32, y: u32) {
+        let min_x = x.saturating_sub(MARKER_RADIUS);
+        let min_y = y.saturating_sub(MARKER_RADIUS);
+        let max_x = (x + MARKER_RADIUS).min(self.width - 1);
+        let max_y = (y + MARKER_RADIUS).min(self.height - 1);
+        for x in min_x..=max_x {
+            img.put_pixel(x, min_y, MARKER);
+            img.put_pixel(x, max_y, MARKER);
+        }
+        for y in min_y..=max_y {
+            img.put_pixel(min_x, y, MARKER);
+            img.put_pixel(max_x, y, MARKER);
         }
     }
 }
@@ -7,6 +7,7 @@ use bitflags::bitflags;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::game::Game;
+use crate::helpers::renamed_to;
 use crate::item::Item;
 use crate::report::{err, ErrorKey};
 use crate::token::Token;
@@ -329,7 +330,11 @@ pub fn scope_to_scope(name: &Token, inscopes: Scopes) -> Option<(Scopes, Scopes)
         scopes
     } else if let Some((version, explanation)) = scope_to_scope_removed(&name_lc) {
         let msg = format!("`{name}` was removed in {version}");
-        err(ErrorKey::Removed).strong().msg(msg).info(explanation).loc(name).push();
+        let mut builder = err(ErrorKey::Removed).strong().msg(msg).info(explanation).loc(name);
+        if let Some(new_name) = renamed_to(explanation) {
+            builder = builder.fix(name, new_name);
+        }
+        builder.push();
         return Some((Scopes::all(), Scopes::all_but_none()));
     } else {
         None
@@ -20,6 +20,7 @@ use crate::validate::{
     validate_iterator_fields, validate_scope_chain, ListType,
 };
 use crate::validator::Validator;
+use crate::value_range::check_script_value;
 
 /// Validate a block that's part of a script value.
 /// * `have_value`: indicates whether this script value has had some sort of value set already.
@@ -295,11 +296,13 @@ fn validate_bv(bv: &BV, data: &Everything, sc: &mut ScopeContext, check_desc: bo
 
 pub fn validate_script_value(bv: &BV, data: &Everything, sc: &mut ScopeContext) {
     validate_bv(bv, data, sc, true);
+    check_script_value(bv, data);
 }
 
 #[cfg(any(feature = "ck3", feature = "imperator"))] // happens not to be used by vic3; silence dead code warning
 pub fn validate_script_value_no_breakdown(bv: &BV, data: &Everything, sc: &mut ScopeContext) {
     validate_bv(bv, data, sc, false);
+    check_script_value(bv, data);
 }
 
 /// Validate a script value that's not allowed to do calculations. It must be a literal or the name of another script value
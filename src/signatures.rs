@@ -0,0 +1,79 @@
+//! Export of the scope signatures of scripted items.
+//!
+//! A signature says what a scripted trigger, scripted effect, script value, or scripted modifier
+//! needs from the scope context of whoever calls it: the scope types of `root`, `this`, and `prev`,
+//! and which named scopes and lists must already be set. Each requirement points at the line in
+//! the item that caused it. Call sites that don't meet the requirements are reported during
+//! validation; this export is for looking up what an item expects.
+
+use std::fs::write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::context::ScopeSignature;
+use crate::everything::Everything;
+use crate::scopes::Scopes;
+use crate::token::Token;
+
+/// One scope requirement as JSON, or `null` if any scope will do.
+fn requirement((scopes, token): &(Scopes, Token)) -> Value {
+    if *scopes == Scopes::all() {
+        return Value::Null;
+    }
+    json!({
+        "scopes": scopes.to_string(),
+        "path": token.loc.pathname(),
+        "linenr": token.loc.line,
+    })
+}
+
+fn named_requirements(names: &[(String, Scopes, Token)]) -> Value {
+    names
+        .iter()
+        .map(|(name, scopes, token)| {
+            json!({
+                "name": name,
+                "scopes": scopes.to_string(),
+                "path": token.loc.pathname(),
+                "linenr": token.loc.line,
+            })
+        })
+        .collect()
+}
+
+fn signatures_of<'a>(
+    kind: &str,
+    signatures: impl Iterator<Item = (&'a Token, ScopeSignature)>,
+) -> Vec<Value> {
+    let mut signatures: Vec<_> = signatures.collect();
+    signatures.sort_unstable_by_key(|(key, _)| key.loc);
+    signatures
+        .into_iter()
+        .map(|(key, signature)| {
+            json!({
+                "kind": kind,
+                "key": key.as_str(),
+                "path": key.loc.pathname(),
+                "linenr": key.loc.line,
+                "root": requirement(&signature.root),
+                "this": requirement(&signature.this),
+                "prev": requirement(&signature.prev),
+                "named_scopes": named_requirements(&signature.named),
+                "lists": named_requirements(&signature.lists),
+            })
+        })
+        .collect()
+}
+
+/// Write the signatures of all the validated scripted items to `path` as JSON.
+pub(crate) fn write_signatures(data: &Everything, path: &Path) -> Result<()> {
+    let mut signatures = signatures_of("scripted_trigger", data.triggers.iter_signatures());
+    signatures.extend(signatures_of("scripted_effect", data.effects.iter_signatures()));
+    signatures.extend(signatures_of("script_value", data.script_values.iter_signatures()));
+    signatures
+        .extend(signatures_of("scripted_modifier", data.scripted_modifiers.iter_signatures()));
+    let output = serde_json::to_string_pretty(&signatures)?;
+    write(path, output).with_context(|| format!("could not write {}", path.display()))
+}
@@ -12,6 +12,8 @@ use crate::date::Date;
 use crate::desc::validate_desc;
 use crate::everything::Everything;
 use crate::game::Game;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::helpers::renamed_to;
 use crate::helpers::stringify_choices;
 #[cfg(feature = "vic3")]
 use crate::helpers::stringify_list;
@@ -26,11 +28,13 @@ use crate::scopes::{
 use crate::script_value::validate_script_value;
 use crate::token::{Loc, Token};
 use crate::tooltipped::Tooltipped;
+use crate::trigger_logic::check_trigger_logic;
 use crate::validate::{
     precheck_iterator_fields, validate_ifelse_sequence, validate_inside_iterator,
     validate_iterator_fields, ListType,
 };
 use crate::validator::Validator;
+use crate::variables::VariableKind;
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::misc::{APPROVALS, LEVELS};
 
@@ -65,7 +69,7 @@ pub fn validate_trigger(
     sc: &mut ScopeContext,
     tooltipped: Tooltipped,
 ) -> bool {
-    validate_trigger_internal(
+    let side_effects = validate_trigger_internal(
         Lowercase::empty(),
         false,
         block,
@@ -74,7 +78,9 @@ pub fn validate_trigger(
         tooltipped,
         false,
         Severity::Error,
-    )
+    );
+    check_trigger_logic(block, data);
+    side_effects
 }
 
 /// Like [`validate_trigger`] but specifies a maximum [`Severity`] for the reports emitted by this
@@ -88,7 +94,7 @@ pub fn validate_trigger_max_sev(
     tooltipped: Tooltipped,
     max_sev: Severity,
 ) -> bool {
-    validate_trigger_internal(
+    let side_effects = validate_trigger_internal(
         Lowercase::empty(),
         false,
         block,
@@ -97,7 +103,9 @@ pub fn validate_trigger_max_sev(
         tooltipped,
         false,
         max_sev,
-    )
+    );
+    check_trigger_logic(block, data);
+    side_effects
 }
 
 /// The interface to trigger validation when [`validate_trigger`] is too limited.
@@ -323,6 +331,7 @@ pub fn validate_trigger_key_bv(
 
     // Scripted trigger?
     if let Some(trigger) = data.get_trigger(key) {
+        data.mark_used(Item::ScriptedTrigger, key.as_str());
         match bv {
             BV::Value(token) => {
                 if !(token.is("yes") || token.is("no") || token.is("YES") || token.is("NO")) {
@@ -460,6 +469,7 @@ pub fn validate_trigger_key_bv(
                         return side_effects;
                     }
                     validate_inscopes(part_flags, part, inscopes, sc);
+                    data.variables.record_field(part, bv, sc.scopes());
                     if sc.scopes() == Scopes::None && part_lc == "current_year" {
                         warn(ErrorKey::Bugs)
                             .msg("current_year does not work in empty scope")
@@ -978,7 +988,11 @@ fn match_trigger_bv(
         }
         #[cfg(any(feature = "ck3", feature = "vic3"))]
         Trigger::Removed(msg, info) => {
-            err(ErrorKey::Removed).msg(*msg).info(*info).loc(name).push();
+            let mut builder = err(ErrorKey::Removed).msg(*msg).info(*info).loc(name);
+            if let Some(new_name) = renamed_to(info) {
+                builder = builder.fix(name, new_name);
+            }
+            builder.push();
         }
         Trigger::UncheckedValue => {
             bv.expect_value();
@@ -1362,6 +1376,9 @@ pub fn validate_argument_scope(
 ) {
     validate_inscopes(part_flags, func, inscopes, sc);
     validate_argument_internal(arg, validation, data, sc);
+    if let Some(kind) = VariableKind::from_prefix(func.as_str()) {
+        data.variables.record_read(kind, arg, sc.scopes());
+    }
 
     let mut outscopes_token = func.clone();
     outscopes_token.combine(arg, ':');
@@ -0,0 +1,398 @@
+//! Folding triggers to constants, to find the ones that can never be true or are always true.
+//!
+//! After a trigger has been validated, its structure is evaluated as far as that's possible
+//! without knowing the game state. `always = yes` and `always = no` are constants, `AND`, `OR`,
+//! `NOT` and their relatives combine them, and scripted triggers without parameters fold to the
+//! value of their body. Within a conjunction, the numeric comparisons of the same trigger are
+//! combined into a range, so that for example `age > 50` together with `age < 20` is found to be
+//! impossible.
+//!
+//! A plain `always = no` at the top of a trigger is the usual way to switch something off, so
+//! that is not reported. Only logic that reduces to a constant in a less obvious way is.
+
+use std::cmp::Ordering;
+
+use fnv::FnvHashMap;
+
+use crate::block::{Block, BlockItem, Comparator, Eq::*, Field, BV};
+use crate::data::scripted_triggers::Trigger;
+use crate::everything::Everything;
+use crate::report::{warn, ErrorKey};
+use crate::scopes::{scope_prefix, scope_to_scope, Scopes};
+use crate::token::Token;
+use crate::trigger::trigger_comparevalue;
+
+/// The fields of an `any_` iterator that are not triggers.
+const ITERATOR_FIELDS: &[&str] = &["count", "percent", "filter", "list", "variable", "type"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+impl Truth {
+    fn not(self) -> Self {
+        match self {
+            Truth::True => Truth::False,
+            Truth::False => Truth::True,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Truth::True => "is always true",
+            Truth::False => "can never be true",
+            Truth::Unknown => "depends on the game state",
+        }
+    }
+}
+
+/// The folded value of a trigger, and whether a report has already explained that value.
+#[derive(Debug, Clone, Copy)]
+struct Folded {
+    truth: Truth,
+    reported: bool,
+}
+
+impl Folded {
+    const UNKNOWN: Folded = Folded { truth: Truth::Unknown, reported: false };
+
+    fn new(truth: Truth) -> Self {
+        Self { truth, reported: false }
+    }
+
+    fn not(self) -> Self {
+        Self { truth: self.truth.not(), ..self }
+    }
+}
+
+/// Combine the values of triggers that must all be true.
+fn all(folded: &[Folded]) -> Folded {
+    if folded.iter().any(|f| f.truth == Truth::False) {
+        let reported = folded.iter().any(|f| f.truth == Truth::False && f.reported);
+        Folded { truth: Truth::False, reported }
+    } else if folded.iter().all(|f| f.truth == Truth::True) {
+        Folded { truth: Truth::True, reported: folded.iter().any(|f| f.reported) }
+    } else {
+        Folded::UNKNOWN
+    }
+}
+
+/// Combine the values of triggers of which at least one must be true.
+fn any(folded: &[Folded]) -> Folded {
+    let negated: Vec<Folded> = folded.iter().map(|f| f.not()).collect();
+    all(&negated).not()
+}
+
+/// The values that a numeric comparison trigger can still have within one conjunction.
+#[derive(Debug, Default)]
+struct Range<'b> {
+    /// The lower bound, whether it's included, and the comparison that set it.
+    lower: Option<(f64, bool, &'b Field)>,
+    /// The upper bound, whether it's included, and the comparison that set it.
+    upper: Option<(f64, bool, &'b Field)>,
+    /// Whether a contradiction was already found.
+    empty: bool,
+}
+
+impl<'b> Range<'b> {
+    fn raise_lower(&mut self, value: f64, included: bool, field: &'b Field) {
+        let tighter =
+            self.lower.map_or(true, |(lower, lower_included, _)| match value.total_cmp(&lower) {
+                Ordering::Greater => true,
+                Ordering::Equal => lower_included && !included,
+                Ordering::Less => false,
+            });
+        if tighter {
+            self.lower = Some((value, included, field));
+        }
+    }
+
+    fn lower_upper(&mut self, value: f64, included: bool, field: &'b Field) {
+        let tighter =
+            self.upper.map_or(true, |(upper, upper_included, _)| match value.total_cmp(&upper) {
+                Ordering::Less => true,
+                Ordering::Equal => upper_included && !included,
+                Ordering::Greater => false,
+            });
+        if tighter {
+            self.upper = Some((value, included, field));
+        }
+    }
+
+    /// Narrow the range by one comparison. If that makes it empty, return the earlier comparison
+    /// that it contradicts.
+    fn narrow(&mut self, cmp: Comparator, value: f64, field: &'b Field) -> Option<&'b Field> {
+        match cmp {
+            Comparator::GreaterThan => self.raise_lower(value, false, field),
+            Comparator::AtLeast => self.raise_lower(value, true, field),
+            Comparator::LessThan => self.lower_upper(value, false, field),
+            Comparator::AtMost => self.lower_upper(value, true, field),
+            Comparator::Equals(Single | Double) => {
+                self.raise_lower(value, true, field);
+                self.lower_upper(value, true, field);
+            }
+            Comparator::Equals(Question) | Comparator::NotEquals => return None,
+        }
+        if self.empty {
+            return None;
+        }
+        let (lower, lower_included, lower_field) = self.lower?;
+        let (upper, upper_included, upper_field) = self.upper?;
+        let empty = match lower.total_cmp(&upper) {
+            Ordering::Greater => true,
+            Ordering::Equal => !lower_included || !upper_included,
+            Ordering::Less => false,
+        };
+        if !empty {
+            return None;
+        }
+        self.empty = true;
+        if std::ptr::eq(lower_field, field) {
+            Some(upper_field)
+        } else {
+            Some(lower_field)
+        }
+    }
+}
+
+fn show_comparison(Field(key, cmp, bv): &Field) -> String {
+    match bv {
+        BV::Value(value) => format!("{key} {cmp} {value}"),
+        BV::Block(_) => format!("{key} {cmp} {{ ... }}"),
+    }
+}
+
+/// Return true iff `key` is a chain of scope transitions, such as `scope:actor.father`.
+fn is_scope_chain(key: &Token) -> bool {
+    key.split('.').iter().all(|part| {
+        if let Some((prefix, _)) = part.split_once(':') {
+            scope_prefix(&prefix).is_some()
+        } else {
+            part.lowercase_is("root")
+                || part.lowercase_is("this")
+                || part.lowercase_is("prev")
+                || scope_to_scope(part, Scopes::all()).is_some()
+        }
+    })
+}
+
+struct Folder<'a> {
+    data: &'a Everything,
+    /// The scripted triggers currently being folded, to guard against recursion.
+    stack: Vec<&'static str>,
+    /// The folded values of the scripted triggers seen so far.
+    memo: FnvHashMap<&'static str, Truth>,
+    /// How many times the recursion guard has cut a fold short.
+    cut_short: usize,
+}
+
+impl<'a> Folder<'a> {
+    fn new(data: &'a Everything) -> Self {
+        Self { data, stack: Vec::new(), memo: FnvHashMap::default(), cut_short: 0 }
+    }
+
+    /// Fold a block in which all the triggers must be true, skipping the fields in `skip`.
+    /// Only reports if `report` is true.
+    fn conjunction(&mut self, block: &Block, report: bool, skip: &[&str]) -> Folded {
+        let mut ranges: FnvHashMap<String, Range> = FnvHashMap::default();
+        let mut folded = Vec::new();
+        for item in block.iter_items() {
+            let BlockItem::Field(field) = item else {
+                folded.push(Folded::UNKNOWN);
+                continue;
+            };
+            if skip.iter().any(|name| field.key().lowercase_is(name)) {
+                continue;
+            }
+            if let Some(f) = self.comparison(field, &mut ranges, report) {
+                folded.push(f);
+            } else {
+                folded.push(self.field(field, report));
+            }
+        }
+        all(&folded)
+    }
+
+    /// Fold each item of a block separately.
+    fn items(&mut self, block: &Block, report: bool) -> Vec<Folded> {
+        block
+            .iter_items()
+            .map(|item| match item {
+                BlockItem::Field(field) => self.field(field, report),
+                _ => Folded::UNKNOWN,
+            })
+            .collect()
+    }
+
+    /// Narrow the range of a numeric comparison such as `age > 50`. Returns `None` if the field
+    /// isn't one.
+    fn comparison<'b>(
+        &self,
+        field: &'b Field,
+        ranges: &mut FnvHashMap<String, Range<'b>>,
+        report: bool,
+    ) -> Option<Folded> {
+        let Field(key, cmp, BV::Value(value)) = field else { return None };
+        let number = value.get_number()?;
+        if key.as_str().contains('$') {
+            return None;
+        }
+        trigger_comparevalue(&key.split('.').pop()?, self.data)?;
+        let range = ranges.entry(key.as_str().to_ascii_lowercase()).or_default();
+        let Some(other) = range.narrow(*cmp, number, field) else {
+            return Some(Folded::UNKNOWN);
+        };
+        if report {
+            let msg = format!(
+                "`{}` can never be true together with `{}`",
+                show_comparison(field),
+                show_comparison(other)
+            );
+            warn(ErrorKey::Logic).msg(msg).loc(key).loc_msg(other.key(), "other comparison").push();
+        }
+        Some(Folded { truth: Truth::False, reported: report })
+    }
+
+    fn field(&mut self, field: &Field, report: bool) -> Folded {
+        let Field(key, cmp, bv) = field;
+        let key_lc = key.as_str().to_ascii_lowercase();
+        match bv {
+            BV::Value(value) if matches!(cmp, Comparator::Equals(Single | Double)) => {
+                if key_lc == "always" {
+                    if value.lowercase_is("yes") {
+                        Folded::new(Truth::True)
+                    } else if value.lowercase_is("no") {
+                        Folded::new(Truth::False)
+                    } else {
+                        Folded::UNKNOWN
+                    }
+                } else if value.lowercase_is("yes") || value.lowercase_is("no") {
+                    self.scripted_call(key, value, report)
+                } else {
+                    Folded::UNKNOWN
+                }
+            }
+            BV::Value(_) => Folded::UNKNOWN,
+            BV::Block(block) => match key_lc.as_str() {
+                "and" | "or" | "not" | "nor" | "nand" | "all_false" | "any_false" => {
+                    self.control(key, &key_lc, block, report)
+                }
+                "custom_description" | "custom_tooltip" => {
+                    self.conjunction(block, report, &["text", "subject"])
+                }
+                _ if key_lc.starts_with("any_") => {
+                    let inner = self.conjunction(block, report, ITERATOR_FIELDS);
+                    let counted = block.has_key("count") || block.has_key("percent");
+                    // An iterator over an empty list is false, so only falseness carries over.
+                    if inner.truth == Truth::False && !counted {
+                        inner
+                    } else {
+                        Folded::UNKNOWN
+                    }
+                }
+                _ if is_scope_chain(key) => {
+                    let inner = self.conjunction(block, report, &[]);
+                    // The scope might not exist, so only falseness carries over.
+                    if inner.truth == Truth::False {
+                        inner
+                    } else {
+                        Folded::UNKNOWN
+                    }
+                }
+                _ => Folded::UNKNOWN,
+            },
+        }
+    }
+
+    fn control(&mut self, key: &Token, key_lc: &str, block: &Block, report: bool) -> Folded {
+        if block.iter_items().next().is_none() {
+            return Folded::UNKNOWN;
+        }
+        let mut folded = match key_lc {
+            "and" => self.conjunction(block, report, &[]),
+            "nand" | "any_false" => self.conjunction(block, report, &[]).not(),
+            "or" => any(&self.items(block, report)),
+            "nor" | "all_false" => any(&self.items(block, report)).not(),
+            _ => {
+                // A `NOT` with more than one trigger in it is ambiguous, so only fold it if
+                // reading it as `NAND` or as `NOR` gives the same answer.
+                let nand = self.conjunction(block, report, &[]).not();
+                if block.iter_items().count() == 1 {
+                    nand
+                } else {
+                    let nor = any(&self.items(block, false)).not();
+                    if nand.truth == nor.truth {
+                        nand
+                    } else {
+                        Folded::UNKNOWN
+                    }
+                }
+            }
+        };
+        if report && folded.truth != Truth::Unknown && !folded.reported {
+            let msg = format!("this `{key}` {}", folded.truth.describe());
+            warn(ErrorKey::Logic).msg(msg).loc(key).push();
+            folded.reported = true;
+        }
+        folded
+    }
+
+    /// Fold a call to a scripted trigger, such as `my_trigger = yes`.
+    fn scripted_call(&mut self, key: &Token, value: &Token, report: bool) -> Folded {
+        let Some(trigger) = self.data.get_trigger(key) else { return Folded::UNKNOWN };
+        let mut truth = self.scripted(trigger);
+        if truth == Truth::Unknown {
+            return Folded::UNKNOWN;
+        }
+        let body = truth.describe();
+        if value.lowercase_is("no") {
+            truth = truth.not();
+        }
+        if report {
+            let msg = format!("`{key} = {value}` {}", truth.describe());
+            let info = format!("scripted trigger {key} {body}");
+            warn(ErrorKey::Logic)
+                .msg(msg)
+                .info(info)
+                .loc(key)
+                .loc_msg(&trigger.key, "defined here")
+                .push();
+        }
+        Folded { truth, reported: report }
+    }
+
+    fn scripted(&mut self, trigger: &Trigger) -> Truth {
+        // Triggers with parameters can only be folded per call.
+        if trigger.block.source.is_some() {
+            return Truth::Unknown;
+        }
+        let name = trigger.key.as_str();
+        if let Some(truth) = self.memo.get(name) {
+            return *truth;
+        }
+        if self.stack.contains(&name) {
+            self.cut_short += 1;
+            return Truth::Unknown;
+        }
+        let cut_short = self.cut_short;
+        self.stack.push(name);
+        let truth = self.conjunction(&trigger.block, false, &[]).truth;
+        self.stack.pop();
+        // A value that depended on the recursion guard might be different when folded from
+        // elsewhere, so it isn't remembered.
+        if self.cut_short == cut_short {
+            self.memo.insert(name, truth);
+        }
+        truth
+    }
+}
+
+/// Report the parts of the trigger `block` that fold to a constant, and the numeric comparisons
+/// in it that contradict each other.
+pub fn check_trigger_logic(block: &Block, data: &Everything) {
+    Folder::new(data).conjunction(block, true, &[]);
+}
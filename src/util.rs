@@ -1,6 +1,7 @@
 //! Miscellaneous helper functions related to filesystem operations.
 
 use std::borrow::Borrow;
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 
 /// A trait to join a string that may contain `.` or `..` to a path, and process those components
@@ -44,3 +45,52 @@ impl SmartJoin for Path {
 pub fn fix_slashes_for_target_platform<P: Borrow<Path>>(path: P) -> PathBuf {
     path.borrow().components().collect()
 }
+
+/// Percent-encode a path for use in a URI, with forward slashes between its components.
+pub(crate) fn encode_uri_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~:".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Convert an absolute path to a `file://` URI.
+pub(crate) fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let encoded = encode_uri_path(path);
+    if !encoded.starts_with('/') {
+        // Windows drive letter
+        uri.push('/');
+    }
+    uri.push_str(&encoded);
+    uri
+}
+
+/// Convert a `file://` URI to a path. Returns `None` for other kinds of URI.
+pub(crate) fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut iter = encoded.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    #[cfg(windows)]
+    let path = if path.starts_with('/') && path.get(2..3) == Some(":") {
+        path[1..].to_string()
+    } else {
+        path
+    };
+    Some(PathBuf::from(path))
+}
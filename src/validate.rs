@@ -15,9 +15,7 @@ use crate::game::Game;
 use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::report::{err, fatal, report, warn, Confidence, ErrorKey, Severity};
-#[cfg(feature = "ck3")]
-use crate::scopes::Scopes;
-use crate::scopes::{scope_prefix, scope_to_scope};
+use crate::scopes::{scope_prefix, scope_to_scope, Scopes};
 use crate::script_value::{validate_non_dynamic_script_value, validate_script_value};
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -28,6 +26,10 @@ use crate::trigger::{
     validate_trigger_internal, warn_not_first, Part, PartFlags,
 };
 use crate::validator::Validator;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::value_range::check_ai_chance;
+use crate::value_range::check_modifier;
+use crate::variables::VariableKind;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ListType {
@@ -346,6 +348,18 @@ pub fn validate_inside_iterator(
             )
         });
     }
+    let kind = match name.as_str() {
+        "in_list" => Some(VariableKind::VariableList),
+        "in_local_list" => Some(VariableKind::LocalVariableList),
+        "in_global_list" => Some(VariableKind::GlobalVariableList),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        if let Some(token) = block.get_field_value("variable") {
+            // The scope here is already that of the list entries, not the one holding the list.
+            data.variables.record_read(kind, token, Scopes::all());
+        }
+    }
 
     #[cfg(feature = "ck3")]
     if Game::is_ck3() {
@@ -524,6 +538,7 @@ pub fn validate_modifiers(vd: &mut Validator, sc: &mut ScopeContext) {
             false,
             Severity::Error,
         );
+        check_modifier(b, data);
     });
     #[cfg(feature = "ck3")]
     if Game::is_ck3() {
@@ -563,6 +578,7 @@ pub fn validate_scripted_modifier_call(
     data: &Everything,
     sc: &mut ScopeContext,
 ) {
+    data.mark_used(Item::ScriptedModifier, key.as_str());
     match bv {
         BV::Value(token) => {
             if !modifier.macro_parms().is_empty() {
@@ -622,7 +638,11 @@ pub fn validate_scripted_modifier_calls(
 pub fn validate_ai_chance(bv: &BV, data: &Everything, sc: &mut ScopeContext) {
     match bv {
         BV::Value(t) => _ = t.expect_number(),
-        BV::Block(b) => validate_modifiers_with_base(b, data, sc),
+        BV::Block(b) => {
+            validate_modifiers_with_base(b, data, sc);
+            #[cfg(any(feature = "ck3", feature = "vic3"))]
+            check_ai_chance(b, data);
+        }
     }
 }
 
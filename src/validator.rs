@@ -8,6 +8,8 @@ use crate::context::ScopeContext;
 use crate::date::Date;
 use crate::everything::Everything;
 use crate::helpers::dup_assign_error;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::helpers::renamed_to;
 use crate::item::Item;
 #[cfg(feature = "ck3")]
 use crate::report::fatal;
@@ -186,7 +188,11 @@ impl<'a> Validator<'a> {
         let sev = Severity::Error.at_most(self.max_severity);
         self.multi_field_check(name, |key, _| {
             let msg = format!("`{name}` has been replaced by {replaced_by}");
-            report(ErrorKey::Validation, sev).msg(msg).loc(key).push();
+            let mut builder = report(ErrorKey::Validation, sev).msg(msg).loc(key);
+            if let Some(new_name) = renamed_to(replaced_by) {
+                builder = builder.fix(key, new_name);
+            }
+            builder.push();
         });
     }
 
@@ -0,0 +1,418 @@
+//! Static range evaluation of script values.
+//!
+//! Script values are evaluated with intervals instead of numbers: each value is known to lie
+//! somewhere between a lowest and a highest possible value. Literal numbers and defines are exact,
+//! builtin values such as `age` have their declared ranges, and the operators of the script value
+//! narrow or widen the range as it's calculated. Anything that can't be known, such as a value
+//! read from a variable, can be anything at all.
+//!
+//! This finds divisions by a value that may be zero, `random_list` weights that are always zero,
+//! modifiers that always come out as 0, and `ai_chance` blocks that can never be positive.
+
+use fnv::FnvHashMap;
+
+use crate::block::{Block, BlockItem, Field, BV};
+use crate::everything::Everything;
+#[cfg(feature = "ck3")]
+use crate::game::Game;
+use crate::report::{err, warn, ErrorKey};
+use crate::token::Token;
+
+/// The modifier blocks whose effect on a weight depends on things that can't be known here.
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+const OPAQUE_MODIFIERS: &[&str] = &[
+    "compare_modifier",
+    "opinion_modifier",
+    "ai_value_modifier",
+    "compatibility_modifier",
+    "scheme_modifier",
+    "activity_modifier",
+];
+
+/// The possible values of a number, from `min` to `max` inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Interval {
+    min: f64,
+    max: f64,
+}
+
+impl Interval {
+    const ANY: Interval = Interval { min: f64::NEG_INFINITY, max: f64::INFINITY };
+
+    fn point(value: f64) -> Self {
+        Self { min: value, max: value }
+    }
+
+    fn is_zero(self) -> bool {
+        self.min >= 0.0 && self.max <= 0.0
+    }
+
+    fn contains_zero(self) -> bool {
+        self.min <= 0.0 && self.max >= 0.0
+    }
+
+    fn is_finite(self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    /// The interval spanned by the results of applying `f` to the bounds of both intervals.
+    fn combine(self, other: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        let results = [
+            f(self.min, other.min),
+            f(self.min, other.max),
+            f(self.max, other.min),
+            f(self.max, other.max),
+        ];
+        if results.iter().any(|r| r.is_nan()) {
+            return Self::ANY;
+        }
+        Self {
+            min: results.iter().copied().fold(f64::INFINITY, f64::min),
+            max: results.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        self.combine(other, |a, b| a + b)
+    }
+
+    fn subtract(self, other: Self) -> Self {
+        self.combine(other, |a, b| a - b)
+    }
+
+    fn multiply(self, other: Self) -> Self {
+        // Zero times anything is zero, even if the anything is unbounded.
+        self.combine(other, |a, b| if a == 0.0 || b == 0.0 { 0.0 } else { a * b })
+    }
+
+    fn divide(self, other: Self) -> Self {
+        if other.contains_zero() {
+            Self::ANY
+        } else {
+            self.combine(other, |a, b| a / b)
+        }
+    }
+
+    fn modulo(self, other: Self) -> Self {
+        if other.contains_zero() {
+            return Self::ANY;
+        }
+        let limit = other.min.abs().max(other.max.abs());
+        if self.min >= 0.0 {
+            Self { min: 0.0, max: limit.min(self.max) }
+        } else {
+            Self { min: -limit, max: limit }
+        }
+    }
+
+    /// The result of `min = other`, which raises the value to at least `other`.
+    fn at_least(self, other: Self) -> Self {
+        Self { min: self.min.max(other.min), max: self.max.max(other.max) }
+    }
+
+    /// The result of `max = other`, which lowers the value to at most `other`.
+    fn at_most(self, other: Self) -> Self {
+        Self { min: self.min.min(other.min), max: self.max.min(other.max) }
+    }
+
+    fn abs(self) -> Self {
+        if self.min >= 0.0 {
+            self
+        } else if self.max <= 0.0 {
+            Self { min: -self.max, max: -self.min }
+        } else {
+            Self { min: 0.0, max: self.max.max(-self.min) }
+        }
+    }
+
+    /// Apply a rounding function, which never changes the order of two values.
+    fn round_with(self, f: fn(f64) -> f64) -> Self {
+        Self { min: f(self.min), max: f(self.max) }
+    }
+}
+
+/// The declared range of a builtin value such as `age`.
+#[allow(unused_variables)] // `name` is not used by all games
+fn builtin_range(name: &str) -> Option<Interval> {
+    #[cfg(feature = "ck3")]
+    if Game::is_ck3() {
+        return crate::ck3::tables::misc::VALUE_RANGES
+            .iter()
+            .find(|(builtin, _, _)| *builtin == name)
+            .map(|&(_, min, max)| Interval { min, max });
+    }
+    None
+}
+
+struct Evaluator<'a> {
+    data: &'a Everything,
+    /// Whether to report the divisions by zero found along the way.
+    report: bool,
+    /// The script values currently being evaluated, to guard against recursion.
+    stack: Vec<&'static str>,
+    /// The ranges of the script values seen so far.
+    memo: FnvHashMap<&'static str, Interval>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(data: &'a Everything, report: bool) -> Self {
+        Self { data, report, stack: Vec::new(), memo: FnvHashMap::default() }
+    }
+
+    fn bv(&mut self, bv: &BV) -> Interval {
+        match bv {
+            BV::Value(token) => self.token(token),
+            BV::Block(block) => {
+                if matches!(block.iter_items().next(), Some(BlockItem::Value(_))) {
+                    // It's a range like { 1 5 }
+                    block
+                        .iter_values()
+                        .map(|token| self.token(token))
+                        .reduce(Interval::union)
+                        .unwrap_or(Interval::ANY)
+                } else {
+                    self.block(block, Interval::point(0.0))
+                }
+            }
+        }
+    }
+
+    fn token(&mut self, token: &Token) -> Interval {
+        if let Some(value) = token.get_number() {
+            return Interval::point(value);
+        }
+        if let Some(define) = token.strip_prefix("define:") {
+            return match self.data.defines.get_bv(define.as_str()) {
+                Some(BV::Value(value)) => value.get_number().map_or(Interval::ANY, Interval::point),
+                _ => Interval::ANY,
+            };
+        }
+        if self.data.script_values.exists(token.as_str()) {
+            return self.script_value(token.as_str());
+        }
+        let last = token.as_str().rsplit('.').next().unwrap_or_default();
+        builtin_range(&last.to_ascii_lowercase()).unwrap_or(Interval::ANY)
+    }
+
+    fn script_value(&mut self, name: &'static str) -> Interval {
+        if let Some(interval) = self.memo.get(name) {
+            return *interval;
+        }
+        if self.stack.contains(&name) {
+            return Interval::ANY;
+        }
+        let Some(bv) = self.data.script_values.get_bv(name) else { return Interval::ANY };
+        // The script value reports its own problems when it's validated.
+        let report = std::mem::replace(&mut self.report, false);
+        self.stack.push(name);
+        let interval = self.bv(bv);
+        self.stack.pop();
+        self.report = report;
+        self.memo.insert(name, interval);
+        interval
+    }
+
+    /// Evaluate the operators in a script value block, starting from `value`.
+    fn block(&mut self, block: &Block, mut value: Interval) -> Interval {
+        for Field(key, _, bv) in block.iter_fields() {
+            let key_lc = key.as_str().to_ascii_lowercase();
+            let is_yes = bv.get_value().is_some_and(|token| token.lowercase_is("yes"));
+            value = match key_lc.as_str() {
+                "value" => self.bv(bv),
+                "add" => value.add(self.bv(bv)),
+                "subtract" => value.subtract(self.bv(bv)),
+                "multiply" => value.multiply(self.bv(bv)),
+                "divide" => value.divide(self.divisor(key, bv)),
+                "modulo" => value.modulo(self.divisor(key, bv)),
+                "min" => value.at_least(self.bv(bv)),
+                "max" => value.at_most(self.bv(bv)),
+                "round" if is_yes => value.round_with(f64::round),
+                "ceiling" if is_yes => value.round_with(f64::ceil),
+                "floor" if is_yes => value.round_with(f64::floor),
+                "abs" if is_yes => value.abs(),
+                "fixed_range" | "integer_range" => match bv {
+                    BV::Block(block) => {
+                        let min = block.get_field("min").map_or(Interval::ANY, |bv| self.bv(bv));
+                        let max = block.get_field("max").map_or(Interval::ANY, |bv| self.bv(bv));
+                        Interval { min: min.min, max: max.max }
+                    }
+                    BV::Value(_) => Interval::ANY,
+                },
+                "limit" | "desc" | "format" => value,
+                _ => match bv {
+                    BV::Block(block) => {
+                        // The `limit` of an `if` or `else_if` may be what keeps a divisor away
+                        // from zero, so don't report divisions in limited blocks.
+                        let report = self.report;
+                        self.report &= !block.has_key("limit");
+                        let changed = self.block(block, value);
+                        self.report = report;
+                        if key_lc.starts_with("every_")
+                            || key_lc.starts_with("ordered_")
+                            || key_lc.starts_with("random_")
+                        {
+                            // Iterators apply their block any number of times.
+                            if changed == value {
+                                value
+                            } else {
+                                Interval::ANY
+                            }
+                        } else {
+                            // An `if`, `else_if`, `else`, or scope change, which may or may not
+                            // apply.
+                            value.union(changed)
+                        }
+                    }
+                    BV::Value(_) => value,
+                },
+            };
+        }
+        value
+    }
+
+    /// Evaluate a divisor, and report if it may be zero.
+    fn divisor(&mut self, key: &Token, bv: &BV) -> Interval {
+        let divisor = self.bv(bv);
+        if self.report && divisor != Interval::ANY && divisor.contains_zero() {
+            if divisor.is_zero() {
+                let msg = format!("this would {key} by zero");
+                err(ErrorKey::Range).msg(msg).loc(key).push();
+            } else {
+                let msg = format!("this may {key} by zero");
+                let info = divisor
+                    .is_finite()
+                    .then(|| format!("the value ranges from {} to {}", divisor.min, divisor.max));
+                warn(ErrorKey::Range).msg(msg).opt_info(info).loc(key).push();
+            }
+        }
+        divisor
+    }
+
+    /// Evaluate a weight that starts at `value` and is changed by the modifiers in `block`.
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    fn modifiers(&mut self, block: &Block, mut value: Interval) -> Interval {
+        for Field(key, _, bv) in block.iter_fields() {
+            let key_lc = key.as_str().to_ascii_lowercase();
+            value = match key_lc.as_str() {
+                "base" => self.bv(bv),
+                "add" => value.add(self.bv(bv)),
+                "factor" => value.multiply(self.bv(bv)),
+                "min" => value.at_least(self.bv(bv)),
+                "max" => value.at_most(self.bv(bv)),
+                "modifier" => match bv {
+                    BV::Block(block) => self.modifier(block, value),
+                    BV::Value(_) => Interval::ANY,
+                },
+                "first_valid" => match bv {
+                    BV::Block(block) => block
+                        .get_field_blocks("modifier")
+                        .into_iter()
+                        .fold(value, |result, block| result.union(self.modifier(block, value))),
+                    BV::Value(_) => Interval::ANY,
+                },
+                _ if OPAQUE_MODIFIERS.contains(&key_lc.as_str())
+                    || self.data.scripted_modifiers.get(key.as_str()).is_some() =>
+                {
+                    Interval::ANY
+                }
+                _ => value,
+            };
+        }
+        value
+    }
+
+    /// Apply one `modifier` block to `value`. Unless the modifier has no conditions, it may or may
+    /// not apply.
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    fn modifier(&mut self, block: &Block, value: Interval) -> Interval {
+        let mut changed = value;
+        let mut conditional = false;
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Field(Field(key, _, bv)) if key.lowercase_is("add") => {
+                    changed = changed.add(self.bv(bv));
+                }
+                BlockItem::Field(Field(key, _, bv)) if key.lowercase_is("factor") => {
+                    changed = changed.multiply(self.bv(bv));
+                }
+                BlockItem::Field(Field(key, _, _)) if key.lowercase_is("desc") => (),
+                _ => conditional = true,
+            }
+        }
+        if conditional {
+            value.union(changed)
+        } else {
+            changed
+        }
+    }
+}
+
+/// Report the divisions by a value that may be zero in the script value `bv`.
+pub fn check_script_value(bv: &BV, data: &Everything) {
+    Evaluator::new(data, true).bv(bv);
+}
+
+/// Report the `add` and `factor` fields of a `modifier` block that are calculated but always 0.
+pub fn check_modifier(block: &Block, data: &Everything) {
+    let mut evaluator = Evaluator::new(data, false);
+    for Field(key, _, bv) in block.iter_fields() {
+        if !key.lowercase_is("add") && !key.lowercase_is("factor") {
+            continue;
+        }
+        // A literal 0 is clearly meant.
+        if bv.get_value().is_some_and(Token::is_number) {
+            continue;
+        }
+        if evaluator.bv(bv).is_zero() {
+            let msg = format!("this `{key}` is always 0");
+            let info = if key.lowercase_is("add") {
+                "so the modifier does nothing"
+            } else {
+                "so the modifier always makes the weight 0"
+            };
+            warn(ErrorKey::Logic).msg(msg).info(info).loc(key).push();
+        }
+    }
+}
+
+/// Return true iff the weight in `block` is more than just a base value.
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+fn has_modifiers(block: &Block) -> bool {
+    block.iter_fields().any(|Field(key, _, _)| {
+        ["add", "factor", "modifier", "first_valid"].iter().any(|name| key.lowercase_is(name))
+            || OPAQUE_MODIFIERS.iter().any(|name| key.lowercase_is(name))
+    })
+}
+
+/// Report an `ai_chance` block that can never be positive despite its modifiers.
+#[cfg(any(feature = "ck3", feature = "vic3"))] // happens not to be used by imperator
+pub fn check_ai_chance(block: &Block, data: &Everything) {
+    if !has_modifiers(block) {
+        // Something like `base = 0` is clearly meant.
+        return;
+    }
+    let chance = Evaluator::new(data, false).modifiers(block, Interval::point(0.0));
+    if chance.max <= 0.0 {
+        let msg = "this ai_chance can never be positive";
+        let info = "so the AI will never pick this option";
+        warn(ErrorKey::Logic).msg(msg).info(info).loc(block).push();
+    }
+}
+
+/// Report a `random_list` weight that is always zero despite its modifiers.
+#[cfg(feature = "ck3")]
+pub fn check_random_list_weight(key: &Token, weight: f64, block: &Block, data: &Everything) {
+    if !has_modifiers(block) {
+        return;
+    }
+    let weight = Evaluator::new(data, false).modifiers(block, Interval::point(weight));
+    if weight.is_zero() {
+        let msg = "this random_list weight is always zero";
+        let info = "so this entry will never be picked";
+        warn(ErrorKey::Logic).msg(msg).info(info).loc(key).push();
+    }
+}
@@ -0,0 +1,239 @@
+//! Cross-referencing the variables and flags that the script sets and reads.
+//!
+//! The effects and triggers that set, change, or check variables (and, for CK3, character flags)
+//! record each use here while they are validated, together with the scope types the script is in
+//! at that point. After validation, the names that are read but never set anywhere are reported,
+//! as are the variables that are read on a different type of scope than they are set on.
+//!
+//! Names that are set but never read are only reported with the unused items, because they may
+//! well be read from the gui or from localization, which are not looked at here.
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::block::BV;
+use crate::report::{warn, ErrorKey};
+use crate::scopes::Scopes;
+use crate::token::{Loc, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VariableKind {
+    Variable,
+    GlobalVariable,
+    LocalVariable,
+    VariableList,
+    GlobalVariableList,
+    LocalVariableList,
+    #[cfg(feature = "ck3")]
+    CharacterFlag,
+}
+
+impl VariableKind {
+    /// The kind of variable read by a prefix such as `var:`.
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "var" => Some(VariableKind::Variable),
+            "global_var" => Some(VariableKind::GlobalVariable),
+            "local_var" => Some(VariableKind::LocalVariable),
+            _ => None,
+        }
+    }
+
+    /// The kind of variable that the effect or trigger `field` works on, and whether it sets it.
+    /// Removing or clearing a variable counts as reading it.
+    fn from_field(field: &str) -> Option<(Self, bool)> {
+        #[cfg(feature = "ck3")]
+        match field {
+            "add_character_flag" => return Some((VariableKind::CharacterFlag, true)),
+            "has_character_flag" | "remove_character_flag" => {
+                return Some((VariableKind::CharacterFlag, false));
+            }
+            _ => (),
+        }
+
+        let strip = |prefixes: &[&str], suffix: &str| {
+            prefixes.iter().find_map(|prefix| field.strip_prefix(prefix)?.strip_suffix(suffix))
+        };
+        // The part of the name before `variable`, which is empty or says if it's global or local.
+        let (qualifier, is_list, is_set) = if let Some(q) = strip(&["add_to_"], "variable_list") {
+            (q, true, true)
+        } else if let Some(q) = strip(&["remove_list_"], "variable") {
+            (q, true, false)
+        } else if let Some(q) = strip(&["clear_", "has_", "is_target_in_"], "variable_list") {
+            (q, true, false)
+        } else if let Some(q) = field.strip_suffix("variable_list_size") {
+            (q, true, false)
+        } else if let Some(q) = strip(&["set_", "change_", "clamp_", "round_"], "variable") {
+            (q, false, true)
+        } else if let Some(q) = strip(&["has_", "remove_"], "variable") {
+            (q, false, false)
+        } else {
+            return None;
+        };
+        let kind = match (qualifier, is_list) {
+            ("", false) => VariableKind::Variable,
+            ("global_", false) => VariableKind::GlobalVariable,
+            ("local_", false) => VariableKind::LocalVariable,
+            ("", true) => VariableKind::VariableList,
+            ("global_", true) => VariableKind::GlobalVariableList,
+            ("local_", true) => VariableKind::LocalVariableList,
+            // Such as the `dead_character` variables
+            _ => return None,
+        };
+        Some((kind, is_set))
+    }
+
+    /// Whether variables of this kind are stored on the scope that sets them.
+    fn is_on_scope(self) -> bool {
+        matches!(self, VariableKind::Variable | VariableKind::VariableList)
+    }
+}
+
+impl Display for VariableKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let s = match self {
+            VariableKind::Variable => "variable",
+            VariableKind::GlobalVariable => "global variable",
+            VariableKind::LocalVariable => "local variable",
+            VariableKind::VariableList => "variable list",
+            VariableKind::GlobalVariableList => "global variable list",
+            VariableKind::LocalVariableList => "local variable list",
+            #[cfg(feature = "ck3")]
+            VariableKind::CharacterFlag => "character flag",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug)]
+struct Access {
+    kind: VariableKind,
+    name: Token,
+    is_set: bool,
+    /// The scope types the script was in when it used the name.
+    scopes: Scopes,
+}
+
+/// The key of an [`Access`]. Uses at the same location, such as from several calls to the same
+/// scripted effect, share a key and their scopes are merged.
+type AccessKey = (VariableKind, &'static str, Loc, bool);
+
+/// The uses of variables and flags, collected during validation.
+#[derive(Debug, Default)]
+pub struct Variables(RwLock<FnvHashMap<AccessKey, Access>>);
+
+impl Variables {
+    /// Record the use of a variable or flag, if the effect or trigger `field` uses one.
+    pub fn record_field(&self, field: &Token, bv: &BV, scopes: Scopes) {
+        let Some((kind, is_set)) = VariableKind::from_field(field.as_str()) else { return };
+        match bv {
+            BV::Value(name) => self.record(kind, name, is_set, scopes),
+            BV::Block(block) => {
+                #[cfg(feature = "ck3")]
+                let key = if kind == VariableKind::CharacterFlag { "flag" } else { "name" };
+                #[cfg(not(feature = "ck3"))]
+                let key = "name";
+                for name in block.get_field_values(key) {
+                    self.record(kind, name, is_set, scopes);
+                }
+            }
+        }
+    }
+
+    /// Record a read of the variable `name`, such as from `var:name`.
+    pub fn record_read(&self, kind: VariableKind, name: &Token, scopes: Scopes) {
+        self.record(kind, name, false, scopes);
+    }
+
+    fn record(&self, kind: VariableKind, name: &Token, is_set: bool, scopes: Scopes) {
+        // Macro parameters are only known when the macro is used.
+        if name.as_str().contains('$') {
+            return;
+        }
+        let key = (kind, name.as_str(), name.loc, is_set);
+        self.0
+            .write()
+            .unwrap()
+            .entry(key)
+            .and_modify(|access| access.scopes |= scopes)
+            .or_insert_with(|| Access { kind, name: name.clone(), is_set, scopes });
+    }
+
+    /// Forget the uses in the given files, because they are about to be revalidated.
+    pub fn forget_files(&self, paths: &FnvHashSet<PathBuf>) {
+        self.0.write().unwrap().retain(|_, access| !paths.contains(access.name.loc.pathname()));
+    }
+
+    /// Group the uses by kind and name, in a stable order.
+    fn grouped(accesses: &FnvHashMap<AccessKey, Access>) -> Vec<Vec<Access>> {
+        let mut merged: Vec<Access> = accesses
+            .values()
+            .map(|access| Access { name: access.name.clone(), ..*access })
+            .collect();
+        merged.sort_unstable_by(|a, b| {
+            (a.kind, a.name.as_str(), a.name.loc).cmp(&(b.kind, b.name.as_str(), b.name.loc))
+        });
+
+        let mut groups: Vec<Vec<Access>> = Vec::new();
+        for access in merged {
+            match groups.last_mut() {
+                Some(group)
+                    if group[0].kind == access.kind && group[0].name.is(access.name.as_str()) =>
+                {
+                    group.push(access);
+                }
+                _ => groups.push(vec![access]),
+            }
+        }
+        groups
+    }
+
+    /// Report the names that are read but never set, and the variables that are read on a scope
+    /// type that they are never set on.
+    pub fn check(&self) {
+        for group in Self::grouped(&self.0.read().unwrap()) {
+            let kind = group[0].kind;
+            let Some(first_set) = group.iter().find(|access| access.is_set) else {
+                for access in &group {
+                    let msg = format!("{kind} {} is read but never set", access.name);
+                    warn(ErrorKey::UnsetVariable).msg(msg).loc(&access.name).push();
+                }
+                continue;
+            };
+
+            if !kind.is_on_scope() {
+                continue;
+            }
+            let set_scopes = group
+                .iter()
+                .filter(|access| access.is_set)
+                .fold(Scopes::empty(), |scopes, access| scopes | access.scopes);
+            for access in group.iter().filter(|access| !access.is_set) {
+                if !access.scopes.intersects(set_scopes) {
+                    let msg = format!(
+                        "{kind} {} is read on {} but only set on {set_scopes}",
+                        access.name, access.scopes
+                    );
+                    warn(ErrorKey::Scopes)
+                        .msg(msg)
+                        .loc(&access.name)
+                        .loc_msg(&first_set.name, "set here")
+                        .push();
+                }
+            }
+        }
+    }
+
+    /// Report the names that are set but never read.
+    pub fn check_unused(&self) {
+        for group in Self::grouped(&self.0.read().unwrap()) {
+            if group.iter().all(|access| access.is_set) {
+                let msg = format!("{} {} is set but never read", group[0].kind, group[0].name);
+                warn(ErrorKey::UnusedVariable).msg(msg).loc(&group[0].name).push();
+            }
+        }
+    }
+}
@@ -7,10 +7,13 @@ use crate::context::ScopeContext;
 use crate::effect::validate_effect;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
+use crate::item::Item;
 use crate::pdxfile::PdxFile;
+use crate::report::{err, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
+use crate::vic3::data::state_regions::StateRegion;
 
 /// The history files in Vic3 are fairly simple. Files under `common/history/` have `keyword = { effect... }` as top-level blocks,
 /// where the effects from the same keywords are all added together. The keywords seem to be arbitrary, except for GLOBAL which
@@ -80,5 +83,35 @@ impl HistoryEffect {
     pub fn validate(&self, data: &Everything) {
         let mut sc = ScopeContext::new(Scopes::None, &self.key);
         validate_effect(&self.block, data, &mut sc, Tooltipped::No);
+        check_create_state(&self.block, None, data);
+    }
+}
+
+/// Check that the `create_state` effects under `s:STATE_REGION = { ... }` only give out provinces
+/// that are part of that state region.
+fn check_create_state(block: &Block, state_region: Option<&Token>, data: &Everything) {
+    for (key, block) in block.iter_definitions() {
+        if let Some(name) = key.as_str().strip_prefix("s:") {
+            if data.item_exists(Item::StateRegion, name) {
+                check_create_state(block, Some(key), data);
+            }
+        } else if key.is("create_state") {
+            let Some(state_region) = state_region else { continue };
+            let name = state_region.as_str().trim_start_matches("s:");
+            for token in block.get_field_list("owned_provinces").unwrap_or_default() {
+                if data.item_exists(Item::Province, token.as_str())
+                    && !StateRegion::has_province(name, token.as_str(), data)
+                {
+                    let msg = format!("province {token} is not part of state region {name}");
+                    err(ErrorKey::Validation)
+                        .msg(msg)
+                        .loc(&token)
+                        .loc_msg(state_region, "state region")
+                        .push();
+                }
+            }
+        } else {
+            check_create_state(block, state_region, data);
+        }
     }
 }
@@ -1,12 +1,18 @@
+use std::mem::take;
 use std::path::PathBuf;
 
-use fnv::FnvHashSet;
-use image::{DynamicImage, Rgb};
+use fnv::{FnvHashMap, FnvHashSet};
+use image::{DynamicImage, Rgb, RgbImage};
 
+use crate::block::Block;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
+use crate::heightmap::{
+    check_same_size, height_histograms, water_level, HeightHistogram, Heightmap,
+};
 use crate::item::Item;
-use crate::report::{err, report, ErrorKey, Severity};
+use crate::pdxfile::PdxFile;
+use crate::report::{err, report, warn, ErrorKey, Severity};
 use crate::token::Token;
 
 #[derive(Clone, Debug, Default)]
@@ -16,9 +22,51 @@ pub struct Vic3Provinces {
 
     /// Kept and used for error reporting.
     provinces_png: Option<FileEntry>,
+
+    /// The width and height of provinces.png.
+    dimensions: Option<(u32, u32)>,
+
+    /// The `sea_starts` and `lakes` from default.map.
+    /// Unlike land provinces, they don't have to be part of a state region.
+    water: FnvHashSet<Rgb<u8>>,
+
+    /// The `sea_starts` from default.map. Unlike lakes, they should be at the water level.
+    sea: FnvHashSet<Rgb<u8>>,
+
+    /// The provinces.png image itself. It's only kept until `finalize`, because it's big.
+    provinces_img: Option<RgbImage>,
+
+    /// The shared heightmap.png image. Like `provinces_img`, it's only kept until `finalize`.
+    heightmap: Heightmap,
+
+    /// The heights of the pixels of each sea province, taken from the heightmap in `finalize`.
+    sea_heights: FnvHashMap<Rgb<u8>, HeightHistogram>,
+}
+
+/// Parse a province id of the form `xRRGGBB` into the color it has in provinces.png.
+pub fn province_color(key: &str) -> Option<Rgb<u8>> {
+    if key.len() != 7 {
+        return None; // not a valid province id
+    }
+    let hexid = key.strip_prefix('x')?;
+    let r = u8::from_str_radix(&hexid[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hexid[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hexid[4..6], 16).ok()?;
+    Some(Rgb([r, g, b]))
+}
+
+/// Format a color from provinces.png as a province id.
+pub fn province_id(color: Rgb<u8>) -> String {
+    let Rgb([r, g, b]) = color;
+    format!("x{r:02X}{g:02X}{b:02X}")
 }
 
 impl Vic3Provinces {
+    /// Make a handler that checks the sea provinces against the given heightmap.
+    pub fn new(heightmap: Heightmap) -> Self {
+        Self { heightmap, ..Self::default() }
+    }
+
     pub fn verify_exists_implied(&self, key: &str, item: &Token, max_sev: Severity) {
         if !self.exists(key) {
             // TODO: determine the severity of a missing province. Does it cause crashes?
@@ -33,32 +81,85 @@ impl Vic3Provinces {
         if self.provinces_png.is_none() {
             return true;
         }
-        if key.len() != 7 {
-            return false; // not a valid province id
-        }
-        if let Some(hexid) = key.strip_prefix('x') {
-            if let Ok(r) = u8::from_str_radix(&hexid[0..2], 16) {
-                if let Ok(g) = u8::from_str_radix(&hexid[2..4], 16) {
-                    if let Ok(b) = u8::from_str_radix(&hexid[4..6], 16) {
-                        return self.colors.contains(&Rgb([r, g, b]));
+        province_color(key).is_some_and(|color| self.colors.contains(&color))
+    }
+
+    pub fn provinces_png(&self) -> Option<&FileEntry> {
+        self.provinces_png.as_ref()
+    }
+
+    /// The width and height of provinces.png, if it was loaded.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
+    }
+
+    /// Iterate over the colors in provinces.png that are not sea or lake provinces.
+    pub fn iter_land(&self) -> impl Iterator<Item = Rgb<u8>> + '_ {
+        self.colors.iter().copied().filter(|color| !self.water.contains(color))
+    }
+
+    fn load_default_map(&mut self, block: &Block) {
+        for field in &["sea_starts", "lakes"] {
+            for token in block.get_field_list(field).unwrap_or_default() {
+                if let Some(color) = province_color(token.as_str()) {
+                    self.water.insert(color);
+                    if *field == "sea_starts" {
+                        self.sea.insert(color);
                     }
+                } else {
+                    let msg = "expected province id like `xRRGGBB`";
+                    err(ErrorKey::Validation).msg(msg).loc(token).push();
                 }
             }
         }
-        false
     }
 
-    #[allow(clippy::unused_self)]
-    pub fn validate(&self, _data: &Everything) {}
+    pub fn validate(&self, data: &Everything) {
+        if let Some(level) = water_level(data) {
+            self.check_sea_heights(level);
+        }
+    }
+
+    /// Report sea provinces that are mostly on land according to the heightmap.
+    /// The coasts blend into each other, so a few pixels above the water are fine.
+    fn check_sea_heights(&self, level: u8) {
+        let Some(provinces_png) = &self.provinces_png else { return };
+        let mut colors: Vec<Rgb<u8>> = self.sea_heights.keys().copied().collect();
+        colors.sort_unstable_by_key(|color| color.0);
+        for color in colors {
+            let heights = &self.sea_heights[&color];
+            let above = heights.count_above(level);
+            let total = heights.total();
+            if above * 2 > total {
+                let id = province_id(color);
+                let msg =
+                    format!("sea province {id} is mostly above the water level in heightmap.png");
+                let info = format!("{above} of its {total} pixels are higher than the water level");
+                warn(ErrorKey::ProvinceMap).msg(msg).info(info).loc(provinces_png).push();
+            }
+        }
+    }
 }
 
-impl FileHandler<DynamicImage> for Vic3Provinces {
+#[derive(Debug)]
+pub enum FileContent {
+    Provinces(DynamicImage),
+    DefaultMap(Block),
+}
+
+impl FileHandler<FileContent> for Vic3Provinces {
     fn subpath(&self) -> PathBuf {
-        PathBuf::from("map_data/provinces.png")
+        PathBuf::from("map_data")
     }
 
-    fn load_file(&self, entry: &FileEntry) -> Option<DynamicImage> {
-        if entry.path().components().count() == 2 {
+    fn load_file(&self, entry: &FileEntry) -> Option<FileContent> {
+        if entry.path().components().count() != 2 {
+            return None;
+        }
+        if entry.filename() == "default.map" {
+            return PdxFile::read_optional_bom(entry).map(FileContent::DefaultMap);
+        }
+        if entry.filename() == "provinces.png" {
             let img = match image::open(entry.fullpath()) {
                 Ok(img) => img,
                 Err(e) => {
@@ -69,7 +170,7 @@ impl FileHandler<DynamicImage> for Vic3Provinces {
                 }
             };
             if let DynamicImage::ImageRgb8(_) = img {
-                return Some(img);
+                return Some(FileContent::Provinces(img));
             }
             let msg = format!(
                 "`{}` has wrong color format `{:?}`, should be Rgb8",
@@ -82,11 +183,30 @@ impl FileHandler<DynamicImage> for Vic3Provinces {
         None
     }
 
-    fn handle_file(&mut self, entry: &FileEntry, img: DynamicImage) {
-        self.provinces_png = Some(entry.clone());
-        if let DynamicImage::ImageRgb8(img) = img {
-            for pixel in img.pixels() {
-                self.colors.insert(*pixel);
+    fn handle_file(&mut self, entry: &FileEntry, content: FileContent) {
+        match content {
+            FileContent::Provinces(img) => {
+                self.provinces_png = Some(entry.clone());
+                if let DynamicImage::ImageRgb8(img) = img {
+                    for pixel in img.pixels() {
+                        self.colors.insert(*pixel);
+                    }
+                    self.dimensions = Some(img.dimensions());
+                    self.provinces_img = Some(img);
+                }
+            }
+            FileContent::DefaultMap(block) => self.load_default_map(&block),
+        }
+    }
+
+    fn finalize(&mut self) {
+        let heightmap = take(&mut self.heightmap);
+        if let (Some(img), Some((heightmap_png, heightmap)), Some(provinces_png)) =
+            (self.provinces_img.take(), heightmap.get(), &self.provinces_png)
+        {
+            let dimensions = heightmap.dimensions();
+            if check_same_size(heightmap_png, dimensions, provinces_png, img.dimensions()) {
+                self.sea_heights = height_histograms(&img, heightmap, &self.sea);
             }
         }
     }
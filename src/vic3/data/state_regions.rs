@@ -1,10 +1,15 @@
+use fnv::FnvHashMap;
+use image::Rgb;
+
 use crate::block::Block;
 use crate::db::{Db, DbKind};
 use crate::everything::Everything;
 use crate::game::GameFlags;
 use crate::item::{Item, ItemLoader};
+use crate::report::{err, ErrorKey};
 use crate::token::Token;
 use crate::validator::Validator;
+use crate::vic3::data::provinces::{province_color, province_id};
 
 #[derive(Clone, Debug)]
 pub struct StateRegion {}
@@ -17,6 +22,51 @@ impl StateRegion {
     pub fn add(db: &mut Db, key: Token, block: Block) {
         db.add(Item::StateRegion, key, block, Box::new(Self {}));
     }
+
+    pub fn crosscheck(data: &Everything) {
+        // Each province must be part of only one state region.
+        let mut seen: FnvHashMap<Rgb<u8>, Token> = FnvHashMap::default();
+        for (_, block) in data.database.iter_key_block(Item::StateRegion) {
+            for token in block.get_field_list("provinces").unwrap_or_default() {
+                let Some(color) = province_color(token.as_str()) else { continue };
+                if let Some(other) = seen.get(&color) {
+                    let msg = format!("province {token} is part of more than one state region");
+                    err(ErrorKey::Conflict)
+                        .strong()
+                        .msg(msg)
+                        .loc(&token)
+                        .loc_msg(other, "the other one")
+                        .push();
+                } else {
+                    seen.insert(color, token);
+                }
+            }
+        }
+
+        // Each land province must be part of a state region.
+        if let Some(provinces_png) = data.provinces_vic3.provinces_png() {
+            let mut missing: Vec<Rgb<u8>> =
+                data.provinces_vic3.iter_land().filter(|color| !seen.contains_key(color)).collect();
+            missing.sort_unstable_by_key(|color| color.0);
+            for color in missing {
+                let id = province_id(color);
+                let msg = format!("province {id} is not part of any state region");
+                err(ErrorKey::Validation).strong().msg(msg).loc(provinces_png).push();
+            }
+        }
+    }
+
+    /// Return true iff `province` is listed in the `provinces` of state region `key`.
+    pub fn has_province(key: &str, province: &str, data: &Everything) -> bool {
+        let Some(color) = province_color(province) else { return false };
+        data.database.get_key_block(Item::StateRegion, key).is_some_and(|(_, block)| {
+            block
+                .get_field_list("provinces")
+                .unwrap_or_default()
+                .iter()
+                .any(|token| province_color(token.as_str()) == Some(color))
+        })
+    }
 }
 
 impl DbKind for StateRegion {
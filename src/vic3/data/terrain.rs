@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use crate::block::{Block, BV};
 use crate::context::ScopeContext;
 use crate::db::{Db, DbKind};
@@ -6,7 +8,7 @@ use crate::game::GameFlags;
 use crate::item::{Item, ItemLoader};
 use crate::modif::{verify_modif_exists, ModifKinds};
 use crate::pdxfile::PdxEncoding;
-use crate::report::{untidy, warn, ErrorKey, Severity};
+use crate::report::{err, untidy, warn, ErrorKey, Severity};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::util::SmartJoin;
@@ -223,10 +225,34 @@ impl DbKind for TerrainMask {
         if let Some(token) = vd.field_value("filename") {
             let path = key.loc.pathname().smart_join_parent(token.as_str());
             data.verify_exists_implied(Item::File, &path.to_string_lossy(), token);
+            if let Some(fullpath) = data.fileset.fullpath_of(&path) {
+                check_mask_size(token, fullpath, data);
+            }
         }
     }
 }
 
+/// Check that a terrain mask covers the same area as provinces.png. It may have a lower
+/// resolution, but only by the same whole factor in both directions.
+fn check_mask_size(token: &Token, fullpath: &Path, data: &Everything) {
+    let Some((map_width, map_height)) = data.provinces_vic3.dimensions() else { return };
+    let Ok((width, height)) = image::image_dimensions(fullpath) else {
+        // Unreadable images are reported elsewhere.
+        return;
+    };
+    let scales = width > 0
+        && height > 0
+        && map_width % width == 0
+        && map_height % height == 0
+        && map_width / width == map_height / height;
+    if !scales {
+        let map = format!("{map_width}x{map_height}");
+        let msg = format!("terrain mask is {width}x{height} but provinces.png is {map}");
+        let info = "the mask must be the same size as the map, or scaled down evenly";
+        err(ErrorKey::ImageFormat).msg(msg).info(info).loc(token).push();
+    }
+}
+
 fn validate_layer(bv: &BV, data: &Everything) {
     match bv {
         BV::Value(token) => {
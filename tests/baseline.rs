@@ -0,0 +1,50 @@
+use std::fs::{read_to_string, remove_file, write};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use tiger_lib::{finish_baseline, set_baseline, take_reports, Everything, LogReport};
+
+fn check_mod1() -> Vec<LogReport> {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod1");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    take_reports()
+}
+
+#[test]
+fn test_baseline() {
+    let baseline =
+        std::env::temp_dir().join(format!("tiger-baseline-test-{}.json", std::process::id()));
+    _ = remove_file(&baseline);
+
+    // Without a baseline file, all reports are shown and then written to the baseline.
+    set_baseline(&baseline).unwrap();
+    let reports = check_mod1();
+    assert!(!reports.is_empty());
+    finish_baseline().unwrap();
+    let fingerprints: Vec<Value> =
+        serde_json::from_str(&read_to_string(&baseline).unwrap()).unwrap();
+    assert!(fingerprints.iter().any(|fp| fp["key"] == "missing-localization"
+        && fp["message"] == "missing english localization key my_decision_desc"
+        && fp["path"] == "common/decisions/decision.txt"));
+
+    // With the baseline, the same reports are suppressed.
+    set_baseline(&baseline).unwrap();
+    assert!(check_mod1().is_empty());
+
+    // Reports that are not in the baseline are shown.
+    let fewer: Vec<&Value> = fingerprints
+        .iter()
+        .filter(|fp| fp["message"] != "missing english localization key my_decision_desc")
+        .collect();
+    write(&baseline, serde_json::to_string(&fewer).unwrap()).unwrap();
+    set_baseline(&baseline).unwrap();
+    let reports = check_mod1();
+    _ = remove_file(&baseline);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].msg, "missing english localization key my_decision_desc");
+}
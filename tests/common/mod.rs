@@ -0,0 +1,15 @@
+use std::fs::{copy, create_dir_all, read_dir};
+use std::path::Path;
+
+/// Copy the directory `from` and everything in it to `to`.
+pub fn copy_dir(from: &Path, to: &Path) {
+    create_dir_all(to).unwrap();
+    for entry in read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()));
+        } else {
+            copy(entry.path(), to.join(entry.file_name())).unwrap();
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use tiger_lib::{emit_conflicts, set_output_file, Everything};
+
+#[test]
+fn test_conflicts() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/conflicts/main");
+    let output =
+        std::env::temp_dir().join(format!("tiger-conflicts-test-{}.json", std::process::id()));
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    set_output_file(&output).unwrap();
+    emit_conflicts(true);
+
+    let groups: Value = serde_json::from_str(&read_to_string(&output).unwrap()).unwrap();
+    _ = remove_file(&output);
+    let groups = groups.as_array().unwrap();
+    assert_eq!(groups.len(), 3);
+
+    assert_eq!(groups[0]["overridden"], "BASE");
+    assert_eq!(groups[0]["winner"], "PATCH");
+    assert_eq!(
+        groups[0]["files"],
+        json!([
+            { "path": "common/decisions/base_decisions.txt", "replaced_dir": false },
+            { "path": "common/scripted_lists/base_lists.txt", "replaced_dir": true },
+        ])
+    );
+    assert_eq!(groups[0]["items"], json!([]));
+
+    // Files are loaded in order of their names, so the main mod's `shared_effect` is overridden by
+    // the one from PATCH.
+    assert_eq!(groups[1]["overridden"], "BASE");
+    assert_eq!(groups[1]["winner"], "MOD");
+    assert_eq!(groups[2]["overridden"], "MOD");
+    assert_eq!(groups[2]["winner"], "PATCH");
+    let items = groups[2]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["item"], "scripted effect");
+    assert_eq!(items[0]["key"], "shared_effect");
+    assert_eq!(items[0]["overridden"]["path"], "common/scripted_effects/main_effects.txt");
+    assert_eq!(items[0]["winner"]["path"], "common/scripted_effects/patch_effects.txt");
+    assert_eq!(items[0]["winner"]["linenr"], 3);
+}
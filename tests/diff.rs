@@ -0,0 +1,52 @@
+use std::fs::{read_to_string, remove_dir_all, remove_file, write};
+use std::path::Path;
+
+use serde_json::Value;
+
+use tiger_lib::{emit_reports_diff, set_output_file, take_reports, Everything};
+
+mod common;
+use common::copy_dir;
+
+fn check_mod(mod_root: &Path) {
+    let vanilla_dir = Path::new("tests/files/ck3");
+    let mut everything = Everything::new(None, Some(vanilla_dir), mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+}
+
+fn messages(reports: &Value) -> Vec<&str> {
+    reports.as_array().unwrap().iter().map(|r| r["message"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_diff() {
+    let new_root = std::env::temp_dir().join(format!("tiger-diff-test-{}", std::process::id()));
+    let output = new_root.with_extension("json");
+    _ = remove_dir_all(&new_root);
+    copy_dir(Path::new("tests/files/mod1"), &new_root);
+
+    // Shift the existing decisions down, add a decision, and add a missing loca key.
+    let decisions = new_root.join("common/decisions/decision.txt");
+    let contents = read_to_string(&decisions).unwrap();
+    let contents = contents.replacen('\u{feff}', "\u{feff}# Decisions\n\n", 1);
+    write(&decisions, contents + "\nmy_decision3 = {\n    picture = \"gfx/dummy.jpg\"\n}\n")
+        .unwrap();
+    let loca = new_root.join("localization/english/diff_l_english.yml");
+    write(loca, "\u{feff}l_english:\n my_decision_desc:0 \"Description\"\n").unwrap();
+
+    check_mod(Path::new("tests/files/mod1"));
+    let old = take_reports();
+    check_mod(&new_root);
+    set_output_file(&output).unwrap();
+    emit_reports_diff(old, true);
+
+    let diff: Value = serde_json::from_str(&read_to_string(&output).unwrap()).unwrap();
+    _ = remove_file(&output);
+    _ = remove_dir_all(&new_root);
+    let introduced = messages(&diff["introduced"]);
+    let resolved = messages(&diff["resolved"]);
+    assert!(introduced.contains(&"missing english localization key my_decision3"));
+    assert!(!introduced.contains(&"missing english localization key my_decision"));
+    assert_eq!(resolved, ["missing english localization key my_decision_desc"]);
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "ck3")]
+
+use std::fs::{read_to_string, remove_file};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use tiger_lib::{take_reports, Everything};
+
+fn has_edge(graph: &Value, from: &str, to: &str, via: &str) -> bool {
+    graph["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|edge| edge["from"] == from && edge["to"] == to && edge["via"] == via)
+}
+
+#[test]
+fn test_event_graph() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod5");
+    let output = std::env::temp_dir().join(format!("tiger-event-graph-{}", std::process::id()));
+    let json_file = output.with_extension("json");
+    let dot_file = output.with_extension("dot");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    _ = take_reports();
+    everything.write_event_graph(&json_file).unwrap();
+    let reports = take_reports();
+    everything.write_event_graph(&dot_file).unwrap();
+    _ = take_reports();
+
+    let graph: Value = serde_json::from_str(&read_to_string(&json_file).unwrap()).unwrap();
+    let dot = read_to_string(&dot_file).unwrap();
+    _ = remove_file(&json_file);
+    _ = remove_file(&dot_file);
+
+    assert!(has_edge(
+        &graph,
+        "decision:graph_decision",
+        "on_action:graph_on_action",
+        "trigger_event"
+    ));
+    assert!(has_edge(&graph, "on_action:graph_on_action", "event:graph.1", "random_events"));
+    assert!(has_edge(&graph, "event:graph.1", "event:graph.2", "trigger_event"));
+    assert!(has_edge(
+        &graph,
+        "event:graph.2",
+        "scripted_effect:graph_fire_three",
+        "scripted_effect"
+    ));
+    assert!(has_edge(&graph, "scripted_effect:graph_fire_three", "event:graph.3", "trigger_event"));
+    assert!(has_edge(&graph, "event:graph.3", "event:graph.2", "trigger_event"));
+    assert_eq!(graph["edges"].as_array().unwrap().len(), 6);
+    let unreachable = graph["unreachable"].as_array().unwrap();
+    assert!(unreachable.contains(&Value::from("event:graph.4")));
+    assert!(!unreachable.contains(&Value::from("event:graph.1")));
+    let cycles = graph["cycles"].as_array().unwrap();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].as_array().unwrap().len(), 3);
+
+    assert!(dot.starts_with("digraph events {"));
+    assert!(dot.contains("\"event:graph.1\" -> \"event:graph.2\" [label=\"trigger_event\"];"));
+
+    let unreachable: Vec<_> = reports
+        .iter()
+        .filter(|r| r.key.to_string() == "unreachable-event")
+        .filter(|r| r.primary().loc.pathname() == Path::new("events/graph_events.txt"))
+        .collect();
+    assert_eq!(unreachable.len(), 1);
+    assert_eq!(unreachable[0].primary().loc.line, 24);
+    let cycles: Vec<_> = reports.iter().filter(|r| r.key.to_string() == "event-cycle").collect();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].pointers.len(), 3);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "ck3")]
+
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use tiger_lib::Everything;
+
+fn ids(tree: &Value) -> Vec<&str> {
+    tree["characters"].as_array().unwrap().iter().map(|ch| ch["id"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_family_tree() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod8");
+    let output = std::env::temp_dir().join(format!("tiger-family-tree-{}", std::process::id()));
+    let json_file = output.with_extension("json");
+    let dot_file = output.with_extension("dot");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.write_family_tree(&json_file, None).unwrap();
+    let all: Value = serde_json::from_str(&read_to_string(&json_file).unwrap()).unwrap();
+    everything.write_family_tree(&json_file, Some("history_dynasty")).unwrap();
+    let dynasty: Value = serde_json::from_str(&read_to_string(&json_file).unwrap()).unwrap();
+    everything.write_family_tree(&dot_file, Some("house_history_test")).unwrap();
+    let dot = read_to_string(&dot_file).unwrap();
+    _ = remove_file(&json_file);
+    _ = remove_file(&dot_file);
+    assert!(everything.write_family_tree(&json_file, Some("no_such_house")).is_err());
+
+    assert_eq!(ids(&all).len(), 10);
+
+    assert_eq!(ids(&dynasty), vec!["history_test_6", "history_test_7", "history_test_8"]);
+    let gorm = &dynasty["characters"][1];
+    assert_eq!(gorm["name"], "Gorm");
+    assert_eq!(gorm["dynasty"], "history_dynasty");
+    assert_eq!(gorm["house"], "house_history_test");
+    assert_eq!(gorm["birth"], "860.1.1");
+    assert_eq!(gorm["death"], Value::Null);
+    assert_eq!(gorm["father"], "history_test_8");
+    assert_eq!(gorm["mother"], "history_test_6");
+
+    assert!(dot.starts_with("digraph family {"));
+    assert!(dot.contains("subgraph \"cluster_house_history_test\" {"));
+    assert!(dot.contains("\"history_test_7\" [label=\"Gorm (history_test_7)\\n860.1.1 - \""));
+    assert!(dot.contains("\"history_test_6\" -> \"history_test_7\" [style=dashed];"));
+    // Hakon is not of the house, so he is left out.
+    assert!(!dot.contains("history_test_8"));
+}
@@ -0,0 +1,3 @@
+version="1.0"
+name="Base"
+supported_version="1.*"
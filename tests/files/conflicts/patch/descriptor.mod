@@ -0,0 +1,4 @@
+version="1.0"
+name="Patch"
+supported_version="1.*"
+replace_path="common/scripted_lists"
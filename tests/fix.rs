@@ -0,0 +1,55 @@
+use std::fs::{read_to_string, remove_dir_all, remove_file};
+use std::path::Path;
+
+use serde_json::Value;
+
+use tiger_lib::{apply_fixes, emit_reports, set_output_file, take_reports, Everything};
+
+mod common;
+use common::copy_dir;
+
+fn check_mod(mod_root: &Path) {
+    let vanilla_dir = Path::new("tests/files/ck3");
+    let mut everything = Everything::new(None, Some(vanilla_dir), mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+}
+
+#[test]
+fn test_fixes() {
+    let mod_root = std::env::temp_dir().join(format!("tiger-fix-test-{}", std::process::id()));
+    let output = mod_root.with_extension("json");
+    _ = remove_dir_all(&mod_root);
+    copy_dir(Path::new("tests/files/mod4"), &mod_root);
+    let event_file = mod_root.join("events/fixme.txt");
+
+    // The fixes are in the JSON output, as byte ranges.
+    check_mod(&mod_root);
+    set_output_file(&output).unwrap();
+    emit_reports(true);
+    let reports: Value = serde_json::from_str(&read_to_string(&output).unwrap()).unwrap();
+    _ = remove_file(&output);
+    let report = reports
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["message"].as_str().unwrap().starts_with("`save_event_target_as`"))
+        .expect("replaced field report");
+    let fix = &report["fixes"][0];
+    assert_eq!(fix["path"], "events/fixme.txt");
+    assert_eq!(fix["start"], 110);
+    assert_eq!(fix["end"], 130);
+    assert_eq!(fix["replacement"], "save_scope_as");
+
+    // The fixes are applied to the files, and the fixed reports are gone.
+    check_mod(&mod_root);
+    assert_eq!(apply_fixes().unwrap(), 3);
+    let reports = take_reports();
+    let contents = read_to_string(&event_file).unwrap();
+    _ = remove_dir_all(&mod_root);
+    assert!(contents.starts_with('\u{feff}'));
+    assert!(contents.contains("\t\tsave_scope_as = fixme_target\n"));
+    assert!(contents.contains("\t\thidden_effect_new_object = { }\n"));
+    assert!(!reports.iter().any(|r| r.msg == "file must start with a UTF-8 BOM"));
+    assert!(!reports.iter().any(|r| r.msg.starts_with("`save_event_target_as`")));
+}
@@ -0,0 +1,73 @@
+use lazy_static::lazy_static;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tiger_lib::{take_reports, Everything, LogReport};
+
+lazy_static! {
+    static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
+}
+
+fn history_reports() -> Vec<LogReport> {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod8");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    take_reports()
+}
+
+#[test]
+fn test_bookmark_history() {
+    let reports = history_reports();
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+
+    assert_eq!(find("holder history_test_1 of c_history_dead is not alive on 867.1.1"), 1);
+    assert_eq!(find("liege d_history_test of c_history_faithless has no holder on 867.1.1"), 1);
+    assert_eq!(
+        find("history_test_2 is married to both history_test_3 and history_test_4 on 867.1.1"),
+        1
+    );
+    assert_eq!(find("holder history_test_5 of c_history_faithless has no culture on 867.1.1"), 1);
+    assert_eq!(find("holder history_test_5 of c_history_faithless has no faith on 867.1.1"), 1);
+    assert_eq!(find("capital province 2 of c_history_faithless has no culture on 867.1.1"), 1);
+    assert_eq!(find("capital province 1 of c_history_dead has no culture on 867.1.1"), 0);
+    assert_eq!(find("capital province 3 of c_history_unsettled has no history"), 1);
+}
+
+#[test]
+fn test_bookmark_characters() {
+    let reports = history_reports();
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+
+    assert_eq!(find("character history_test_1 is not alive on 867.1.1"), 1);
+    assert_eq!(
+        find("d_history_test is held by no one in history at 867.1.1, not by history_test_5"),
+        1
+    );
+    assert_eq!(find("character type is boy but history_test_5 is 67 years old at 867.1.1"), 1);
+    assert_eq!(find("character type is boy but their portrait is for a female"), 1);
+    assert_eq!(
+        find("bookmark portrait bm_history_alf not defined in common/bookmark_portraits/"),
+        1
+    );
+}
+
+#[test]
+fn test_genealogy() {
+    let reports = history_reports();
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+
+    assert_eq!(
+        find("father history_test_8 was born on 870.1.1, not before history_test_7 on 860.1.1"),
+        1
+    );
+    assert_eq!(find("mother history_test_6 is only 10 when history_test_7 is born on 860.1.1"), 1);
+    assert_eq!(
+        find("mother history_test_10 died on 840.1.1, before history_test_9 was born on 845.1.1"),
+        1
+    );
+}
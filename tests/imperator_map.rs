@@ -0,0 +1,69 @@
+#![cfg(feature = "imperator")]
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use tiger_lib::{take_reports, Everything, LogReport};
+
+lazy_static! {
+    static ref REPORTS: Mutex<Option<Vec<LogReport>>> = Mutex::new(None);
+}
+
+/// Validate mod15 once and return the reports about the given file.
+fn reports_for(pathname: &str) -> Vec<LogReport> {
+    let mut reports = REPORTS.lock().unwrap();
+    let reports = reports.get_or_insert_with(|| {
+        let mod_root = PathBuf::from("tests/files/mod15");
+        let mut everything = Everything::new(None, None, &mod_root, Vec::new()).unwrap();
+        everything.load_all();
+        everything.validate_all();
+        take_reports()
+    });
+    let pathname = Path::new(pathname);
+    reports.iter().filter(|r| r.primary().loc.pathname() == pathname).cloned().collect()
+}
+
+fn count(reports: &[LogReport], msg: &str) -> usize {
+    reports.iter().filter(|r| r.msg == msg).count()
+}
+
+#[test]
+fn test_provinces() {
+    let reports = reports_for("map_data/definition.csv");
+    assert_eq!(count(&reports, "land province 5 does not border any other land province"), 1);
+    // Province 4 is reachable through adjacencies.csv
+    assert_eq!(count(&reports, "land province 4 does not border any other land province"), 0);
+    assert_eq!(count(&reports, "province 5 is not part of any area"), 1);
+    assert_eq!(count(&reports, "province 4 is not part of any area"), 0);
+    // Sea provinces don't need an area
+    assert_eq!(count(&reports, "province 6 is not part of any area"), 0);
+}
+
+#[test]
+fn test_adjacencies() {
+    let reports = reports_for("map_data/adjacencies.csv");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].msg, "province 2 is not a sea zone or river province");
+    assert_eq!(reports[0].primary().loc.line, 3);
+}
+
+#[test]
+fn test_areas_regions() {
+    let reports = reports_for("map_data/areas.txt");
+    assert_eq!(count(&reports, "province 2 is part of more than one area"), 1);
+    assert_eq!(count(&reports, "area area_east is not part of any region"), 1);
+    assert_eq!(count(&reports, "area area_west is not part of any region"), 0);
+}
+
+#[test]
+fn test_setup() {
+    let reports = reports_for("setup/main/00_default.txt");
+    let conflicts: Vec<&LogReport> =
+        reports.iter().filter(|r| r.msg == "province 2 is owned by both ROM and CAR").collect();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].primary().loc.line, 9);
+    assert_eq!(conflicts[0].pointers[1].loc.line, 3);
+    assert_eq!(count(&reports, "province 9 not defined in map_data/definition.csv"), 1);
+    assert_eq!(count(&reports, "province 1 not defined in map_data/definition.csv"), 0);
+}
@@ -0,0 +1,273 @@
+use lazy_static::lazy_static;
+use std::fs::{remove_dir_all, remove_file, write};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json::{json, Value};
+
+use tiger_lib::{take_reports, Everything, FileKind, Item, LspServer};
+
+mod common;
+use common::copy_dir;
+
+lazy_static! {
+    static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
+}
+
+fn frame(message: &Value) -> String {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+fn unframe(mut output: &str) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while let Some((header, rest)) = output.split_once("\r\n\r\n") {
+        let length: usize = header.strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+        messages.push(serde_json::from_str(&rest[..length]).unwrap());
+        output = &rest[length..];
+    }
+    messages
+}
+
+/// Return the diagnostics messages that were last published for the file.
+fn last_published(messages: &[Value], uri: &str) -> Option<Vec<String>> {
+    let published = messages
+        .iter()
+        .rev()
+        .find(|m| m["method"] == "textDocument/publishDiagnostics" && m["params"]["uri"] == uri)?;
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    Some(diagnostics.iter().map(|d| d["message"].as_str().unwrap().to_string()).collect())
+}
+
+#[test]
+fn test_lsp_diagnostics() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let decision =
+        std::env::current_dir().unwrap().join("tests/files/mod1/common/decisions/decision.txt");
+    let decision_uri = format!("file://{}", decision.display());
+
+    let mut input = String::new();
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    input +=
+        &frame(&json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {}}));
+    input += &frame(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didSave",
+        "params": {"textDocument": {"uri": decision_uri}},
+    }));
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "exit"}));
+
+    let mut output = Vec::new();
+    let mut server = LspServer::new(
+        None,
+        Some(PathBuf::from("tests/files/ck3")),
+        Some(PathBuf::from("tests/files/mod1")),
+        Vec::new(),
+    );
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+
+    assert_eq!(messages[0]["id"], 1);
+    assert!(messages[0]["result"]["capabilities"].is_object());
+
+    let unsupported = messages.iter().find(|m| m["id"] == 2).unwrap();
+    assert_eq!(unsupported["error"]["code"], -32601);
+
+    let published: Vec<&Value> = messages
+        .iter()
+        .filter(|m| {
+            m["method"] == "textDocument/publishDiagnostics"
+                && m["params"]["uri"] == decision_uri.as_str()
+        })
+        .collect();
+    // Once after `initialized` and once after `didSave`.
+    assert_eq!(published.len(), 2);
+    let diagnostics = published[1]["params"]["diagnostics"].as_array().unwrap();
+    let missing = diagnostics
+        .iter()
+        .find(|d| d["message"] == "missing english localization key my_decision_desc")
+        .expect("missing loca diagnostic");
+    assert_eq!(missing["code"], "missing-localization");
+    assert_eq!(missing["source"], "ck3-tiger");
+
+    let picture = diagnostics
+        .iter()
+        .find(|d| d["message"] == "file  does not exist")
+        .expect("missing file diagnostic");
+    assert_eq!(picture["range"]["start"]["line"], 6);
+
+    assert_eq!(messages.last().unwrap()["id"], 3);
+}
+
+#[test]
+fn test_lsp_load_failure() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let mut input = String::new();
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "exit"}));
+
+    // No mod root, and the client doesn't send one either.
+    let mut output = Vec::new();
+    let mut server = LspServer::new(None, Some(PathBuf::from("tests/files/ck3")), None, Vec::new());
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+
+    let shown = messages.iter().find(|m| m["method"] == "window/showMessage").unwrap();
+    assert_eq!(shown["params"]["type"], 1);
+    assert!(shown["params"]["message"].as_str().unwrap().contains("workspace root"));
+    assert_eq!(messages.last().unwrap()["id"], 2);
+}
+
+#[test]
+fn test_lsp_incremental() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let mod_root = std::env::temp_dir().join(format!("tiger-lsp-test-{}", std::process::id()));
+    _ = remove_dir_all(&mod_root);
+    copy_dir(Path::new("tests/files/mod1"), &mod_root);
+    let decision_uri =
+        format!("file://{}", mod_root.join("common/decisions/decision.txt").display());
+    let loca = mod_root.join("localization/english/added_l_english.yml");
+    let missing = "missing english localization key my_decision_also";
+
+    let mut server = LspServer::new(
+        None,
+        Some(PathBuf::from("tests/files/ck3")),
+        Some(mod_root.clone()),
+        Vec::new(),
+    );
+
+    let mut input = String::new();
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    let mut output = Vec::new();
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+    let before = last_published(&messages, &decision_uri).unwrap();
+    assert!(before.iter().any(|msg| msg == missing));
+
+    write(&loca, "\u{feff}l_english:\n my_decision_also:0 \"Also\"\n").unwrap();
+    let mut input = String::new();
+    input += &frame(&json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeWatchedFiles",
+        "params": {"changes": [{"uri": format!("file://{}", loca.display()), "type": 1}]},
+    }));
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "exit"}));
+    let mut output = Vec::new();
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+    _ = remove_dir_all(&mod_root);
+
+    let after = last_published(&messages, &decision_uri).expect("decision.txt was revalidated");
+    assert!(!after.iter().any(|msg| msg == missing));
+    assert_eq!(after.len(), before.len() - 1);
+}
+
+#[test]
+fn test_lsp_suppression_removed() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let mod_root =
+        std::env::temp_dir().join(format!("tiger-lsp-suppress-test-{}", std::process::id()));
+    _ = remove_dir_all(&mod_root);
+    copy_dir(Path::new("tests/files/mod3"), &mod_root);
+    let decisions = mod_root.join("common/decisions/suppressed.txt");
+    let decisions_uri = format!("file://{}", decisions.display());
+    let missing = "file gfx/missing.dds does not exist";
+
+    let mut server = LspServer::new(
+        None,
+        Some(PathBuf::from("tests/files/ck3")),
+        Some(mod_root.clone()),
+        Vec::new(),
+    );
+
+    let mut input = String::new();
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    let mut output = Vec::new();
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+    let before = last_published(&messages, &decisions_uri).unwrap();
+    assert!(!before.iter().any(|msg| msg == missing));
+
+    // Drop the suppression comment without moving any lines
+    let contents = std::fs::read_to_string(&decisions).unwrap();
+    write(&decisions, contents.replace(" # tiger-ignore: missing-file", "")).unwrap();
+    let mut input = String::new();
+    input += &frame(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didSave",
+        "params": {"textDocument": {"uri": decisions_uri}},
+    }));
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "exit"}));
+    let mut output = Vec::new();
+    server.run(Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(std::str::from_utf8(&output).unwrap());
+    _ = remove_dir_all(&mod_root);
+
+    let after = last_published(&messages, &decisions_uri).expect("suppressed.txt was revalidated");
+    assert!(after.iter().any(|msg| msg == missing));
+}
+
+#[test]
+fn test_reload_deleted_override() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let mod_root = std::env::temp_dir().join(format!("tiger-reload-test-{}", std::process::id()));
+    _ = remove_dir_all(&mod_root);
+    copy_dir(Path::new("tests/files/mod1"), &mod_root);
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.enable_incremental();
+    everything.load_all();
+    everything.validate_all();
+    _ = take_reports();
+    let event_kind = |everything: &Everything| {
+        everything.iter_keys(Item::Event).find(|key| key.is("non-dup.0001")).map(|key| key.loc.kind)
+    };
+    assert_eq!(event_kind(&everything), Some(FileKind::Mod));
+
+    // Deleting the mod's copy of the file brings back the vanilla one
+    let fullpath = mod_root.join("events/non-dup.txt");
+    remove_file(&fullpath).unwrap();
+    let entry = everything.file_entry(&fullpath).unwrap();
+    everything.reload_files(&[entry]);
+    _ = take_reports();
+    _ = remove_dir_all(&mod_root);
+
+    assert_eq!(event_kind(&everything), Some(FileKind::Vanilla));
+}
+
+#[test]
+fn test_reload_music() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let mod_root = std::env::temp_dir().join(format!("tiger-music-test-{}", std::process::id()));
+    _ = remove_dir_all(&mod_root);
+    copy_dir(Path::new("tests/files/mod1"), &mod_root);
+    std::fs::create_dir(mod_root.join("music")).unwrap();
+    let fullpath = mod_root.join("music/my_music.txt");
+    write(&fullpath, "my_music = { pause_factor = 10 }\n").unwrap();
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.enable_incremental();
+    everything.load_all();
+    everything.validate_all();
+    _ = take_reports();
+
+    // The music files have no directory of their own, but only they should be revalidated
+    let entry = everything.file_entry(&fullpath).unwrap();
+    let revalidated = everything.reload_files(&[entry]);
+    _ = take_reports();
+    _ = remove_dir_all(&mod_root);
+
+    assert!(revalidated.contains(&fullpath));
+    assert!(!revalidated.contains(&mod_root.join("common/decisions/decision.txt")));
+}
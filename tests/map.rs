@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use tiger_lib::{take_reports, Everything, LogReport};
+
+lazy_static! {
+    static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
+}
+
+fn map_reports(mod_root: &str, filename: &str) -> Vec<LogReport> {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from(mod_root);
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let pathname = Path::new("map_data").join(filename);
+    take_reports().into_iter().filter(|r| r.primary().loc.pathname() == pathname).collect()
+}
+
+#[test]
+fn test_adjacencies() {
+    let reports = map_reports("tests/files/mod7", "adjacencies.csv");
+    assert!(reports.iter().all(|r| r.primary().loc.line == 3));
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+    assert_eq!(find("unknown adjacency type `land`"), 1);
+    assert_eq!(find("province 1 is not a sea zone or river province"), 1);
+    assert_eq!(find("start coordinates are not on province 1"), 1);
+    assert_eq!(find("stop coordinates (20, 1) are outside the map"), 1);
+    assert_eq!(reports.len(), 4);
+}
+
+#[test]
+fn test_province_topology() {
+    let reports = map_reports("tests/files/mod7", "definition.csv");
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+    assert_eq!(find("province 4 is split into 2 separate areas"), 1);
+    assert_eq!(find("province 5 has only 2 pixels in provinces.png"), 1);
+    assert_eq!(find("land province 4 does not border any other land province"), 1);
+    assert_eq!(find("land province 5 does not border any other land province"), 1);
+    assert_eq!(find("land province 1 does not border any other land province"), 0);
+    // The fifth one is the sea province on land, from test_heightmap
+    assert_eq!(reports.iter().filter(|r| r.key.to_string() == "province-map").count(), 5);
+
+    let reports = map_reports("tests/files/mod7", "provinces.png");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].msg, "(11, 5) stray pixel of province 4");
+}
+
+#[test]
+fn test_rivers_debug() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod7");
+    let output = std::env::temp_dir().join(format!("tiger-rivers-{}.png", std::process::id()));
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.check_rivers();
+    let pathname = Path::new("map_data/rivers.png");
+    let reports: Vec<LogReport> =
+        take_reports().into_iter().filter(|r| r.primary().loc.pathname() == pathname).collect();
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+    assert_eq!(find("(9, 4) orphan river pixel"), 1);
+    assert_eq!(find("(3, 1) river pixel is out at sea in heightmap.png"), 1);
+    assert_eq!(find("(4, 1) river pixel is out at sea in heightmap.png"), 1);
+    assert_eq!(find("(1, 4) river pixel is higher than the land around it in heightmap.png"), 1);
+    assert_eq!(reports.len(), 5);
+
+    everything.write_rivers_debug(&output).unwrap();
+    let img = image::open(&output).unwrap().to_rgb8();
+    let json = std::fs::read_to_string(output.with_extension("json")).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    std::fs::remove_file(output.with_extension("json")).unwrap();
+
+    assert_eq!(img.dimensions(), (12, 6));
+    assert_eq!(img.get_pixel(9, 4).0, [255, 0, 255]);
+    assert_eq!(img.get_pixel(3, 1).0, [255, 0, 255]);
+    // A correct river keeps its own colors.
+    assert_eq!(img.get_pixel(2, 4).0, [0, 0, 200]);
+
+    let problems: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let problems = problems.as_array().unwrap();
+    assert_eq!(problems.len(), 5);
+    let segment = problems.iter().find(|p| p["kind"] == "orphan-segment").unwrap();
+    let mut pixels: Vec<(u32, u32)> = serde_json::from_value(segment["pixels"].clone()).unwrap();
+    pixels.sort_unstable();
+    assert_eq!(pixels, vec![(2, 1), (3, 1), (4, 1), (5, 1)]);
+    let pixel = problems.iter().find(|p| p["kind"] == "orphan-pixel").unwrap();
+    assert_eq!(pixel["message"], "(9, 4) orphan river pixel");
+}
+
+#[test]
+fn test_heightmap() {
+    let reports = map_reports("tests/files/mod7", "definition.csv");
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+    assert_eq!(find("sea province 6 is mostly above the water level in heightmap.png"), 1);
+    assert_eq!(find("sea province 3 is mostly above the water level in heightmap.png"), 0);
+
+    // The heightmap has the same size as provinces.png, so there is no complaint about that.
+    let reports = map_reports("tests/files/mod7", "heightmap.png");
+    assert!(reports.is_empty());
+}
@@ -0,0 +1,43 @@
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use tiger_lib::{emit_reports_sarif, set_output_file, Everything};
+
+#[test]
+fn test_sarif_output() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod1");
+    let output = std::env::temp_dir().join(format!("tiger-sarif-test-{}.json", std::process::id()));
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    set_output_file(&output).unwrap();
+    emit_reports_sarif();
+
+    let log: Value = serde_json::from_str(&read_to_string(&output).unwrap()).unwrap();
+    _ = remove_file(&output);
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "ck3-tiger");
+    assert!(run["originalUriBaseIds"]["MOD"]["uri"].as_str().unwrap().ends_with("mod1/"));
+
+    let results = run["results"].as_array().unwrap();
+    let missing = results
+        .iter()
+        .find(|r| r["message"]["text"] == "missing english localization key my_decision_desc")
+        .expect("missing loca result");
+    assert_eq!(missing["ruleId"], "missing-localization");
+    let rule_index = missing["ruleIndex"].as_u64().unwrap() as usize;
+    assert_eq!(run["tool"]["driver"]["rules"][rule_index]["id"], "missing-localization");
+    assert_eq!(missing["level"], "warning");
+    let location = &missing["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "common/decisions/decision.txt");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], "MOD");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 1);
+    assert_eq!(location["region"]["endColumn"], 12);
+    assert_eq!(location["region"]["snippet"]["text"], "my_decision = {");
+}
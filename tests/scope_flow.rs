@@ -0,0 +1,43 @@
+use std::path::{Path, PathBuf};
+
+use tiger_lib::{take_reports, Everything};
+
+#[test]
+fn test_scope_flow() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod11");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+
+    let unsaved: Vec<_> = reports
+        .iter()
+        .filter(|r| r.key.to_string() == "unsaved-scope")
+        .filter(|r| r.primary().loc.pathname() == Path::new("events/scope_flow_events.txt"))
+        .collect();
+    let find = |name: &str| {
+        let msg = format!("scope:{name} is read here but nothing saves it before this point");
+        unsaved.iter().find(|r| r.msg == msg).copied()
+    };
+
+    // Saved scopes and the on_action's own scopes are passed on by `trigger_event`.
+    assert!(find("flow_friend").is_none());
+    assert!(find("child").is_none());
+    assert_eq!(find("flow_stranger").unwrap().primary().loc.line, 17);
+    // Temporary scopes are not passed on.
+    assert_eq!(find("flow_temp").unwrap().primary().loc.line, 18);
+    assert!(find("flow_guarded").is_none());
+
+    // An event that nothing fires is only checked against its own saves.
+    let late = find("flow_late").unwrap();
+    assert_eq!(late.primary().loc.line, 29);
+    assert_eq!(late.pointers[1].loc.line, 31);
+    assert!(find("flow_unknown").is_none());
+
+    // One option doesn't see the saves of another, but `after` sees them all.
+    let picked = find("flow_picked").unwrap();
+    assert_eq!(picked.primary().loc.line, 41);
+    assert_eq!(unsaved.len(), 4);
+}
@@ -0,0 +1,47 @@
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use tiger_lib::{take_reports, Everything};
+
+#[test]
+fn test_scope_signatures() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod10");
+    let output = std::env::temp_dir().join(format!("tiger-signatures-{}.json", std::process::id()));
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+    everything.write_scope_signatures(&output).unwrap();
+    let signatures: Value = serde_json::from_str(&read_to_string(&output).unwrap()).unwrap();
+    _ = remove_file(&output);
+
+    let find = |key: &str| {
+        signatures.as_array().unwrap().iter().find(|s| s["key"] == key).unwrap().clone()
+    };
+    let effect = find("signature_gold_effect");
+    assert_eq!(effect["kind"], "scripted_effect");
+    assert_eq!(effect["this"]["scopes"], "character");
+    assert_eq!(effect["this"]["linenr"], 2);
+    assert_eq!(effect["root"], Value::Null);
+    let named = effect["named_scopes"].as_array().unwrap();
+    assert_eq!(named.len(), 1);
+    assert_eq!(named[0]["name"], "signature_target");
+    assert_eq!(named[0]["scopes"], "character");
+    assert_eq!(named[0]["linenr"], 3);
+
+    let trigger = find("signature_title_trigger");
+    assert_eq!(trigger["kind"], "scripted_trigger");
+    assert_eq!(trigger["this"]["scopes"], "landed title");
+    assert_eq!(find("signature_value")["this"]["scopes"], "character");
+
+    // The call from a title scope is reported at the call, and points into the definition.
+    let msg = "`signature_gold_effect` expects scope to be character but scope seems to be \
+               landed title";
+    let report = reports.iter().find(|r| r.msg == msg).unwrap();
+    assert_eq!(report.pointers[0].loc.line, 10);
+    assert!(report.pointers.iter().any(|p| p.loc.line == 2));
+}
@@ -2,7 +2,7 @@ use lazy_static::lazy_static;
 use std::path::PathBuf;
 use std::sync::Mutex;
 
-use tiger_lib::{take_reports, Everything, LogReport};
+use tiger_lib::{report_unused_suppressions, take_reports, Everything, LogReport};
 
 lazy_static! {
     static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
@@ -17,6 +17,7 @@ fn check_mod_helper(modname: &str) -> Vec<LogReport> {
     let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
     everything.load_all();
     everything.validate_all();
+    report_unused_suppressions();
 
     take_reports()
 }
@@ -151,3 +152,39 @@ fn test_mod2() {
     dbg!(&reports);
     assert!(reports.is_empty());
 }
+
+#[test]
+fn test_mod3() {
+    let mut reports = check_mod_helper("mod3");
+
+    let decisions = "common/decisions/suppressed.txt";
+
+    for key in ["my_decision", "my_decision_desc", "my_decision_confirm", "my_decision_tooltip"] {
+        let msg = format!("missing english localization key {key}");
+        take_report(&mut reports, decisions, &msg).expect("unsuppressed decision loca test");
+    }
+    let report = take_report(&mut reports, decisions, "file gfx/also_missing.dds does not exist");
+    report.expect("suppression with other key test");
+    let report = take_report(
+        &mut reports,
+        decisions,
+        "`tiger-ignore: missing-item` did not suppress any report",
+    );
+    let report = report.expect("unused suppression test");
+    assert!(report.pointers[0].loc.line == 7);
+    let report = take_report(
+        &mut reports,
+        decisions,
+        "unknown error key `no-such-key` in tiger-ignore comment",
+    );
+    report.expect("unknown suppression key test");
+
+    let loca = "localization/english/mod3_l_english.yml";
+    let report =
+        take_report(&mut reports, loca, "`tiger-ignore: localization` did not suppress any report");
+    let report = report.expect("unused loca suppression test");
+    assert!(report.pointers[0].loc.line == 4);
+
+    dbg!(&reports);
+    assert!(reports.is_empty());
+}
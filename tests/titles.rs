@@ -0,0 +1,53 @@
+#![cfg(feature = "ck3")]
+
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use tiger_lib::{take_reports, Everything};
+
+#[test]
+fn test_title_hierarchy() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod9");
+    let output = std::env::temp_dir().join(format!("tiger-titles-{}", std::process::id()));
+    let json_file = output.with_extension("json");
+    let csv_file = output.with_extension("csv");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+    let find = |msg: &str| reports.iter().filter(|r| r.msg == msg).count();
+
+    assert_eq!(find("duchy d_orphan is not part of any kingdom"), 1);
+    assert_eq!(find("county c_direct is not part of any duchy"), 1);
+    assert_eq!(find("duchy d_titular is not part of any kingdom"), 0);
+    assert_eq!(find("county c_one is not part of any duchy"), 0);
+    assert_eq!(find("landless title k_landless has de jure titles inside it"), 1);
+    assert_eq!(find("capital c_outside is not de jure part of e_test"), 1);
+    assert_eq!(find("capital c_one is not de jure part of k_test"), 0);
+    assert_eq!(find("capital c_one is not de jure part of d_titular"), 0);
+    assert_eq!(find("province 1 is in both b_one and b_again"), 1);
+
+    everything.write_title_hierarchy(&json_file).unwrap();
+    everything.write_title_hierarchy(&csv_file).unwrap();
+    let tree: Value = serde_json::from_str(&read_to_string(&json_file).unwrap()).unwrap();
+    let csv = read_to_string(&csv_file).unwrap();
+    _ = remove_file(&json_file);
+    _ = remove_file(&csv_file);
+
+    let roots: Vec<_> = tree.as_array().unwrap().iter().map(|t| t["title"].clone()).collect();
+    assert_eq!(roots, vec!["e_test", "d_orphan", "k_lonely", "d_titular", "k_landless"]);
+    let county = &tree[0]["children"][0]["children"][0]["children"][1];
+    assert_eq!(county["title"], "c_two");
+    assert_eq!(county["tier"], "county");
+    assert_eq!(county["children"][1]["province"], "1");
+
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines[0], "title,tier,parent,capital,province,landless");
+    assert_eq!(lines[1], "e_test,empire,,c_outside,,false");
+    assert_eq!(lines[5], "b_one,barony,c_one,,1,false");
+    assert_eq!(lines.len(), 21);
+}
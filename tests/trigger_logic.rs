@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use tiger_lib::{take_reports, Everything, LogReport};
+
+fn find<'a>(reports: &'a [LogReport], msg: &str, line: u32) -> Option<&'a LogReport> {
+    reports.iter().find(|r| r.msg == msg && r.primary().loc.line == line)
+}
+
+#[test]
+fn test_trigger_logic() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod13");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+    let logic: Vec<_> = reports
+        .iter()
+        .filter(|r| r.msg.ends_with("can never be true") || r.msg.ends_with("is always true"))
+        .collect();
+
+    // Contradicting comparisons, in an event and in a scripted trigger
+    let report = find(&reports, "`age < 20` can never be true together with `age > 50`", 7);
+    assert_eq!(report.unwrap().pointers[1].loc.line, 6);
+    let msg = "`age < 20` can never be true together with `age > 50`";
+    assert_eq!(reports.iter().filter(|r| r.msg == msg).count(), 2);
+    let report = find(&reports, "`age > 40` can never be true together with `age = 40`", 47);
+    assert_eq!(report.unwrap().pointers[1].loc.line, 46);
+
+    // Constants hidden in scripted triggers and control blocks
+    let msg = "`logic_test_disabled_trigger = yes` can never be true";
+    let report = find(&reports, msg, 11).unwrap();
+    assert_eq!(report.pointers[1].loc.line, 1);
+    let msg = "`logic_test_disabled_trigger = no` is always true";
+    assert!(find(&reports, msg, 44).is_some());
+    assert!(find(&reports, "this `NOT` can never be true", 15).is_some());
+    assert!(find(&reports, "this `OR` is always true", 40).is_some());
+
+    // A plain `always = no`, an `OR`, and a range of one value are fine
+    assert_eq!(logic.len(), 4);
+}
@@ -0,0 +1,32 @@
+use std::fs::{read_to_string, remove_file};
+use std::path::PathBuf;
+
+use tiger_lib::{emit_reports, set_output_file, take_reports, Everything};
+
+#[test]
+fn test_unused_items() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod6");
+    let output = std::env::temp_dir().join(format!("tiger-unused-test-{}", std::process::id()));
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    _ = take_reports();
+    set_output_file(&output).unwrap();
+    everything.check_unused();
+    emit_reports(false);
+
+    let text = read_to_string(&output).unwrap();
+    _ = remove_file(&output);
+
+    assert!(text.contains("Unused items - scripted effect:"));
+    assert!(text.contains("unused_test_unused_effect"));
+    assert!(!text.contains("unused_test_used_effect"));
+    assert!(text.contains("Unused items - scripted trigger:"));
+    assert!(text.contains("unused_test_unused_trigger"));
+    assert!(!text.contains("unused_test_used_trigger"));
+    assert!(text.contains("Unused items - script value:"));
+    assert!(text.contains("unused_test_unused_value"));
+    assert!(!text.contains("unused_test_used_value"));
+}
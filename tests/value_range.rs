@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use tiger_lib::{take_reports, Everything, LogReport};
+
+fn lines(reports: &[LogReport], msg: &str) -> Vec<u32> {
+    let mut lines: Vec<u32> =
+        reports.iter().filter(|r| r.msg == msg).map(|r| r.primary().loc.line).collect();
+    lines.sort_unstable();
+    lines
+}
+
+#[test]
+fn test_value_range() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod14");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+
+    // Divisions by a script value, a define, and a builtin value
+    assert_eq!(lines(&reports, "this would divide by zero"), vec![8, 26]);
+    // The division at line 38 is guarded by its `if` limit.
+    // The one at line 60 is by a value raised with `min` to a range that includes 0.
+    assert_eq!(lines(&reports, "this may divide by zero"), vec![13, 60]);
+
+    assert_eq!(lines(&reports, "this random_list weight is always zero"), vec![7]);
+    assert_eq!(lines(&reports, "this ai_chance can never be positive"), vec![24]);
+    // The values at lines 53 and 57 are 0 raised or lowered by `min` and `max` to a range
+    assert_eq!(lines(&reports, "this `add` is always 0"), vec![37]);
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use tiger_lib::{take_reports, Everything, LogReport};
+
+fn find(reports: &[LogReport], msg: &str) -> Option<u32> {
+    reports.iter().find(|r| r.msg == msg).map(|r| r.primary().loc.line)
+}
+
+#[test]
+fn test_variables() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod12");
+
+    let mut everything = Everything::new(None, Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    let reports = take_reports();
+    everything.check_unused();
+    let unused = take_reports();
+
+    // Typos in the names of variables and flags
+    assert_eq!(find(&reports, "variable var_test_countr is read but never set"), Some(15));
+    assert_eq!(find(&reports, "character flag var_test_flagg is read but never set"), Some(17));
+    assert_eq!(find(&reports, "variable var_test_counter is read but never set"), None);
+    assert_eq!(find(&reports, "variable list var_test_list is read but never set"), None);
+    assert_eq!(find(&reports, "global variable var_test_global is read but never set"), None);
+
+    // Set on a character, but read on a title
+    let msg = "variable var_test_counter is read on landed title but only set on character";
+    let report = reports.iter().find(|r| r.msg == msg).unwrap();
+    assert_eq!(report.primary().loc.line, 20);
+    assert_eq!(report.pointers[1].loc.line, 6);
+    assert_eq!(reports.iter().filter(|r| r.msg.contains("var_test_counter")).count(), 1);
+
+    assert_eq!(find(&unused, "variable var_test_unread is set but never read"), Some(9));
+    assert_eq!(find(&unused, "variable var_test_counter is set but never read"), None);
+}
@@ -6,8 +6,8 @@ use anyhow::{bail, Result};
 use console::Term;
 
 use tiger_lib::{
-    emit_reports, find_game_directory_steam, find_paradox_directory, set_output_file, Everything,
-    Game, ModMetadata,
+    emit_reports, find_game_directory_steam, find_paradox_directory, report_unused_suppressions,
+    set_output_file, Everything, Game, ModMetadata,
 };
 
 /// Steam's code for Victoria 3
@@ -142,6 +142,7 @@ fn validate_mod(vic3: &Path, modpath: &Path, logdir: &Path) -> Result<()> {
     everything.load_all();
     everything.validate_all();
     everything.check_rivers();
+    report_unused_suppressions();
     emit_reports(false);
 
     // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
@@ -0,0 +1,71 @@
+//! A Language Server Protocol server that publishes vic3-tiger reports as diagnostics.
+//! It talks to the editor over stdin and stdout.
+
+use std::io::{stdin, stdout};
+use std::mem::forget;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use tiger_lib::{find_game_directory_steam, validate_config_file, Game, LspServer, ModMetadata};
+
+/// Steam's code for Victoria 3
+const VIC3_APP_ID: &str = "529340";
+
+/// VIC3 directory under steam library dir
+const VIC3_DIR: &str = "steamapps/common/Victoria 3";
+
+/// A file that should be present if this is the VIC3 directory
+const VIC3_SIGNATURE_FILE: &str = "game/events/titanic_events.txt";
+
+#[derive(Parser)]
+struct Cli {
+    /// Path to folder of mod to check. If not given, the editor's workspace is checked.
+    modpath: Option<PathBuf>,
+    /// Path to Vic3 directory.
+    #[clap(long)]
+    vic3: Option<PathBuf>,
+    /// Path to custom .conf file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let mut args = Cli::parse();
+
+    Game::set(Game::Vic3)?;
+
+    if args.vic3.is_none() {
+        args.vic3 = find_game_directory_steam(VIC3_APP_ID, &PathBuf::from(VIC3_DIR));
+    }
+    if let Some(ref mut vic3) = args.vic3 {
+        eprintln!("Using Vic3 directory: {}", vic3.display());
+        if !vic3.join(VIC3_SIGNATURE_FILE).is_file() {
+            vic3.push("..");
+            if !vic3.join(VIC3_SIGNATURE_FILE).is_file() {
+                bail!("Cannot find Vic3 directory. Please supply it as the --vic3 option.");
+            }
+        }
+    } else {
+        bail!("Cannot find Vic3 directory. Please supply it as the --vic3 option.");
+    }
+
+    args.config = validate_config_file(args.config);
+
+    let mut mod_root = None;
+    let mut replace_paths = Vec::new();
+    if let Some(modpath) = args.modpath {
+        let metadata = ModMetadata::read(&modpath)?;
+        replace_paths = metadata.replace_paths();
+        mod_root = Some(modpath);
+    }
+
+    let mut server = LspServer::new(args.config, args.vic3, mod_root, replace_paths);
+    server.run(stdin().lock(), stdout().lock())?;
+
+    // Properly dropping the server takes a noticeable amount of time, and we're exiting anyway.
+    forget(server);
+
+    Ok(())
+}
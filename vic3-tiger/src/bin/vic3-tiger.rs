@@ -1,12 +1,14 @@
 use std::mem::forget;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
 use clap::Parser;
 
 use tiger_lib::{
-    disable_ansi_colors, emit_reports, find_game_directory_steam, set_show_loaded_mods,
-    set_show_vanilla, validate_config_file, Everything, Game, ModMetadata,
+    apply_fixes, disable_ansi_colors, emit_conflicts, emit_reports, emit_reports_diff,
+    emit_reports_sarif, find_game_directory_steam, finish_baseline, report_unused_suppressions,
+    set_baseline, set_show_loaded_mods, set_show_vanilla, take_reports, validate_config_file,
+    Everything, Game, LogReport, ModMetadata,
 };
 
 /// Steam's code for Victoria 3
@@ -37,9 +39,39 @@ struct Cli {
     /// Output the reports in JSON format
     #[clap(long)]
     json: bool,
+    /// Output the reports in SARIF 2.1.0 format
+    #[clap(long, conflicts_with = "json")]
+    sarif: bool,
+    /// Compare with another version of the mod, and only show the reports that were introduced
+    /// or resolved since then
+    #[clap(long, conflicts_with_all = ["sarif", "baseline", "fix"])]
+    diff: Option<PathBuf>,
+    /// Only show reports that are not in this baseline file.
+    /// If the file does not exist yet, it is created from the current reports.
+    #[clap(long)]
+    baseline: Option<PathBuf>,
+    /// Apply the suggested fixes to the mod files. The reports that get fixed are not shown.
+    #[clap(long)]
+    fix: bool,
     /// Warn about items that are defined but unused.
     #[clap(long)]
     unused: bool,
+    /// List the files and items of loaded mods that are overridden by other mods
+    #[clap(long)]
+    conflicts: bool,
+    /// Write the graph of which events, on_actions, and other items fire which events to this
+    /// file, and warn about events that are never fired. Use a `.dot` file for Graphviz format,
+    /// or any other name for JSON.
+    #[clap(long)]
+    event_graph: Option<PathBuf>,
+    /// Write a copy of rivers.png to this file with the river problems marked in magenta, and a
+    /// JSON list of the problems and their pixel coordinates next to it.
+    #[clap(long)]
+    rivers_debug: Option<PathBuf>,
+    /// Write what each scripted trigger, scripted effect, script value, and scripted modifier
+    /// needs from its callers' scopes to this file, as JSON.
+    #[clap(long)]
+    scope_signatures: Option<PathBuf>,
     /// Omit color from the output.
     #[clap(long)]
     no_color: bool,
@@ -95,7 +127,7 @@ fn main() -> Result<()> {
     }
 
     if args.unused {
-        eprintln!("Showing warnings for unused localization. There will be many false positives.");
+        eprintln!("Showing warnings for unused items. There will be many false positives.");
     }
 
     if args.no_color {
@@ -116,9 +148,12 @@ fn main() -> Result<()> {
     // Print a blank line between the preamble and the first report:
     eprintln!();
 
+    if let Some(baseline) = &args.baseline {
+        set_baseline(baseline)?;
+    }
     everything.load_output_settings(true);
     everything.load_config_filtering_rules();
-    if !args.json {
+    if !args.json && !args.sarif {
         emit_reports(false);
     }
 
@@ -134,16 +169,61 @@ fn main() -> Result<()> {
     if args.show_mods {
         set_show_loaded_mods(true);
     }
+    let base_reports = args.diff.as_deref().map(|base| validate_base(&args, base)).transpose()?;
     everything.load_all();
     everything.validate_all();
     everything.check_rivers();
-    emit_reports(args.json);
+    if let Some(event_graph) = &args.event_graph {
+        everything.write_event_graph(event_graph)?;
+    }
+    if let Some(rivers_debug) = &args.rivers_debug {
+        everything.write_rivers_debug(rivers_debug)?;
+    }
+    if let Some(scope_signatures) = &args.scope_signatures {
+        everything.write_scope_signatures(scope_signatures)?;
+    }
+    report_unused_suppressions();
+    if args.fix {
+        let applied = apply_fixes()?;
+        eprintln!("Applied {applied} fixes to the mod files.");
+    }
+    if let Some(base_reports) = base_reports {
+        emit_reports_diff(base_reports, args.json);
+    } else if args.sarif {
+        emit_reports_sarif();
+    } else {
+        emit_reports(args.json);
+    }
+    finish_baseline()?;
     if args.unused {
         everything.check_unused();
     }
+    if args.conflicts {
+        emit_conflicts(args.json);
+    }
 
     // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
     forget(everything);
 
     Ok(())
 }
+
+/// Validate the other version of the mod given with `--diff`, and return its reports.
+/// This uses the same settings and report filter as the main validation.
+fn validate_base(args: &Cli, base: &Path) -> Result<Vec<LogReport>> {
+    let metadata = ModMetadata::read(base)?;
+    eprintln!("Comparing with mod directory: {}", metadata.modpath().display());
+    let mut everything = Everything::new(
+        args.config.as_deref(),
+        args.vic3.as_deref(),
+        base,
+        metadata.replace_paths(),
+    )?;
+    everything.load_all();
+    everything.validate_all();
+    everything.check_rivers();
+    report_unused_suppressions();
+    let reports = take_reports();
+    forget(everything);
+    Ok(reports)
+}